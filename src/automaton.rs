@@ -0,0 +1,588 @@
+use rayon::prelude::*;
+use std::mem;
+use std::ops::{Index, IndexMut};
+
+/// The 8 directions around a cell as `(dx, dy)`, clockwise from north west
+pub const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+/// Dense rectangular grid of cells stored row by row. Every lookup is bounds checked, so walking off an edge gives
+/// `None` rather than wrapping around or landing on the next row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// A `width` by `height` grid with every cell set to `fill`
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Grid made from rows of cells, top row first. Returns `None` if the rows aren't all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+
+        Some(Grid {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Grid made from rows of cells, top row first, as wide as the longest row. Shorter rows are padded out with
+    /// `fill`.
+    pub fn from_ragged_rows(rows: Vec<Vec<T>>, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        let height = rows.len();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * height);
+        for mut row in rows {
+            row.resize(width, fill.clone());
+            cells.extend(row);
+        }
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Every cell, row by row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Index into `cells` of the cell at `(x, y)`, if it is on the grid
+    pub fn index_of(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    /// Position of the cell at `index` into `cells`
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    /// The position `(dx, dy)` away from `(x, y)`, if it is on the grid
+    pub fn offset(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let x = (x as isize).checked_add(dx)?;
+        let y = (y as isize).checked_add(dy)?;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            None
+        } else {
+            Some((x as usize, y as usize))
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.index_of(x, y).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.index_of(x, y).map(move |index| &mut self.cells[index])
+    }
+
+    /// Every row of cells, top row first
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        // `chunks` panics on a width of 0, and a grid with no columns has no cells to show anyway
+        self.cells.chunks(self.width.max(1)).take(self.height)
+    }
+
+    /// How many cells match `predicate`
+    pub fn count<P: Fn(&T) -> bool>(&self, predicate: P) -> usize {
+        self.cells.iter().filter(|cell| predicate(cell)).count()
+    }
+
+    /// A grid of the same shape with `f` applied to every cell
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).expect("Position is outside the grid")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y).expect("Position is outside the grid")
+    }
+}
+
+/// Which cells each cell looks at when working out its next state. Neighborhoods are worked out once, from the grid
+/// an `Automaton` starts with, so they can depend on cells that never change but not on cells that do.
+///
+/// Any `Fn(&Grid<T>, usize, usize, &mut Vec<usize>)` is a neighborhood too, pushing the `cells` indices of the
+/// neighbors of `(x, y)`.
+pub trait Neighborhood<T> {
+    /// Push the `cells` index of every neighbor of `(x, y)` onto `neighbors`
+    fn neighbors(&self, grid: &Grid<T>, x: usize, y: usize, neighbors: &mut Vec<usize>);
+}
+
+impl<T, F> Neighborhood<T> for F
+where
+    F: Fn(&Grid<T>, usize, usize, &mut Vec<usize>),
+{
+    fn neighbors(&self, grid: &Grid<T>, x: usize, y: usize, neighbors: &mut Vec<usize>) {
+        self(grid, x, y, neighbors)
+    }
+}
+
+/// The 8 cells touching a cell, fewer on the edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adjacent;
+
+impl<T> Neighborhood<T> for Adjacent {
+    fn neighbors(&self, grid: &Grid<T>, x: usize, y: usize, neighbors: &mut Vec<usize>) {
+        Radius(1).neighbors(grid, x, y, neighbors)
+    }
+}
+
+/// Every other cell in the square reaching this many cells out in each direction. `Radius(1)` is `Adjacent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Radius(pub usize);
+
+impl<T> Neighborhood<T> for Radius {
+    fn neighbors(&self, grid: &Grid<T>, x: usize, y: usize, neighbors: &mut Vec<usize>) {
+        let (radius, row_end, column_end) = (self.0, grid.height(), grid.width());
+        for ny in y.saturating_sub(radius)..(y.saturating_add(radius + 1)).min(row_end) {
+            for nx in x.saturating_sub(radius)..(x.saturating_add(radius + 1)).min(column_end) {
+                if (nx, ny) != (x, y) {
+                    neighbors.push(ny * grid.width() + nx);
+                }
+            }
+        }
+    }
+}
+
+/// The first cell in each of the 8 directions that can't be seen through, skipping over every cell `transparent`
+/// holds for
+#[derive(Debug, Clone, Copy)]
+pub struct LineOfSight<F>(pub F);
+
+impl<T, F: Fn(&T) -> bool> Neighborhood<T> for LineOfSight<F> {
+    fn neighbors(&self, grid: &Grid<T>, x: usize, y: usize, neighbors: &mut Vec<usize>) {
+        for (dx, dy) in DIRECTIONS.iter() {
+            let mut position = grid.offset(x, y, *dx, *dy);
+            while let Some((nx, ny)) = position {
+                if !(self.0)(&grid[(nx, ny)]) {
+                    neighbors.push(ny * grid.width() + nx);
+                    break;
+                }
+                position = grid.offset(nx, ny, *dx, *dy);
+            }
+        }
+    }
+}
+
+/// The neighbors of one cell, as handed to a `Rule`
+#[derive(Debug, Clone)]
+pub struct Neighbors<'a, T> {
+    cells: &'a [T],
+    indices: std::slice::Iter<'a, usize>,
+}
+
+impl<'a, T> Iterator for Neighbors<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.indices.next().map(|index| &self.cells[*index])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Neighbors<'a, T> {}
+
+/// How a cell changes from one generation to the next, given its own state and its neighbors'. Any
+/// `Fn(&T, Neighbors<'_, T>) -> T` is a rule too.
+pub trait Rule<T> {
+    fn next(&self, cell: &T, neighbors: Neighbors<'_, T>) -> T;
+}
+
+impl<T, F> Rule<T> for F
+where
+    F: Fn(&T, Neighbors<'_, T>) -> T,
+{
+    fn next(&self, cell: &T, neighbors: Neighbors<'_, T>) -> T {
+        self(cell, neighbors)
+    }
+}
+
+/// A cycle the automaton fell into. A `period` of 1 means the grid stopped changing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// First generation of the cycle
+    pub start: usize,
+    /// How many generations until the grid repeats
+    pub period: usize,
+}
+
+impl Cycle {
+    pub fn is_stable(&self) -> bool {
+        self.period == 1
+    }
+}
+
+/// Runs a `Rule` over every cell of a `Grid` at once, generation after generation. Each cell's neighbors are found
+/// once up front and kept as flat lists of indices, and each generation is written into a second buffer that is
+/// swapped in afterwards, so a step never allocates and can be split across rayon threads.
+#[derive(Debug, Clone)]
+pub struct Automaton<T, R> {
+    grid: Grid<T>,
+    /// Where the next generation is written, holds stale cells between steps
+    buffer: Vec<T>,
+    /// `neighbors[starts[i]..starts[i + 1]]` are the neighbors of cell `i`
+    starts: Vec<usize>,
+    neighbors: Vec<usize>,
+    rule: R,
+    generation: usize,
+    parallel: bool,
+}
+
+impl<T, R> Automaton<T, R>
+where
+    T: Clone + PartialEq + Send + Sync,
+    R: Rule<T> + Sync,
+{
+    pub fn new<N: Neighborhood<T>>(grid: Grid<T>, neighborhood: &N, rule: R) -> Self {
+        let mut starts = Vec::with_capacity(grid.len() + 1);
+        let mut neighbors = Vec::new();
+        starts.push(0);
+        for index in 0..grid.len() {
+            let (x, y) = grid.position(index);
+            neighborhood.neighbors(&grid, x, y, &mut neighbors);
+            starts.push(neighbors.len());
+        }
+
+        Automaton {
+            buffer: grid.cells.clone(),
+            grid,
+            starts,
+            neighbors,
+            rule,
+            generation: 0,
+            parallel: false,
+        }
+    }
+
+    /// Use rayon for the steps taken by `run_until_stable` and `find_cycle`
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    pub fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    /// How many steps have been taken
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The neighbors of the cell at `index` into `cells`, as `cells` indices
+    pub fn neighbors_of(&self, index: usize) -> &[usize] {
+        &self.neighbors[self.starts[index]..self.starts[index + 1]]
+    }
+
+    fn next_cell(&self, cells: &[T], index: usize) -> T {
+        let neighbors = Neighbors {
+            cells,
+            indices: self.neighbors_of(index).iter(),
+        };
+        self.rule.next(&cells[index], neighbors)
+    }
+
+    /// Write the generation after `from` into `to`, returning how many cells changed
+    fn step_cells(&self, from: &[T], to: &mut [T]) -> usize {
+        let mut changes = 0;
+        for (index, next) in to.iter_mut().enumerate() {
+            *next = self.next_cell(from, index);
+            if *next != from[index] {
+                changes += 1;
+            }
+        }
+        changes
+    }
+
+    fn par_step_cells(&self, from: &[T], to: &mut [T]) -> usize {
+        to.par_iter_mut()
+            .enumerate()
+            .map(|(index, next)| {
+                *next = self.next_cell(from, index);
+                (*next != from[index]) as usize
+            })
+            .sum()
+    }
+
+    /// Move on one generation, returning how many cells changed
+    pub fn step(&mut self) -> usize {
+        let mut buffer = mem::take(&mut self.buffer);
+        let changes = self.step_cells(&self.grid.cells, &mut buffer);
+        self.buffer = mem::replace(&mut self.grid.cells, buffer);
+        self.generation += 1;
+        changes
+    }
+
+    /// Move on one generation with the cells split between rayon threads, returning how many cells changed
+    pub fn par_step(&mut self) -> usize {
+        let mut buffer = mem::take(&mut self.buffer);
+        let changes = self.par_step_cells(&self.grid.cells, &mut buffer);
+        self.buffer = mem::replace(&mut self.grid.cells, buffer);
+        self.generation += 1;
+        changes
+    }
+
+    fn advance(&mut self) -> usize {
+        if self.parallel {
+            self.par_step()
+        } else {
+            self.step()
+        }
+    }
+
+    /// Step until a generation changes nothing and return the generation the grid settled at, or `None` if it is still
+    /// changing after `max_steps` more steps
+    pub fn run_until_stable(&mut self, max_steps: usize) -> Option<usize> {
+        for _ in 0..max_steps {
+            if self.advance() == 0 {
+                return Some(self.generation - 1);
+            }
+        }
+        None
+    }
+
+    /// Find the cycle the grid falls into from its current generation, using Brent's algorithm so only a few copies
+    /// of the grid are ever kept, however long the cycle. Afterwards the automaton is left at the first generation of
+    /// the cycle. Returns `None` if no cycle turns up within `max_steps` steps, leaving the automaton wherever the
+    /// search got to.
+    pub fn find_cycle(&mut self, max_steps: usize) -> Option<Cycle> {
+        let origin = self.generation;
+        let initial = self.grid.cells.clone();
+
+        // Find the period: the tortoise waits at each power of two while the hare runs ahead looking for it
+        let mut tortoise = initial.clone();
+        let (mut power, mut period) = (1, 1);
+        self.advance();
+        while self.grid.cells != tortoise {
+            if self.generation - origin >= max_steps {
+                return None;
+            }
+            if power == period {
+                tortoise.clone_from(&self.grid.cells);
+                power *= 2;
+                period = 0;
+            }
+            self.advance();
+            period += 1;
+        }
+
+        // Find the start: one copy `period` generations ahead of another first meets it at the start of the cycle
+        let mut behind = initial;
+        let mut ahead = behind.clone();
+        let mut scratch = behind.clone();
+        for _ in 0..period {
+            self.step_cells(&ahead, &mut scratch);
+            mem::swap(&mut ahead, &mut scratch);
+        }
+        let mut start = origin;
+        while behind != ahead {
+            self.step_cells(&behind, &mut scratch);
+            mem::swap(&mut behind, &mut scratch);
+            self.step_cells(&ahead, &mut scratch);
+            mem::swap(&mut ahead, &mut scratch);
+            start += 1;
+        }
+
+        self.grid.cells = behind;
+        self.generation = start;
+        Some(Cycle { start, period })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Conway's game of life, on a grid of `true` for live cells
+    fn life(cell: &bool, neighbors: Neighbors<'_, bool>) -> bool {
+        match neighbors.filter(|alive| **alive).count() {
+            3 => true,
+            2 => *cell,
+            _ => false,
+        }
+    }
+
+    fn grid(rows: &[&str]) -> Grid<bool> {
+        Grid::from_rows(
+            rows.iter()
+                .map(|row| row.chars().map(|c| c == '#').collect())
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn grid_bounds() {
+        let mut cells = Grid::new(3, 2, 0);
+        assert_eq!((cells.width(), cells.height(), cells.len()), (3, 2, 6));
+        cells[(2, 1)] = 5;
+        assert_eq!(cells.cells(), &[0, 0, 0, 0, 0, 5]);
+        assert_eq!(cells.get(3, 0), None);
+        assert_eq!(cells.offset(0, 0, -1, 0), None);
+        assert_eq!(cells.offset(0, 0, 2, 1), Some((2, 1)));
+        assert_eq!(cells.offset(2, 1, 1, -1), None);
+        assert_eq!(cells.position(4), (1, 1));
+        assert_eq!(
+            cells.rows().collect::<Vec<_>>(),
+            vec![&[0, 0, 0], &[0, 0, 5]]
+        );
+
+        assert_eq!(Grid::from_rows(vec![vec![1, 2], vec![3]]), None);
+        let ragged = Grid::from_ragged_rows(vec![vec![1, 2], vec![3], vec![]], 0);
+        assert_eq!((ragged.width(), ragged.height()), (2, 3));
+        assert_eq!(ragged.cells(), &[1, 2, 3, 0, 0, 0]);
+        assert!(Grid::<u8>::from_rows(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn neighborhoods() {
+        let cells = grid(&["#..#", "....", "#.##"]);
+        let mut neighbors = Vec::new();
+        Adjacent.neighbors(&cells, 0, 0, &mut neighbors);
+        assert_eq!(neighbors, vec![1, 4, 5]);
+
+        neighbors.clear();
+        Radius(2).neighbors(&cells, 1, 1, &mut neighbors);
+        assert_eq!(neighbors.len(), 11);
+
+        // The first live cell in each direction, the corner at (0, 0) sees nothing to its north west
+        neighbors.clear();
+        LineOfSight(|alive: &bool| !*alive).neighbors(&cells, 0, 0, &mut neighbors);
+        assert_eq!(neighbors, vec![3, 10, 8]);
+
+        // Plain functions work too, here only the cells directly above and below
+        let vertical = |grid: &Grid<bool>, x: usize, y: usize, neighbors: &mut Vec<usize>| {
+            for dy in &[-1, 1] {
+                if let Some((x, y)) = grid.offset(x, y, 0, *dy) {
+                    neighbors.push(grid.index_of(x, y).unwrap());
+                }
+            }
+        };
+        let automaton = Automaton::new(cells, &vertical, life);
+        assert_eq!(automaton.neighbors_of(5), &[1, 9]);
+        assert_eq!(automaton.neighbors_of(0), &[4]);
+    }
+
+    #[test]
+    fn blinker_cycles() {
+        let blinker = grid(&[".....", "..#..", "..#..", "..#..", "....."]);
+        let mut automaton = Automaton::new(blinker.clone(), &Adjacent, life);
+        assert_eq!(automaton.step(), 4);
+        assert_eq!(
+            automaton.grid(),
+            &grid(&[".....", ".....", ".###.", ".....", "....."])
+        );
+        assert_eq!(automaton.par_step(), 4);
+        assert_eq!(automaton.grid(), &blinker);
+        assert_eq!(automaton.generation(), 2);
+
+        assert_eq!(automaton.run_until_stable(10), None);
+        assert_eq!(
+            automaton.find_cycle(10),
+            Some(Cycle {
+                start: 12,
+                period: 2
+            })
+        );
+    }
+
+    #[test]
+    fn settles_after_a_transient() {
+        // Three cells of a block fill in the fourth, which then never changes
+        let corner = grid(&["....", ".##.", ".#..", "...."]);
+        let mut automaton = Automaton::new(corner.clone(), &Adjacent, life);
+        let cycle = automaton.find_cycle(10).unwrap();
+        assert_eq!(
+            cycle,
+            Cycle {
+                start: 1,
+                period: 1
+            }
+        );
+        assert!(cycle.is_stable());
+        assert_eq!(automaton.generation(), 1);
+        assert_eq!(automaton.grid().count(|alive| *alive), 4);
+
+        let mut automaton = Automaton::new(corner, &Adjacent, life).with_parallel(true);
+        assert_eq!(automaton.run_until_stable(10), Some(1));
+
+        // Everything dies out, which is stable too
+        let everything_dies = grid(&["#...", "...#"]);
+        let mut automaton = Automaton::new(everything_dies.clone(), &Adjacent, life);
+        assert_eq!(automaton.find_cycle(1), None);
+        let mut automaton = Automaton::new(everything_dies, &Adjacent, life);
+        assert_eq!(
+            automaton.find_cycle(2),
+            Some(Cycle {
+                start: 1,
+                period: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{ParseError, ParseErrorKind};
+
+/// Reads through part of a puzzle input one character at a time, for the days that need a hand written parser.
+/// Errors are located within the whole input, so they point at the right line and column however the text being read
+/// was cut out of it.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    day: u8,
+    /// The whole input, used to locate errors
+    input: &'a str,
+    /// The text being read, a slice of `input`
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// A cursor at the start of `text`, which should be a slice of `input` the same way `ParseError::new` wants its
+    /// fragment to be
+    pub fn new(day: u8, input: &'a str, text: &'a str) -> Self {
+        Cursor {
+            day,
+            input,
+            text,
+            pos: 0,
+        }
+    }
+
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Everything being read, including what has already been read
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Byte offset of the next character within `text`
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Everything that hasn't been read yet
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    /// What has been read since `start`, an earlier `pos`
+    pub fn since(&self, start: usize) -> &'a str {
+        &self.text[start..self.pos]
+    }
+
+    /// Move on `len` bytes
+    pub fn advance(&mut self, len: usize) {
+        self.pos += len;
+    }
+
+    /// Move back to `pos`, an earlier `pos`
+    pub fn rewind(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// An error for this cursor's day pointing at the next character, or at the end of the text if it has all been
+    /// read
+    pub fn error(&self, kind: ParseErrorKind) -> ParseError {
+        let rest = self.rest();
+        let fragment = match rest.chars().next() {
+            Some(c) => &rest[..c.len_utf8()],
+            None => rest,
+        };
+        ParseError::new(self.day, self.input, fragment, kind)
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Consume `expected` if it is the next non-whitespace character
+    pub fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fail if there is anything other than whitespace left
+    pub fn end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedChar(c))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading() {
+        let input = "key = [1,  é]\nnext";
+        let mut cursor = Cursor::new(7, input, &input[6..14]);
+        assert_eq!(cursor.peek(), Some('['));
+        assert!(!cursor.eat('{'));
+        assert!(cursor.eat('['));
+        let start = cursor.pos();
+        cursor.advance(1);
+        assert_eq!(cursor.since(start), "1");
+        assert!(cursor.eat(','));
+        assert_eq!(cursor.rest(), "  é]");
+
+        // Errors point at the next character, after any whitespace `end` skipped
+        let err = cursor.end().unwrap_err();
+        assert_eq!(
+            (err.day, err.line, err.column, err.text.as_str(), err.kind),
+            (7, 1, 12, "é", ParseErrorKind::UnexpectedChar('é'))
+        );
+
+        cursor.rewind(start);
+        assert_eq!(cursor.peek(), Some('1'));
+        cursor.advance(cursor.rest().len());
+        assert_eq!(cursor.end(), Ok(()));
+        let err = cursor.error(ParseErrorKind::Missing("value"));
+        assert_eq!((err.line, err.column, err.text.as_str()), (1, 14, ""));
+    }
+}
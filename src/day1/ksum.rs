@@ -0,0 +1,432 @@
+use fnv::FnvHashMap;
+use rayon::prelude::*;
+
+/// One combination of `k` values that adds up to the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KSum {
+    /// Indexes into the searched values, in increasing order
+    pub indices: Vec<usize>,
+    /// Product of the values at `indices`, `None` if it doesn't fit in an `i128`
+    pub product: Option<i128>,
+}
+
+/// How `find_k_sum_with` searches for combinations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Sort the values, fix all but the last two values of a combination and close in on the last two from both ends
+    /// of the remaining values. Sorting also lets us skip any prefix that can't possibly reach the target.
+    TwoPointer,
+    /// Fix all but the last two values of a combination and find the last two with a single pass over the remaining
+    /// values, remembering what we have seen in a hash map. Doesn't need to sort, but can't skip anything either.
+    Hash,
+    /// The same search as `TwoPointer`, with the choices for the first value of a combination spread across threads
+    Parallel,
+}
+
+/// Find every combination of `k` distinct entries of `values` that adds up to `target`, using the sorted two-pointer
+/// search. Entries with the same value are still distinct entries, so repeated values show up in more than one
+/// combination. The combinations are ordered by their indices.
+pub fn find_k_sum(values: &[i64], k: usize, target: i64) -> Vec<KSum> {
+    find_k_sum_with(values, k, target, Strategy::TwoPointer)
+}
+
+/// Find every combination of `k` distinct entries of `values` that adds up to `target` using a specific `strategy`.
+/// Every strategy returns the same combinations in the same order. Finding all combinations is O(n^(k-1)) in the worst
+/// case, sorting the values lets `TwoPointer` and `Parallel` skip most of that when few combinations come close to
+/// `target`.
+pub fn find_k_sum_with(values: &[i64], k: usize, target: i64, strategy: Strategy) -> Vec<KSum> {
+    let target = target as i128;
+    let mut found = match strategy {
+        Strategy::TwoPointer => {
+            let sorted = sort_with_indices(values);
+            let mut found = Vec::new();
+            sorted_search(&sorted, 0, k, target, &mut Vec::new(), &mut found);
+            found
+        }
+        Strategy::Hash => {
+            let mut found = Vec::new();
+            hash_search(values, 0, k, target, &mut Vec::new(), &mut found);
+            found
+        }
+        Strategy::Parallel => parallel_search(values, k, target),
+    };
+
+    for indices in found.iter_mut() {
+        indices.sort_unstable();
+    }
+    found.sort_unstable();
+
+    found
+        .into_iter()
+        .map(|indices| KSum {
+            product: indices.iter().try_fold(1i128, |product, idx| {
+                product.checked_mul(values[*idx] as i128)
+            }),
+            indices,
+        })
+        .collect()
+}
+
+/// Pair every value with its index and sort by value
+fn sort_with_indices(values: &[i64]) -> Vec<(i64, usize)> {
+    let mut sorted: Vec<(i64, usize)> = values.iter().copied().zip(0..).collect();
+    sorted.sort_unstable();
+    sorted
+}
+
+/// Search `sorted[start..]` for `k` values adding up to `target`, pushing the indices of every match (plus `chosen`)
+/// onto `found`
+fn sorted_search(
+    sorted: &[(i64, usize)],
+    start: usize,
+    k: usize,
+    target: i128,
+    chosen: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+) {
+    let remaining = sorted.len().saturating_sub(start);
+    if k > remaining {
+        return;
+    }
+
+    match k {
+        0 => {
+            if target == 0 {
+                found.push(chosen.clone());
+            }
+        }
+        1 => {
+            for (value, idx) in &sorted[start..] {
+                if *value as i128 == target {
+                    let mut indices = chosen.clone();
+                    indices.push(*idx);
+                    found.push(indices);
+                }
+            }
+        }
+        2 => two_pointer(sorted, start, target, chosen, found),
+        _ => {
+            for first in start..=sorted.len() - k {
+                if prune(sorted, first, k, target) {
+                    break;
+                }
+                if too_small(sorted, first, k, target) {
+                    continue;
+                }
+
+                chosen.push(sorted[first].1);
+                sorted_search(
+                    sorted,
+                    first + 1,
+                    k - 1,
+                    target - sorted[first].0 as i128,
+                    chosen,
+                    found,
+                );
+                chosen.pop();
+            }
+        }
+    }
+}
+
+/// True if the smallest `k` values starting at `first` already overshoot `target`, since the values are sorted no
+/// later choice of `first` can work either
+fn prune(sorted: &[(i64, usize)], first: usize, k: usize, target: i128) -> bool {
+    let smallest: i128 = sorted[first..first + k]
+        .iter()
+        .map(|(value, _)| *value as i128)
+        .sum();
+    smallest > target
+}
+
+/// True if picking `first` together with the `k - 1` largest values still falls short of `target`
+fn too_small(sorted: &[(i64, usize)], first: usize, k: usize, target: i128) -> bool {
+    let largest: i128 = sorted[sorted.len() - (k - 1)..]
+        .iter()
+        .map(|(value, _)| *value as i128)
+        .sum();
+    sorted[first].0 as i128 + largest < target
+}
+
+/// Close in on every pair in `sorted[start..]` that adds up to `target` from both ends
+fn two_pointer(
+    sorted: &[(i64, usize)],
+    start: usize,
+    target: i128,
+    chosen: &[usize],
+    found: &mut Vec<Vec<usize>>,
+) {
+    let mut push = |a: usize, b: usize| {
+        let mut indices = chosen.to_vec();
+        indices.push(sorted[a].1);
+        indices.push(sorted[b].1);
+        found.push(indices);
+    };
+
+    // Nothing above `target - lowest` can be part of a pair, so start the high pointer below it rather than walking
+    // it down one value at a time
+    let mut low = start;
+    let limit = target - sorted[low].0 as i128;
+    let mut high = start + sorted[start..].partition_point(|(value, _)| (*value as i128) <= limit);
+    if high == start {
+        return;
+    }
+    high -= 1;
+
+    while low < high {
+        let sum = sorted[low].0 as i128 + sorted[high].0 as i128;
+        if sum < target {
+            low += 1;
+        } else if sum > target {
+            high -= 1;
+        } else if sorted[low].0 == sorted[high].0 {
+            // Everything between the pointers has the same value, so every pair of them matches
+            for a in low..=high {
+                for b in a + 1..=high {
+                    push(a, b);
+                }
+            }
+            break;
+        } else {
+            // Pair up every copy of the low value with every copy of the high value, then move past both runs
+            let low_end = (low..high)
+                .find(|idx| sorted[*idx].0 != sorted[low].0)
+                .unwrap_or(high);
+            let high_start = (low_end..=high)
+                .find(|idx| sorted[*idx].0 == sorted[high].0)
+                .unwrap_or(high);
+            for a in low..low_end {
+                for b in high_start..=high {
+                    push(a, b);
+                }
+            }
+            low = low_end;
+            high = high_start - 1;
+        }
+    }
+}
+
+/// Search `values[start..]` for `k` values adding up to `target` without sorting, pushing the indices of every match
+/// (plus `chosen`) onto `found`
+fn hash_search(
+    values: &[i64],
+    start: usize,
+    k: usize,
+    target: i128,
+    chosen: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+) {
+    if k > values.len().saturating_sub(start) {
+        return;
+    }
+
+    match k {
+        0 => {
+            if target == 0 {
+                found.push(chosen.clone());
+            }
+        }
+        1 => {
+            for (idx, value) in values.iter().enumerate().skip(start) {
+                if *value as i128 == target {
+                    let mut indices = chosen.clone();
+                    indices.push(idx);
+                    found.push(indices);
+                }
+            }
+        }
+        2 => {
+            // Every index we have passed so far, keyed by its value
+            let mut seen: FnvHashMap<i128, Vec<usize>> = FnvHashMap::default();
+            for (idx, value) in values.iter().enumerate().skip(start) {
+                let value = *value as i128;
+                if let Some(partners) = seen.get(&(target - value)) {
+                    for partner in partners {
+                        let mut indices = chosen.clone();
+                        indices.push(*partner);
+                        indices.push(idx);
+                        found.push(indices);
+                    }
+                }
+                seen.entry(value).or_default().push(idx);
+            }
+        }
+        _ => {
+            for first in start..=values.len() - k {
+                chosen.push(first);
+                hash_search(
+                    values,
+                    first + 1,
+                    k - 1,
+                    target - values[first] as i128,
+                    chosen,
+                    found,
+                );
+                chosen.pop();
+            }
+        }
+    }
+}
+
+/// Sorted search where each choice for the first value of a combination is its own rayon task
+fn parallel_search(values: &[i64], k: usize, target: i128) -> Vec<Vec<usize>> {
+    let sorted = sort_with_indices(values);
+    if k < 3 || k > sorted.len() {
+        let mut found = Vec::new();
+        sorted_search(&sorted, 0, k, target, &mut Vec::new(), &mut found);
+        return found;
+    }
+
+    // Work out where pruning would stop the sequential search so rayon doesn't get handed tasks that can't match
+    let end = (0..=sorted.len() - k)
+        .find(|first| prune(&sorted, *first, k, target))
+        .unwrap_or(sorted.len() - k + 1);
+
+    (0..end)
+        .into_par_iter()
+        .filter(|first| !too_small(&sorted, *first, k, target))
+        .flat_map_iter(|first| {
+            let mut found = Vec::new();
+            let mut chosen = vec![sorted[first].1];
+            sorted_search(
+                &sorted,
+                first + 1,
+                k - 1,
+                target - sorted[first].0 as i128,
+                &mut chosen,
+                &mut found,
+            );
+            found
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [Strategy; 3] = [Strategy::TwoPointer, Strategy::Hash, Strategy::Parallel];
+
+    const EXAMPLE: [i64; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    fn indices(found: &[KSum]) -> Vec<Vec<usize>> {
+        found.iter().map(|sum| sum.indices.clone()).collect()
+    }
+
+    #[test]
+    fn puzzle_example() {
+        for strategy in STRATEGIES.iter() {
+            let pairs = find_k_sum_with(&EXAMPLE, 2, 2020, *strategy);
+            assert_eq!(
+                pairs,
+                vec![KSum {
+                    indices: vec![0, 3],
+                    product: Some(514579)
+                }]
+            );
+
+            let triples = find_k_sum_with(&EXAMPLE, 3, 2020, *strategy);
+            assert_eq!(
+                triples,
+                vec![KSum {
+                    indices: vec![1, 2, 4],
+                    product: Some(241861950)
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn repeated_values_are_distinct_entries() {
+        let values = [5, 5, 5, 1, 9];
+        for strategy in STRATEGIES.iter() {
+            assert_eq!(
+                indices(&find_k_sum_with(&values, 2, 10, *strategy)),
+                vec![vec![0, 1], vec![0, 2], vec![1, 2], vec![3, 4]]
+            );
+            assert_eq!(
+                indices(&find_k_sum_with(&values, 3, 15, *strategy)),
+                vec![vec![0, 1, 2], vec![0, 3, 4], vec![1, 3, 4], vec![2, 3, 4]]
+            );
+        }
+    }
+
+    #[test]
+    fn negative_values() {
+        let values = [-4, 10, -1, 3, 0, 2];
+        for strategy in STRATEGIES.iter() {
+            assert_eq!(
+                indices(&find_k_sum_with(&values, 2, -1, *strategy)),
+                vec![vec![0, 3], vec![2, 4]]
+            );
+            assert_eq!(
+                indices(&find_k_sum_with(&values, 4, 8, *strategy)),
+                vec![vec![0, 1, 2, 3], vec![0, 1, 4, 5]]
+            );
+        }
+    }
+
+    #[test]
+    fn strategies_agree() {
+        // Small pseudo random values so there are lots of combinations, including repeated values
+        let mut seed = 12_345u64;
+        let values: Vec<i64> = (0..60)
+            .map(|_| {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                (seed >> 58) as i64 - 16
+            })
+            .collect();
+
+        for k in 0..=5 {
+            let expected = find_k_sum_with(&values, k, 7, Strategy::Hash);
+            for strategy in STRATEGIES.iter() {
+                assert_eq!(
+                    find_k_sum_with(&values, k, 7, *strategy),
+                    expected,
+                    "k = {}",
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        for strategy in STRATEGIES.iter() {
+            assert!(find_k_sum_with(&[], 2, 0, *strategy).is_empty());
+            assert!(find_k_sum_with(&[1, 2], 3, 3, *strategy).is_empty());
+            assert_eq!(
+                indices(&find_k_sum_with(&[4, 2, 4], 1, 4, *strategy)),
+                vec![vec![0], vec![2]]
+            );
+            assert_eq!(
+                find_k_sum_with(&[4, 2], 0, 0, *strategy),
+                vec![KSum {
+                    indices: vec![],
+                    product: Some(1)
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn product_overflow() {
+        let values = [i64::MAX, i64::MAX, i64::MAX, -i64::MAX];
+        let found = find_k_sum(&values, 3, i64::MAX);
+        assert_eq!(found.len(), 3);
+        assert!(found.iter().all(|sum| sum.product.is_none()));
+    }
+
+    #[test]
+    fn large_input() {
+        // Only a handful of the 100k values are small enough to be part of a match, pruning should skip the rest
+        let values: Vec<i64> = (0..100_000).map(|n| n * 37 % 100_003).collect();
+        let found = find_k_sum_with(&values, 4, 100, Strategy::Parallel);
+
+        assert!(!found.is_empty());
+        for sum in &found {
+            assert_eq!(sum.indices.iter().map(|idx| values[*idx]).sum::<i64>(), 100);
+        }
+        assert_eq!(found, find_k_sum(&values, 4, 100));
+    }
+}
@@ -1,16 +1,25 @@
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod ksum;
+
+pub use ksum::{find_k_sum, find_k_sum_with, KSum, Strategy};
+
 static SEARCH_NUM: u32 = 2020;
 
 #[aoc_generator(day1)]
-pub fn input_generator(input: &str) -> Vec<u32> {
+pub fn input_generator(input: &str) -> Result<Vec<u32>, ParseError> {
     let mut vec: Vec<u32> = input
-        .split("\n")
-        .into_iter()
-        .map(|num| num.parse::<u32>().expect("Error parsing &str into u32"))
-        .collect();
+        .lines()
+        .map(|num| {
+            num.parse::<u32>()
+                .map_err(|_| ParseError::new(1, input, num, ParseErrorKind::InvalidNumber))
+        })
+        .collect::<Result<_, _>>()?;
 
     vec.sort_unstable();
 
-    vec
+    Ok(vec)
 }
 
 #[aoc(day1, part1)]
@@ -119,28 +128,103 @@ pub fn solve_part2_find(input: &[u32]) -> u32 {
     panic!("Unable to find any solutions!");
 }
 
-// // Disabling this solution because the benchmarking is so slow, this is not the best way to solve it.
-// // I am leaving it here just for the sake of already having implemented it.
-// #[aoc(day1, part2, par_iter)]
-// pub fn solve_part2_par_iter(input: &[u32]) -> u32 {
-//     use rayon::prelude::*;
-
-//     for needle in input {
-//         for haystack in input {
-//             let find : Vec<u32> = input.par_iter().filter_map(|&farmland| {
-//                 if needle + haystack + farmland == SEARCH_NUM {
-//                     Some(needle * haystack * farmland)
-//                 } else {
-//                     None
-//                 }
-//             }).collect();
-
-//             if find.len() != 0 {
-//                 return find[0]
-//             }
-//         }
-
-//     }
-
-//     panic!("Unable to find any solutions!");
-// }
+/// Product of the first `k` entries (in sorted order) that add up to `SEARCH_NUM`, found with `strategy`
+fn solve_k_sum(input: &[u32], k: usize, strategy: Strategy) -> i128 {
+    let values: Vec<i64> = input.iter().map(|value| *value as i64).collect();
+    find_k_sum_with(&values, k, SEARCH_NUM as i64, strategy)
+        .first()
+        .and_then(|sum| sum.product)
+        .expect("Unable to find any solutions!")
+}
+
+#[aoc(day1, part1, two_pointer)]
+pub fn solve_part1_two_pointer(input: &[u32]) -> i128 {
+    solve_k_sum(input, 2, Strategy::TwoPointer)
+}
+
+#[aoc(day1, part1, hash)]
+pub fn solve_part1_hash(input: &[u32]) -> i128 {
+    solve_k_sum(input, 2, Strategy::Hash)
+}
+
+#[aoc(day1, part2, two_pointer)]
+pub fn solve_part2_two_pointer(input: &[u32]) -> i128 {
+    solve_k_sum(input, 3, Strategy::TwoPointer)
+}
+
+#[aoc(day1, part2, hash)]
+pub fn solve_part2_hash(input: &[u32]) -> i128 {
+    solve_k_sum(input, 3, Strategy::Hash)
+}
+
+#[aoc(day1, part2, par_iter)]
+pub fn solve_part2_par_iter(input: &[u32]) -> i128 {
+    solve_k_sum(input, 3, Strategy::Parallel)
+}
+
+/// Registers day 1 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<u32>;
+
+    const DAY: u8 = 1;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::One, Some("find"), |input| {
+                solve_part1_find(input).to_string()
+            }),
+            Variant::new(Part::One, Some("par_iter"), |input| {
+                solve_part1_par_iter(input).to_string()
+            }),
+            Variant::new(Part::One, Some("two_pointer"), |input| {
+                solve_part1_two_pointer(input).to_string()
+            }),
+            Variant::new(Part::One, Some("hash"), |input| {
+                solve_part1_hash(input).to_string()
+            }),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+            Variant::new(Part::Two, Some("find"), |input| {
+                solve_part2_find(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("two_pointer"), |input| {
+                solve_part2_two_pointer(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("hash"), |input| {
+                solve_part2_hash(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("par_iter"), |input| {
+                solve_part2_par_iter(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "1721
+979
+366
+299
+675
+1456";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "514579");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "241861950");
+    }
+}
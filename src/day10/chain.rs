@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+use super::bignum::BigUint;
+
+/// Largest joltage difference an adapter can take in the puzzle
+pub const DEFAULT_MAX_GAP: u64 = 3;
+
+/// A bag of adapters between the charging outlet, rated 0 jolts, and the device, rated `max_gap` above the highest
+/// adapter. An adapter can take any input from 1 up to `max_gap` jolts lower than its own rating, so a chain is a run
+/// of adapters with strictly rising joltages and no step larger than `max_gap`.
+///
+/// Internally the outlet, every adapter in order, and the device are nodes `0..=len + 1`, so adapters with the same
+/// rating are still told apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterSet {
+    /// Outlet, sorted adapters, then the device
+    joltages: Vec<u64>,
+    max_gap: u64,
+}
+
+impl AdapterSet {
+    /// Adapters with the puzzle's maximum gap of 3 jolts
+    pub fn new(adapters: &[u64]) -> Self {
+        Self::with_max_gap(adapters, DEFAULT_MAX_GAP).expect("The default gap always fits")
+    }
+
+    /// Adapters that can bridge up to `max_gap` jolts. Returns `None` if the gap is 0, since then nothing connects, or
+    /// if the device rating doesn't fit in a `u64`.
+    pub fn with_max_gap(adapters: &[u64], max_gap: u64) -> Option<Self> {
+        if max_gap == 0 {
+            return None;
+        }
+
+        let mut joltages = Vec::with_capacity(adapters.len() + 2);
+        joltages.push(0);
+        joltages.extend_from_slice(adapters);
+        joltages.sort_unstable();
+        let device = joltages.last().unwrap().checked_add(max_gap)?;
+        joltages.push(device);
+
+        Some(AdapterSet { joltages, max_gap })
+    }
+
+    pub fn max_gap(&self) -> u64 {
+        self.max_gap
+    }
+
+    /// Rating of the device, `max_gap` above the highest adapter
+    pub fn device(&self) -> u64 {
+        *self.joltages.last().unwrap()
+    }
+
+    /// Every adapter's rating, lowest first
+    pub fn adapters(&self) -> &[u64] {
+        &self.joltages[1..self.joltages.len() - 1]
+    }
+
+    pub fn len(&self) -> usize {
+        self.joltages.len() - 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index of the device node
+    fn end(&self) -> usize {
+        self.joltages.len() - 1
+    }
+
+    /// Nodes that can follow `node` in a chain, lowest first
+    fn successors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let joltage = self.joltages[node];
+        (node + 1..self.joltages.len())
+            .take_while(move |next| self.joltages[*next] - joltage <= self.max_gap)
+            .filter(move |next| self.joltages[*next] > joltage)
+    }
+
+    /// Which nodes are on at least one chain from the outlet to the device
+    fn on_chain(&self) -> Vec<bool> {
+        let mut reached = vec![false; self.joltages.len()];
+        reached[0] = true;
+        for node in 0..self.joltages.len() {
+            if reached[node] {
+                for next in self.successors(node) {
+                    reached[next] = true;
+                }
+            }
+        }
+
+        let mut on_chain = vec![false; self.joltages.len()];
+        on_chain[self.end()] = reached[self.end()];
+        for node in (0..self.end()).rev() {
+            on_chain[node] = reached[node] && self.successors(node).any(|next| on_chain[next]);
+        }
+        on_chain
+    }
+
+    /// How many times each joltage difference appears in the chain that uses every adapter, including the steps from
+    /// the outlet and to the device. Returns `None` if the adapters can't all be used, because two share a rating or
+    /// there is a gap larger than `max_gap`.
+    pub fn differences(&self) -> Option<BTreeMap<u64, usize>> {
+        let mut differences = BTreeMap::new();
+        for pair in self.joltages.windows(2) {
+            let difference = pair[1] - pair[0];
+            if difference == 0 || difference > self.max_gap {
+                return None;
+            }
+            *differences.entry(difference).or_insert(0) += 1;
+        }
+        Some(differences)
+    }
+
+    /// Number of distinct chains from the outlet to the device, without any risk of overflowing
+    pub fn arrangements(&self) -> BigUint {
+        // Chains from each node to the device, filled in from the device backwards
+        let mut counts = vec![BigUint::zero(); self.joltages.len()];
+        counts[self.end()] = BigUint::one();
+        for node in (0..self.end()).rev() {
+            let mut count = BigUint::zero();
+            for next in self.successors(node) {
+                count += &counts[next];
+            }
+            counts[node] = count;
+        }
+
+        counts.swap_remove(0)
+    }
+
+    /// Every chain from the outlet to the device as the adapter ratings it uses, generated one at a time in
+    /// lexicographic order. Nodes that can't reach the device are never visited, so each chain takes time proportional
+    /// to its length to find however many dead ends the adapters have.
+    pub fn chains(&self) -> Chains<'_> {
+        let on_chain = self.on_chain();
+        let stack = if on_chain[0] {
+            vec![(0, 1)]
+        } else {
+            Vec::new()
+        };
+        Chains {
+            set: self,
+            on_chain,
+            stack,
+        }
+    }
+
+    /// A chain using as few adapters as possible, or `None` if there is no chain at all. Jumping to the highest rated
+    /// adapter in reach is always at least as good as any other choice, since everything a lower adapter can reach
+    /// above it the higher one can too.
+    pub fn shortest_chain(&self) -> Option<Vec<u64>> {
+        let mut chain = Vec::new();
+        let mut node = 0;
+        while node != self.end() {
+            node = self.successors(node).last()?;
+            chain.push(self.joltages[node]);
+        }
+        chain.pop();
+
+        Some(chain)
+    }
+
+    /// Ratings of the adapters used by every chain, lowest first. An adapter is only mandatory if no step of any chain
+    /// jumps over it, and another adapter with the same rating counts as jumping over it. There are none if there are
+    /// no chains.
+    pub fn mandatory(&self) -> Vec<u64> {
+        let on_chain = self.on_chain();
+        if !on_chain[0] {
+            return Vec::new();
+        }
+
+        let mut mandatory = Vec::new();
+        // The furthest node any step from an earlier node on a chain lands on
+        let mut reach = 0;
+        for node in 0..self.end() {
+            if node > 0 && on_chain[node] && reach <= node {
+                mandatory.push(self.joltages[node]);
+            }
+            if on_chain[node] {
+                if let Some(furthest) = self.successors(node).filter(|next| on_chain[*next]).last()
+                {
+                    reach = reach.max(furthest);
+                }
+            }
+        }
+
+        mandatory
+    }
+}
+
+/// Iterator returned by `AdapterSet::chains`
+#[derive(Debug, Clone)]
+pub struct Chains<'a> {
+    set: &'a AdapterSet,
+    on_chain: Vec<bool>,
+    /// The chain being built, each node alongside the next node to try after it
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a> Iterator for Chains<'a> {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Vec<u64>> {
+        let set = self.set;
+        let on_chain = &self.on_chain;
+        while let Some((node, from)) = self.stack.last_mut() {
+            let joltage = set.joltages[*node];
+            let next = (*from..set.joltages.len())
+                .take_while(|next| set.joltages[*next] - joltage <= set.max_gap)
+                .find(|next| set.joltages[*next] > joltage && on_chain[*next]);
+
+            match next {
+                None => {
+                    self.stack.pop();
+                }
+                Some(next) => {
+                    *from = next + 1;
+                    if next == set.end() {
+                        return Some(
+                            self.stack[1..]
+                                .iter()
+                                .map(|(node, _)| set.joltages[*node])
+                                .collect(),
+                        );
+                    }
+                    self.stack.push((next, next + 1));
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -4,11 +4,23 @@ use factorial::Factorial;
 use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod bignum;
+pub mod chain;
+
+pub use bignum::BigUint;
+pub use chain::{AdapterSet, Chains, DEFAULT_MAX_GAP};
+
 #[aoc_generator(day10)]
-pub fn input_generator(input: &str) -> Vec<u64> {
+pub fn input_generator(input: &str) -> Result<Vec<u64>, ParseError> {
     input
         .lines()
-        .map(|line| line.parse::<u64>().expect("Error while parsing input"))
+        .map(|line| {
+            line.parse::<u64>()
+                .map_err(|_| ParseError::new(10, input, line, ParseErrorKind::InvalidNumber))
+        })
         .collect()
 }
 
@@ -62,6 +74,14 @@ pub fn solve_part1_iter(input: &[u64]) -> u64 {
     diffs[0] * diffs[2]
 }
 
+#[aoc(day10, part1, adapter_set)]
+pub fn solve_part1_adapter_set(input: &[u64]) -> usize {
+    let differences = AdapterSet::new(input)
+        .differences()
+        .expect("Not every adapter can be used");
+    differences.get(&1).unwrap_or(&0) * differences.get(&3).unwrap_or(&0)
+}
+
 pub fn count_chains_cached(
     adapters: &[u64],
     index: usize,
@@ -124,3 +144,166 @@ pub fn solve_part2_mask(input: &[u64]) -> u64 {
 
     mask[0]
 }
+
+#[aoc(day10, part2, bigint)]
+pub fn solve_part2_bigint(input: &[u64]) -> BigUint {
+    AdapterSet::new(input).arrangements()
+}
+
+/// Registers day 10 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<u64>;
+
+    const DAY: u8 = 10;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::One, Some("iter"), |input| {
+                solve_part1_iter(input).to_string()
+            }),
+            Variant::new(Part::One, Some("adapter_set"), |input| {
+                solve_part1_adapter_set(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("cache"), |input| {
+                solve_part2_cache(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("mask"), |input| {
+                solve_part2_mask(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("bigint"), |input| {
+                solve_part2_bigint(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const SMALL_EXAMPLE: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
+    const LARGE_EXAMPLE: &str = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3";
+
+    #[test]
+    fn part1_examples() {
+        assert_variants::<Solution>(SMALL_EXAMPLE, Part::One, "35");
+        assert_variants::<Solution>(LARGE_EXAMPLE, Part::One, "220");
+    }
+
+    #[test]
+    fn part2_examples() {
+        assert_variants::<Solution>(SMALL_EXAMPLE, Part::Two, "8");
+        assert_variants::<Solution>(LARGE_EXAMPLE, Part::Two, "19208");
+    }
+
+    #[test]
+    fn adapter_set() {
+        let set = AdapterSet::new(&input_generator(SMALL_EXAMPLE).unwrap());
+        assert_eq!(set.device(), 22);
+        assert_eq!(set.len(), 11);
+        assert_eq!(set.adapters()[..3], [1, 4, 5]);
+        let differences = set.differences().unwrap();
+        assert_eq!((differences[&1], differences[&3]), (7, 5));
+        assert_eq!(set.arrangements().to_u64(), Some(8));
+
+        let chains: Vec<Vec<u64>> = set.chains().collect();
+        assert_eq!(chains.len(), 8);
+        assert_eq!(chains[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(chains[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+        assert!(chains.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(
+            set.shortest_chain().unwrap(),
+            vec![1, 4, 7, 10, 12, 15, 16, 19]
+        );
+        assert_eq!(set.mandatory(), vec![1, 4, 7, 10, 12, 15, 16, 19]);
+
+        let set = AdapterSet::new(&input_generator(LARGE_EXAMPLE).unwrap());
+        assert_eq!(set.arrangements().to_string(), "19208");
+        assert_eq!(set.chains().count(), 19208);
+        let shortest = set.shortest_chain().unwrap();
+        assert!(set.chains().all(|chain| chain.len() >= shortest.len()));
+        assert!(set
+            .mandatory()
+            .iter()
+            .all(|adapter| shortest.contains(adapter)));
+    }
+
+    #[test]
+    fn adapter_gaps() {
+        // A gap of 2 cuts the small example off at 1 -> 4
+        let adapters = input_generator(SMALL_EXAMPLE).unwrap();
+        let set = AdapterSet::with_max_gap(&adapters, 2).unwrap();
+        assert!(set.arrangements().is_zero());
+        assert_eq!(set.chains().next(), None);
+        assert_eq!(set.shortest_chain(), None);
+        assert!(set.mandatory().is_empty());
+        assert_eq!(set.differences(), None);
+
+        // With a wide gap any subset works, as long as it ends close enough to the device
+        let set = AdapterSet::with_max_gap(&[1, 2, 3, 4], 5).unwrap();
+        assert_eq!(set.device(), 9);
+        assert_eq!(set.arrangements().to_u64(), Some(8));
+        assert_eq!(set.shortest_chain().unwrap(), vec![4]);
+        assert_eq!(set.mandatory(), vec![4]);
+
+        assert_eq!(AdapterSet::with_max_gap(&[1], 0), None);
+        assert_eq!(AdapterSet::with_max_gap(&[u64::MAX], 1), None);
+    }
+
+    #[test]
+    fn repeated_adapters() {
+        // Either 1 can be used, but never both
+        let set = AdapterSet::new(&[1, 1, 2]);
+        assert_eq!(set.arrangements().to_u64(), Some(3));
+        assert_eq!(
+            set.chains().collect::<Vec<_>>(),
+            vec![vec![1, 2], vec![1, 2], vec![2]]
+        );
+        assert_eq!(set.mandatory(), vec![2]);
+        assert_eq!(set.differences(), None);
+
+        let set = AdapterSet::new(&[3, 3, 6, 9]);
+        assert_eq!(set.mandatory(), vec![6, 9]);
+    }
+
+    #[test]
+    fn arrangements_past_u64() {
+        // Each run of 1 jolt steps multiplies the count, so enough of them overflow a u64
+        let adapters: Vec<u64> = (1..=100).collect();
+        let set = AdapterSet::new(&adapters);
+        assert_eq!(set.arrangements().to_u64(), None);
+        assert_eq!(
+            set.arrangements().to_string(),
+            "180396380815100901214157639"
+        );
+        assert_eq!(set.mandatory(), vec![100]);
+        assert_eq!(set.shortest_chain().unwrap().len(), 34);
+
+        // The lazy enumeration doesn't need to find them all to produce the first few
+        assert_eq!(set.chains().nth(2).unwrap().len(), 99);
+    }
+
+    #[test]
+    fn big_uint() {
+        let mut sum = BigUint::from(u64::MAX);
+        sum += &BigUint::one();
+        assert_eq!(sum.to_string(), "18446744073709551616");
+        assert_eq!(sum.to_u64(), None);
+        assert!(sum > BigUint::from(u64::MAX));
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(format!("{:>4}", BigUint::from(12)), "  12");
+        assert_eq!(BigUint::from(0), BigUint::zero());
+        assert_eq!((BigUint::from(7) + &BigUint::from(5)).to_u64(), Some(12));
+    }
+}
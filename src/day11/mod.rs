@@ -1,34 +1,35 @@
-use std::collections::VecDeque;
+use crate::automaton::{Adjacent, Automaton, Grid, LineOfSight, Neighborhood, Neighbors, Rule};
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
 
-use factorial::Factorial;
-use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
-use regex::Regex;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Seat {
     Floor,
     Empty,
     Occupied,
+    /// Past the end of a row that is shorter than the others. Nobody sits here and nobody can see past it, just like
+    /// the edge of the layout.
+    Outside,
 }
 
 impl Seat {
-    fn from_char(input: &char) -> Seat {
+    fn from_char(input: &char) -> Result<Seat, ParseErrorKind> {
         match input {
-            '.' => Seat::Floor,
-            'L' => Seat::Empty,
-            '#' => Seat::Occupied,
-            _ => unreachable!("Unhandled seat layout character"),
+            '.' => Ok(Seat::Floor),
+            'L' => Ok(Seat::Empty),
+            '#' => Ok(Seat::Occupied),
+            _ => Err(ParseErrorKind::UnexpectedChar(*input)),
         }
     }
 
-    fn is_occupied(&self) -> bool {
+    pub fn is_occupied(&self) -> bool {
         match self {
             Self::Occupied => true,
             _ => false,
         }
     }
 
-    fn is_empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
         match self {
             Self::Empty => true,
             _ => false,
@@ -36,265 +37,348 @@ impl Seat {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Slope(i32, i32);
+/// How many steps a seating layout gets to settle before we give up on it
+pub const MAX_ROUNDS: usize = 10_000;
+
+/// The seating rule: an empty seat is taken when none of its neighbors are occupied, and an occupied seat is left
+/// once `tolerance` or more of its neighbors are. Floor never changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeatingRule {
+    pub tolerance: usize,
+}
+
+impl Rule<Seat> for SeatingRule {
+    fn next(&self, seat: &Seat, neighbors: Neighbors<'_, Seat>) -> Seat {
+        match seat {
+            Seat::Floor | Seat::Outside => *seat,
+            Seat::Empty if neighbors.clone().all(|seat| !seat.is_occupied()) => Seat::Occupied,
+            Seat::Occupied
+                if neighbors.filter(|seat| seat.is_occupied()).count() >= self.tolerance =>
+            {
+                Seat::Empty
+            }
+            _ => *seat,
+        }
+    }
+}
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-pub struct Coords(u32, u32);
+/// Part 1: people look at the 8 seats around them and leave once 4 are occupied
+pub const ADJACENT_RULE: SeatingRule = SeatingRule { tolerance: 4 };
+/// Part 2: people look past the floor to the first seat in each direction and leave once 5 are occupied
+pub const VISIBLE_RULE: SeatingRule = SeatingRule { tolerance: 5 };
 
-#[derive(Debug, Clone)]
+/// Seats can be seen through floor, but not through other seats
+pub fn line_of_sight() -> LineOfSight<fn(&Seat) -> bool> {
+    LineOfSight(|seat| *seat == Seat::Floor)
+}
+
+/// The waiting area's seating layout. Seats are stored in a dense grid, but are addressed by signed coordinates
+/// measured from an `origin`, the position of the top left corner, so a layout can sit anywhere on the plane.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Boat {
-    seats: FnvHashMap<Coords, Seat>,
-    neighborhood_map: FnvHashMap<Coords, Vec<Coords>>,
-    visible_neighborhood_map: FnvHashMap<Coords, Vec<Coords>>,
-    update_map: FnvHashMap<Coords, bool>,
+    seats: Grid<Seat>,
+    origin: (isize, isize),
 }
 
 impl Boat {
-    fn update_seat(&mut self, coords: &Coords) {
-        let occupied_neighbors = self.count_occupied_neighbors(coords);
-
-        if occupied_neighbors == 0
-            && self
-                .seats
-                .get(coords)
-                .expect("Unable to get our own seat?")
-                .is_empty()
-        {
-            *self
-                .update_map
-                .get_mut(coords)
-                .expect("Unable to retrieve update map entry") = true
-        } else if occupied_neighbors >= 4
-            && self
-                .seats
-                .get(coords)
-                .expect("Unable to get our own seat?")
-                .is_occupied()
-        {
-            *self
-                .update_map
-                .get_mut(coords)
-                .expect("Unable to retrieve update map entry") = true
-        } else {
+    /// Layout with its top left corner at `(0, 0)`
+    pub fn new(seats: Grid<Seat>) -> Self {
+        Boat {
+            seats,
+            origin: (0, 0),
         }
     }
 
-    fn update_seat_part2(&mut self, coords: &Coords) {
-        let occupied_neighbors = self.count_occupied_neighbors_part2(coords);
-
-        if occupied_neighbors == 0
-            && self
-                .seats
-                .get(coords)
-                .expect("Unable to get our own seat?")
-                .is_empty()
-        {
-            *self
-                .update_map
-                .get_mut(coords)
-                .expect("Unable to retrieve update map entry") = true
-        } else if occupied_neighbors >= 5
-            && self
-                .seats
-                .get(coords)
-                .expect("Unable to get our own seat?")
-                .is_occupied()
-        {
-            *self
-                .update_map
-                .get_mut(coords)
-                .expect("Unable to retrieve update map entry") = true
-        } else {
-        }
+    /// The same layout with its top left corner at `(x, y)`
+    pub fn with_origin(mut self, x: isize, y: isize) -> Self {
+        self.origin = (x, y);
+        self
     }
 
-    fn count_occupied_neighbors(&self, coords: &Coords) -> usize {
-        if let Some(neighbors) = self.neighborhood_map.get(coords) {
-            neighbors
-                .iter()
-                .map(|neighbor| {
-                    self.seats
-                        .get(neighbor)
-                        .expect("Unable to retrieve seat for neighbor!")
-                })
-                .filter(|seat| seat.is_occupied())
-                .count()
-        } else {
-            panic!("Failed to retrieve a neighbor map, even empty!")
-        }
+    pub fn origin(&self) -> (isize, isize) {
+        self.origin
     }
 
-    fn count_occupied_neighbors_part2(&self, coords: &Coords) -> usize {
-        if let Some(neighbors) = self.visible_neighborhood_map.get(coords) {
-            neighbors
-                .iter()
-                .map(|neighbor| {
-                    self.seats
-                        .get(neighbor)
-                        .expect("Unable to retrieve seat for neighbor!")
-                })
-                .filter(|seat| seat.is_occupied())
-                .count()
-        } else {
-            panic!("Failed to retrieve a neighbor map, even empty!")
-        }
+    pub fn seats(&self) -> &Grid<Seat> {
+        &self.seats
     }
 
-    fn apply_update_map(&mut self) {
-        for (coords, val) in &mut self.update_map {
-            if *val == true {
-                let seat = self
-                    .seats
-                    .get_mut(coords)
-                    .expect("Unable to get seat from update map coords");
-                match seat {
-                    Seat::Empty => *seat = Seat::Occupied,
-                    Seat::Occupied => *seat = Seat::Empty,
-                    _ => unreachable!(),
-                }
-
-                *val = false;
-            }
+    /// Grid position of the signed coordinates `(x, y)`, if they are inside the layout
+    fn grid_position(&self, x: isize, y: isize) -> Option<(usize, usize)> {
+        let column = x.checked_sub(self.origin.0)?;
+        let row = y.checked_sub(self.origin.1)?;
+        if column < 0 || row < 0 {
+            return None;
         }
+        self.seats
+            .index_of(column as usize, row as usize)
+            .map(|_| (column as usize, row as usize))
     }
 
-    fn count_changes(&self) -> usize {
-        let mut changes = 0;
-        for (_, val) in &self.update_map {
-            if *val == true {
-                changes += 1;
-            }
+    /// What is at `(x, y)`, or `None` if that is off the edge of the layout
+    pub fn seat(&self, x: isize, y: isize) -> Option<Seat> {
+        match self
+            .grid_position(x, y)
+            .map(|position| self.seats[position])
+        {
+            Some(Seat::Outside) | None => None,
+            seat => seat,
         }
+    }
 
-        changes
+    /// Signed coordinates of every occupied seat, row by row
+    pub fn occupied(&self) -> Vec<(isize, isize)> {
+        (0..self.seats.len())
+            .filter(|index| self.seats.cells()[*index].is_occupied())
+            .map(|index| {
+                let (column, row) = self.seats.position(index);
+                (
+                    self.origin.0 + column as isize,
+                    self.origin.1 + row as isize,
+                )
+            })
+            .collect()
     }
 
-    fn count_occupied_seats(&self) -> usize {
-        let mut seats = 0;
+    pub fn count_occupied_seats(&self) -> usize {
+        self.seats.count(Seat::is_occupied)
+    }
 
-        for (_, seat) in &self.seats {
-            if *seat == Seat::Occupied {
-                seats += 1;
-            }
-        }
+    /// The seating simulation for this layout, with `neighborhood` choosing who each person pays attention to
+    pub fn automaton<N: Neighborhood<Seat>>(
+        &self,
+        neighborhood: &N,
+        rule: SeatingRule,
+    ) -> Automaton<Seat, SeatingRule> {
+        Automaton::new(self.seats.clone(), neighborhood, rule)
+    }
 
-        seats
+    /// The layout once everyone has stopped moving, or `None` if they are still moving after `MAX_ROUNDS` rounds
+    pub fn settled<N: Neighborhood<Seat>>(
+        &self,
+        neighborhood: &N,
+        rule: SeatingRule,
+        parallel: bool,
+    ) -> Option<Boat> {
+        let mut automaton = self.automaton(neighborhood, rule).with_parallel(parallel);
+        automaton.run_until_stable(MAX_ROUNDS)?;
+        Some(Boat {
+            seats: automaton.grid().clone(),
+            origin: self.origin,
+        })
     }
 }
 
+/// Parse a layout, one row per line. Rows may be different lengths, anything past the end of a short row is
+/// `Seat::Outside`.
 #[aoc_generator(day11)]
-pub fn input_generator(input: &str) -> Boat {
-    let mut seats = FnvHashMap::default();
-    let mut neighborhood_map = FnvHashMap::default();
-    let mut visible_neighborhood_map = FnvHashMap::default();
-    let mut update_map = FnvHashMap::default();
-
-    input.lines().enumerate().for_each(|(idx, line)| {
-        let mut x_coord = 0;
-
-        line.chars().for_each(|c| {
-            seats.insert(Coords(x_coord, idx as u32 - 1), Seat::from_char(&c));
-            update_map.insert(Coords(x_coord, idx as u32 - 1), false);
-            x_coord += 1;
-        });
-    });
-
-    seats.iter().for_each(|(coords, _)| {
-        let mut neighbor_vec = Vec::new();
-
-        let nw = Coords(coords.0 - 1, coords.1 - 1);
-        let n = Coords(coords.0, coords.1 - 1);
-        let ne = Coords(coords.0 + 1, coords.1 - 1);
-        let e = Coords(coords.0 + 1, coords.1);
-        let se = Coords(coords.0 + 1, coords.1 + 1);
-        let s = Coords(coords.0, coords.1 + 1);
-        let sw = Coords(coords.0 - 1, coords.1 + 1);
-        let w = Coords(coords.0 - 1, coords.1);
-
-        for ea in [nw, n, ne, e, se, s, sw, w].iter() {
-            if let Some(_) = seats.get(&ea) {
-                neighbor_vec.push(*ea);
-            }
-        }
+pub fn input_generator(input: &str) -> Result<Boat, ParseError> {
+    let rows = input
+        .lines()
+        .map(|line| {
+            line.char_indices()
+                .map(|(idx, c)| {
+                    Seat::from_char(&c).map_err(|kind| {
+                        ParseError::new(11, input, &line[idx..idx + c.len_utf8()], kind)
+                    })
+                })
+                .collect::<Result<Vec<Seat>, _>>()
+        })
+        .collect::<Result<Vec<Vec<Seat>>, _>>()?;
 
-        neighborhood_map.insert(*coords, neighbor_vec);
-    });
-
-    seats.iter().for_each(|(coords, _)| {
-        let mut neighbor_vec = Vec::new();
-
-        let nw = Slope(-1, -1);
-        let n = Slope(0, -1);
-        let ne = Slope(1, -1);
-        let e = Slope(1, 0);
-        let se = Slope(1, 1);
-        let s = Slope(0, 1);
-        let sw = Slope(-1, 1);
-        let w = Slope(-1, 0);
-
-        for ea in [nw, n, ne, e, se, s, sw, w].iter() {
-            let mut idx = 1;
-            loop {
-                let view_coords = Coords(
-                    coords.0.wrapping_add(idx * ea.0 as u32),
-                    coords.1.wrapping_add(idx * ea.1 as u32),
-                );
-                if let Some(seat) = seats.get(&view_coords) {
-                    match seat {
-                        Seat::Floor => idx += 1,
-                        _ => {
-                            neighbor_vec.push(view_coords);
-                            break;
-                        }
-                    }
-                } else {
-                    break;
-                }
-            }
-        }
+    Ok(Boat::new(Grid::from_ragged_rows(rows, Seat::Outside)))
+}
 
-        visible_neighborhood_map.insert(*coords, neighbor_vec);
-    });
+/// Let the seating settle and count the occupied seats
+fn settle<N: Neighborhood<Seat>>(
+    input: &Boat,
+    neighborhood: &N,
+    rule: SeatingRule,
+    parallel: bool,
+) -> usize {
+    input
+        .settled(neighborhood, rule, parallel)
+        .expect("The seating never settles")
+        .count_occupied_seats()
+}
 
-    Boat {
-        seats,
-        neighborhood_map,
-        visible_neighborhood_map,
-        update_map,
-    }
+#[aoc(day11, part1, automaton)]
+pub fn solve_part1_automaton(input: &Boat) -> usize {
+    settle(input, &Adjacent, ADJACENT_RULE, false)
 }
 
-#[aoc(day11, part1, naive)]
-pub fn solve_part1_naive(input: &Boat) -> usize {
-    let mut boat = input.clone();
+#[aoc(day11, part1, parallel)]
+pub fn solve_part1_parallel(input: &Boat) -> usize {
+    settle(input, &Adjacent, ADJACENT_RULE, true)
+}
 
-    loop {
-        for coord in input.seats.keys() {
-            boat.update_seat(coord);
-        }
+#[aoc(day11, part2, automaton)]
+pub fn solve_part2_automaton(input: &Boat) -> usize {
+    settle(input, &line_of_sight(), VISIBLE_RULE, false)
+}
 
-        if boat.count_changes() == 0 {
-            return boat.count_occupied_seats();
-        }
+#[aoc(day11, part2, parallel)]
+pub fn solve_part2_parallel(input: &Boat) -> usize {
+    settle(input, &line_of_sight(), VISIBLE_RULE, true)
+}
+
+/// Registers day 11 with the crate's own runner
+pub struct Solution;
 
-        boat.apply_update_map();
+impl Solver for Solution {
+    type Input = Boat;
+
+    const DAY: u8 = 11;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("automaton"), |input| {
+                solve_part1_automaton(input).to_string()
+            }),
+            Variant::new(Part::One, Some("parallel"), |input| {
+                solve_part1_parallel(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("automaton"), |input| {
+                solve_part2_automaton(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("parallel"), |input| {
+                solve_part2_parallel(input).to_string()
+            }),
+        ]
     }
 }
 
-#[aoc(day11, part2, naive)]
-pub fn solve_part2_naive(input: &Boat) -> usize {
-    let mut boat = input.clone();
-    let mut iterations = 0;
-    loop {
-        for coord in input.seats.keys() {
-            boat.update_seat_part2(coord);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Cycle;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "L.LL.LL.LL
+LLLLLLL.LL
+L.L.L..L..
+LLLL.LL.LL
+L.LL.LL.LL
+L.LLLLL.LL
+..L.L.....
+LLLLLLLLLL
+L.LLLLLL.L
+L.LLLLL.LL";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "37");
+    }
 
-        if boat.count_changes() == 0 {
-            return boat.count_occupied_seats();
-        }
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "26");
+    }
+
+    #[test]
+    fn rounds_until_stable() {
+        let boat = input_generator(EXAMPLE).unwrap();
+        let mut automaton = boat.automaton(&Adjacent, ADJACENT_RULE);
+        automaton.step();
+        assert_eq!(automaton.grid().count(Seat::is_occupied), 71);
+        assert_eq!(automaton.run_until_stable(MAX_ROUNDS), Some(5));
+
+        let mut automaton = boat.automaton(&line_of_sight(), VISIBLE_RULE);
+        assert_eq!(
+            automaton.find_cycle(MAX_ROUNDS),
+            Some(Cycle {
+                start: 6,
+                period: 1
+            })
+        );
+        assert_eq!(automaton.grid().count(Seat::is_occupied), 26);
+    }
+
+    #[test]
+    fn visible_seats() {
+        // The empty seat in the middle sees an occupied seat in all 8 directions
+        let boat = input_generator(
+            ".......#.
+...#.....
+.#.......
+.........
+..#L....#
+....#....
+.........
+#........
+...#.....",
+        )
+        .unwrap();
+        let automaton = boat.automaton(&line_of_sight(), VISIBLE_RULE);
+        let middle = boat.seats().index_of(3, 4).unwrap();
+        assert_eq!(automaton.neighbors_of(middle).len(), 8);
+
+        // Here it sees nothing at all
+        let boat = input_generator(".##.##.\n#.#.#.#\n##...##\n...L...\n##...##\n#.#.#.#\n.##.##.")
+            .unwrap();
+        let automaton = boat.automaton(&line_of_sight(), VISIBLE_RULE);
+        let middle = boat.seats().index_of(3, 3).unwrap();
+        assert!(automaton.neighbors_of(middle).is_empty());
+    }
+
+    #[test]
+    fn malformed_layouts() {
+        let err = input_generator("L.L\nLxL").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('x'));
+        assert_eq!((err.line, err.column), (2, 2));
+
+        // Characters are counted, not bytes
+        let err = input_generator("L.L\nLéL").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('é'));
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 2, "é"));
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let boat = input_generator("L.L\nL\n\n#LL#").unwrap();
+        assert_eq!((boat.seats().width(), boat.seats().height()), (4, 4));
+        assert_eq!(boat.seat(0, 1), Some(Seat::Empty));
+        assert_eq!(boat.seat(1, 1), None);
+        assert_eq!(boat.seat(3, 0), None);
+        assert_eq!(boat.seat(3, 3), Some(Seat::Occupied));
+        assert_eq!(boat.occupied(), vec![(0, 3), (3, 3)]);
+
+        // The gap past a short row stops sight like the edge of the layout does, so the seat at (0, 0) can't see
+        // (0, 3) but would otherwise see (2, 0) and (3, 3) past the floor
+        let automaton = boat.automaton(&line_of_sight(), VISIBLE_RULE);
+        let corner = boat.seats().index_of(0, 0).unwrap();
+        let visible: Vec<Seat> = automaton
+            .neighbors_of(corner)
+            .iter()
+            .map(|index| boat.seats().cells()[*index])
+            .filter(|seat| *seat != Seat::Outside)
+            .collect();
+        assert_eq!(visible, vec![Seat::Empty, Seat::Empty]);
+
+        // Nobody ever sits outside
+        let settled = boat.settled(&Adjacent, ADJACENT_RULE, false).unwrap();
+        assert_eq!(settled.seats().count(|seat| *seat == Seat::Outside), 8);
+        assert_eq!(settled.count_occupied_seats(), 5);
+    }
 
-        boat.apply_update_map();
+    #[test]
+    fn signed_coordinates() {
+        let boat = input_generator(EXAMPLE).unwrap().with_origin(-5, -1);
+        assert_eq!(boat.origin(), (-5, -1));
+        assert_eq!(boat.seat(-5, -1), Some(Seat::Empty));
+        assert_eq!(boat.seat(-4, -1), Some(Seat::Floor));
+        assert_eq!(boat.seat(-6, -1), None);
+        assert_eq!(boat.seat(4, 8), Some(Seat::Empty));
+        assert_eq!(boat.seat(5, 8), None);
+        assert_eq!(boat.seat(isize::MIN, isize::MAX), None);
+
+        let settled = boat.settled(&Adjacent, ADJACENT_RULE, true).unwrap();
+        assert_eq!(settled.origin(), (-5, -1));
+        assert_eq!(settled.occupied().len(), 37);
+        assert_eq!(settled.occupied()[0], (-5, -1));
     }
 }
@@ -1,11 +1,18 @@
 use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 use regex::Regex;
+use std::fmt;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod navigation;
+pub use navigation::{NavigationError, Navigator, Scalar, Steering, Track, TrackPoint, Vector};
 
 lazy_static! {
-    static ref INSTRUCTIONS: Regex = Regex::new(r"([NSEWLFR])(\d+)").unwrap();
+    static ref INSTRUCTIONS: Regex = Regex::new(r"^([NSEWLFR])(\d+)$").unwrap();
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     North(usize),
     South(usize),
@@ -17,52 +24,49 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    pub fn from_str(input: &str) -> Self {
-        let caps = INSTRUCTIONS
-            .captures(input)
-            .expect("Error while capturing instruction from string");
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let caps = INSTRUCTIONS.captures(input).ok_or_else(|| {
+            ParseError::new(
+                12,
+                input,
+                input,
+                ParseErrorKind::Malformed("one of `NSEWLRF` followed by a number"),
+            )
+        })?;
+
+        let val = caps.get(2).unwrap().as_str();
+        let val = val
+            .parse()
+            .map_err(|_| ParseError::new(12, input, val, ParseErrorKind::InvalidNumber))?;
 
         match &caps[1] {
-            "N" => Instruction::North(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with North instruction"),
-            ),
-            "S" => Instruction::South(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with South instruction"),
-            ),
-            "E" => Instruction::East(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with East instruction"),
-            ),
-            "W" => Instruction::West(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with West instruction"),
-            ),
-            "L" => Instruction::Left(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with Left instruction"),
-            ),
-            "R" => Instruction::Right(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with Right instruction"),
-            ),
-            "F" => Instruction::Forward(
-                caps[2]
-                    .parse()
-                    .expect("Error while parsing value associated with Forward instruction"),
-            ),
-            _ => unreachable!("Invalid instruction in input"),
+            "N" => Ok(Instruction::North(val)),
+            "S" => Ok(Instruction::South(val)),
+            "E" => Ok(Instruction::East(val)),
+            "W" => Ok(Instruction::West(val)),
+            "L" => Ok(Instruction::Left(val)),
+            "R" => Ok(Instruction::Right(val)),
+            "F" => Ok(Instruction::Forward(val)),
+            _ => unreachable!("Regex only matches valid instructions"),
         }
     }
 }
 
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (action, val) = match self {
+            Instruction::North(val) => ('N', val),
+            Instruction::South(val) => ('S', val),
+            Instruction::East(val) => ('E', val),
+            Instruction::West(val) => ('W', val),
+            Instruction::Left(val) => ('L', val),
+            Instruction::Right(val) => ('R', val),
+            Instruction::Forward(val) => ('F', val),
+        };
+        write!(f, "{}{}", action, val)
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
 pub enum Direction {
@@ -207,8 +211,11 @@ impl Boat {
 }
 
 #[aoc_generator(day12)]
-pub fn input_generator(input: &str) -> Vec<Instruction> {
-    input.lines().map(Instruction::from_str).collect()
+pub fn input_generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .map(|line| Instruction::from_str(line).map_err(|err| err.within(input, line)))
+        .collect()
 }
 
 #[aoc(day12, part1, naive)]
@@ -228,3 +235,207 @@ pub fn solve_part2_naive(input: &[Instruction]) -> usize {
     }
     boat.get_manhattan_distance()
 }
+
+/// Follow `input` with a `Navigator`, returning how far the ship ends up from where it started
+pub fn navigate<T: Scalar>(
+    input: &[Instruction],
+    steering: Steering,
+) -> Result<T, NavigationError> {
+    let mut navigator = Navigator::new(steering);
+    navigator.run(input)?;
+    Ok(navigator.distance())
+}
+
+#[aoc(day12, part1, vector)]
+pub fn solve_part1_vector(input: &[Instruction]) -> Result<i64, NavigationError> {
+    navigate(input, Steering::Ship)
+}
+
+#[aoc(day12, part1, float)]
+pub fn solve_part1_float(input: &[Instruction]) -> usize {
+    navigate::<f64>(input, Steering::Ship)
+        .expect("Floats can turn by any angle")
+        .round() as usize
+}
+
+#[aoc(day12, part2, vector)]
+pub fn solve_part2_vector(input: &[Instruction]) -> Result<i64, NavigationError> {
+    navigate(input, Steering::Waypoint)
+}
+
+#[aoc(day12, part2, float)]
+pub fn solve_part2_float(input: &[Instruction]) -> usize {
+    navigate::<f64>(input, Steering::Waypoint)
+        .expect("Floats can turn by any angle")
+        .round() as usize
+}
+
+/// Registers day 12 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<Instruction>;
+
+    const DAY: u8 = 12;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::One, Some("vector"), |input| {
+                solve_part1_vector(input)
+                    .expect("Unable to navigate")
+                    .to_string()
+            }),
+            Variant::new(Part::One, Some("float"), |input| {
+                solve_part1_float(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("naive"), |input| {
+                solve_part2_naive(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("vector"), |input| {
+                solve_part2_vector(input)
+                    .expect("Unable to navigate")
+                    .to_string()
+            }),
+            Variant::new(Part::Two, Some("float"), |input| {
+                solve_part2_float(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::navigation::Rotation;
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "F10
+N3
+F7
+R90
+F11";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "25");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "286");
+    }
+
+    #[test]
+    fn rotations() {
+        let waypoint = Vector::new(10, 4);
+        assert_eq!(
+            Rotation::clockwise(90).unwrap().apply(waypoint),
+            Vector::new(4, -10)
+        );
+        assert_eq!(
+            Rotation::clockwise(-90).unwrap().apply(waypoint),
+            Vector::new(-4, 10)
+        );
+        assert_eq!(Rotation::<i64>::clockwise(450), Rotation::clockwise(90));
+        assert_eq!(
+            Rotation::clockwise(-180).unwrap().apply(waypoint),
+            Vector::new(-10, -4)
+        );
+        assert_eq!(Rotation::<i64>::clockwise(45), None);
+
+        let turned = Rotation::clockwise(30)
+            .unwrap()
+            .apply(Vector::new(0.0, 2.0));
+        assert!((turned.x - 1.0).abs() < 1e-9 && (turned.y - 3f64.sqrt()).abs() < 1e-9);
+        // Quarter turns stay exact in float mode
+        assert_eq!(
+            Rotation::clockwise(270)
+                .unwrap()
+                .apply(Vector::new(10.0, 4.0)),
+            Vector::new(-4.0, 10.0)
+        );
+    }
+
+    #[test]
+    fn arbitrary_angles() {
+        let input = input_generator("F10\nR45\nF10\nL135\nF10").unwrap();
+        let mut navigator = Navigator::<i64>::new(Steering::Ship);
+        assert_eq!(
+            navigator.run(&input),
+            Err(NavigationError::UnsupportedAngle {
+                step: 2,
+                instruction: Instruction::Right(45)
+            })
+        );
+        assert_eq!(navigator.position(), Vector::new(10, 0));
+        assert_eq!(navigator.track().len(), 2);
+        assert_eq!(
+            solve_part1_vector(&input).unwrap_err().to_string(),
+            "step 2: `R45` doesn't turn by a multiple of 90 degrees, navigate with floats instead"
+        );
+
+        let mut navigator = Navigator::<f64>::new(Steering::Ship);
+        navigator.run(&input).unwrap();
+        let position = navigator.position();
+        let half = 10.0 / 2f64.sqrt();
+        assert!((position.x - (10.0 + half)).abs() < 1e-9);
+        assert!((position.y - (10.0 - half)).abs() < 1e-9);
+        assert!((navigator.heading().y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn forward_is_constant_time() {
+        let mut navigator = Navigator::<i64>::new(Steering::Waypoint);
+        navigator
+            .apply(&Instruction::Forward(1_000_000_000_000))
+            .unwrap();
+        assert_eq!(
+            navigator.position(),
+            Vector::new(10_000_000_000_000, 1_000_000_000_000)
+        );
+        assert_eq!(navigator.distance(), 11_000_000_000_000);
+    }
+
+    #[test]
+    fn track_log() {
+        let mut navigator = Navigator::<i64>::new(Steering::Waypoint);
+        navigator.run(&input_generator(EXAMPLE).unwrap()).unwrap();
+        let track = navigator.track();
+        assert_eq!(track.len(), 6);
+        assert_eq!(track.points()[0].instruction, None);
+        assert_eq!(
+            track.points()[4],
+            TrackPoint {
+                instruction: Some(Instruction::Right(90)),
+                position: Vector::new(170, 38),
+                heading: Vector::new(4, -10),
+            }
+        );
+
+        assert_eq!(
+            track.to_csv(),
+            "step,instruction,x,y,heading_x,heading_y
+0,,0,0,10,1
+1,F10,100,10,10,1
+2,N3,100,10,10,4
+3,F7,170,38,10,4
+4,R90,170,38,4,-10
+5,F11,214,-72,4,-10
+"
+        );
+
+        let svg = track.to_svg();
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-10.7 -48.7 235.4 131.4\">"
+        ));
+        assert!(svg.contains("<polyline points=\"0,0 100,-10 100,-10 170,-38 170,-38 214,72\""));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
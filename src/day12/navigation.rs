@@ -0,0 +1,408 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+use super::{Direction, Instruction};
+
+/// Numbers the ship can navigate with. Integers keep the puzzle exact but can only turn in multiples of 90 degrees,
+/// floats can turn by any angle.
+pub trait Scalar:
+    Copy
+    + PartialOrd
+    + fmt::Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_distance(distance: usize) -> Self;
+
+    /// Cosine and sine of `degrees`, or `None` if they can't be represented
+    fn cos_sin(degrees: i64) -> Option<(Self, Self)>;
+
+    fn abs(self) -> Self;
+
+    fn to_f64(self) -> f64;
+}
+
+/// Cosine and sine of the quarter turns, which both modes can represent exactly
+fn quarter_turn(degrees: i64) -> Option<(i64, i64)> {
+    match degrees.rem_euclid(360) {
+        0 => Some((1, 0)),
+        90 => Some((0, 1)),
+        180 => Some((-1, 0)),
+        270 => Some((0, -1)),
+        _ => None,
+    }
+}
+
+impl Scalar for i64 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn from_distance(distance: usize) -> Self {
+        distance as i64
+    }
+
+    fn cos_sin(degrees: i64) -> Option<(Self, Self)> {
+        quarter_turn(degrees)
+    }
+
+    fn abs(self) -> Self {
+        i64::abs(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Scalar for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_distance(distance: usize) -> Self {
+        distance as f64
+    }
+
+    fn cos_sin(degrees: i64) -> Option<(Self, Self)> {
+        // Quarter turns are kept exact so the puzzle's own turns don't pick up rounding errors
+        Some(match quarter_turn(degrees) {
+            Some((cos, sin)) => (cos as f64, sin as f64),
+            None => {
+                let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+                (cos, sin)
+            }
+        })
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// A position or direction, with `x` pointing east and `y` pointing north
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vector<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Scalar> Vector<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Vector { x, y }
+    }
+
+    pub fn zero() -> Self {
+        Vector::new(T::ZERO, T::ZERO)
+    }
+
+    /// One step in `direction`
+    pub fn unit(direction: Direction) -> Self {
+        match direction {
+            Direction::North => Vector::new(T::ZERO, T::ONE),
+            Direction::East => Vector::new(T::ONE, T::ZERO),
+            Direction::South => Vector::new(T::ZERO, -T::ONE),
+            Direction::West => Vector::new(-T::ONE, T::ZERO),
+        }
+    }
+
+    pub fn manhattan(self) -> T {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+impl<T: Scalar> Add for Vector<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar> AddAssign for Vector<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Scalar> Sub for Vector<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vector<T> {
+    type Output = Self;
+
+    fn mul(self, scale: T) -> Self {
+        Vector::new(self.x * scale, self.y * scale)
+    }
+}
+
+/// A clockwise turn as a 2x2 matrix, applied to vectors by multiplying them on the right
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation<T> {
+    matrix: [[T; 2]; 2],
+}
+
+impl<T: Scalar> Rotation<T> {
+    /// A clockwise turn by `degrees`, anticlockwise if it's negative. Returns `None` if `T` can't represent it.
+    pub fn clockwise(degrees: i64) -> Option<Self> {
+        let (cos, sin) = T::cos_sin(degrees)?;
+        Some(Rotation {
+            matrix: [[cos, sin], [-sin, cos]],
+        })
+    }
+
+    pub fn apply(&self, vector: Vector<T>) -> Vector<T> {
+        let [[a, b], [c, d]] = self.matrix;
+        Vector::new(a * vector.x + b * vector.y, c * vector.x + d * vector.y)
+    }
+}
+
+/// What the `N`, `S`, `E` and `W` instructions move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Steering {
+    /// Part 1: they move the ship, and the heading is the direction it faces
+    Ship,
+    /// Part 2: they move the waypoint, which is the heading
+    Waypoint,
+}
+
+/// Error returned when navigating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationError {
+    /// The instruction at `step`, counting from 1, turns by an angle the navigator's numbers can't represent
+    UnsupportedAngle {
+        step: usize,
+        instruction: Instruction,
+    },
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedAngle { step, instruction } => write!(
+                f,
+                "step {}: `{}` doesn't turn by a multiple of 90 degrees, navigate with floats instead",
+                step, instruction
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NavigationError {}
+
+/// Where the ship was and where it was heading, after `instruction`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint<T> {
+    /// `None` for where the ship started
+    pub instruction: Option<Instruction>,
+    pub position: Vector<T>,
+    pub heading: Vector<T>,
+}
+
+/// Every position and heading of the ship, from where it started to after the latest instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    points: Vec<TrackPoint<T>>,
+}
+
+impl<T: Scalar> Track<T> {
+    pub fn points(&self) -> &[TrackPoint<T>] {
+        &self.points
+    }
+
+    /// Number of points, one more than the number of instructions followed
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// One row per point, numbered from 0 for the start, with the instruction that led there
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,instruction,x,y,heading_x,heading_y\n");
+        for (step, point) in self.points.iter().enumerate() {
+            let instruction = point
+                .instruction
+                .map(|instruction| instruction.to_string())
+                .unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                step,
+                instruction,
+                point.position.x,
+                point.position.y,
+                point.heading.x,
+                point.heading.y
+            ));
+        }
+        csv
+    }
+
+    /// The ship's course as an SVG polyline with north at the top, marking where it started
+    pub fn to_svg(&self) -> String {
+        let positions: Vec<(f64, f64)> = self
+            .points
+            .iter()
+            // Subtracting rather than negating, so 0 doesn't turn into -0
+            .map(|point| (point.position.x.to_f64(), 0.0 - point.position.y.to_f64()))
+            .collect();
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0f64, 0f64, 0f64, 0f64);
+        for (x, y) in &positions {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        // Leave a border, and keep a course that doesn't go anywhere visible
+        let margin = ((max_x - min_x).max(max_y - min_y) / 20.0).max(1.0);
+
+        let points: Vec<String> = positions
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect();
+        let (start_x, start_y) = positions.first().copied().unwrap_or((0.0, 0.0));
+
+        format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+                "  <polyline points=\"{}\" fill=\"none\" stroke=\"#1f6f8b\" stroke-width=\"2\" ",
+                "vector-effect=\"non-scaling-stroke\"/>\n",
+                "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"#d64f3c\"/>\n",
+                "</svg>\n"
+            ),
+            min_x - margin,
+            min_y - margin,
+            max_x - min_x + 2.0 * margin,
+            max_y - min_y + 2.0 * margin,
+            points.join(" "),
+            start_x,
+            start_y,
+            margin / 2.0
+        )
+    }
+}
+
+/// A ship that follows instructions using vector arithmetic. Turning multiplies the heading by a rotation matrix, and
+/// `Forward` adds a multiple of the heading, so every instruction takes the same time however large its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Navigator<T> {
+    steering: Steering,
+    position: Vector<T>,
+    heading: Vector<T>,
+    track: Track<T>,
+}
+
+impl<T: Scalar> Navigator<T> {
+    /// A ship at the origin heading the way the puzzle starts it: facing east for `Steering::Ship`, or with the
+    /// waypoint 10 east and 1 north for `Steering::Waypoint`
+    pub fn new(steering: Steering) -> Self {
+        let heading = match steering {
+            Steering::Ship => Vector::new(T::ONE, T::ZERO),
+            Steering::Waypoint => Vector::new(T::from_distance(10), T::ONE),
+        };
+        Self::with_heading(steering, heading)
+    }
+
+    /// A ship at the origin with its own starting heading
+    pub fn with_heading(steering: Steering, heading: Vector<T>) -> Self {
+        let position = Vector::zero();
+        Navigator {
+            steering,
+            position,
+            heading,
+            track: Track {
+                points: vec![TrackPoint {
+                    instruction: None,
+                    position,
+                    heading,
+                }],
+            },
+        }
+    }
+
+    pub fn steering(&self) -> Steering {
+        self.steering
+    }
+
+    pub fn position(&self) -> Vector<T> {
+        self.position
+    }
+
+    /// Direction the ship faces, or the waypoint relative to the ship
+    pub fn heading(&self) -> Vector<T> {
+        self.heading
+    }
+
+    pub fn track(&self) -> &Track<T> {
+        &self.track
+    }
+
+    /// Manhattan distance from where the ship started
+    pub fn distance(&self) -> T {
+        (self.position - self.track.points[0].position).manhattan()
+    }
+
+    /// Follow one instruction and log where it leaves the ship. A turn the numbers can't represent changes nothing.
+    pub fn apply(&mut self, instruction: &Instruction) -> Result<(), NavigationError> {
+        let degrees = match *instruction {
+            Instruction::Left(degrees) => Some(-(degrees as i64)),
+            Instruction::Right(degrees) => Some(degrees as i64),
+            _ => None,
+        };
+        if let Some(degrees) = degrees {
+            let rotation =
+                Rotation::clockwise(degrees).ok_or(NavigationError::UnsupportedAngle {
+                    step: self.track.len(),
+                    instruction: *instruction,
+                })?;
+            self.heading = rotation.apply(self.heading);
+        }
+
+        let cardinal = match *instruction {
+            Instruction::North(dist) => Some((Direction::North, dist)),
+            Instruction::South(dist) => Some((Direction::South, dist)),
+            Instruction::East(dist) => Some((Direction::East, dist)),
+            Instruction::West(dist) => Some((Direction::West, dist)),
+            _ => None,
+        };
+        if let Some((direction, dist)) = cardinal {
+            let offset = Vector::unit(direction) * T::from_distance(dist);
+            match self.steering {
+                Steering::Ship => self.position += offset,
+                Steering::Waypoint => self.heading += offset,
+            }
+        }
+
+        if let Instruction::Forward(dist) = *instruction {
+            self.position += self.heading * T::from_distance(dist);
+        }
+
+        self.track.points.push(TrackPoint {
+            instruction: Some(*instruction),
+            position: self.position,
+            heading: self.heading,
+        });
+        Ok(())
+    }
+
+    /// Follow every instruction in order, stopping at the first one that can't be followed
+    pub fn run(&mut self, instructions: &[Instruction]) -> Result<(), NavigationError> {
+        instructions
+            .iter()
+            .try_for_each(|instruction| self.apply(instruction))
+    }
+}
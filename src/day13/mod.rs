@@ -2,6 +2,9 @@ use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 use rayon::prelude::*;
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
 #[derive(Debug, Clone)]
 pub struct Schedule {
     leave_time: usize,
@@ -79,19 +82,33 @@ impl Schedule {
 }
 
 #[aoc_generator(day13)]
-pub fn input_generator(input: &str) -> Schedule {
-    let splits: Vec<&str> = input.split('\n').collect();
+pub fn input_generator(input: &str) -> Result<Schedule, ParseError> {
+    let mut splits = input.lines();
 
-    let leave_time = splits[0].parse().unwrap();
-    let bus_options = splits[1]
+    let leave_time = splits
+        .next()
+        .ok_or_else(|| ParseError::new(13, input, input, ParseErrorKind::Missing("departure time")))
+        .and_then(|time| {
+            time.parse()
+                .map_err(|_| ParseError::new(13, input, time, ParseErrorKind::InvalidNumber))
+        })?;
+    let bus_options = splits
+        .next()
+        .ok_or_else(|| ParseError::new(13, input, input, ParseErrorKind::Missing("bus list")))?
         .split(',')
-        .map(|c| c.parse::<usize>().unwrap_or(0))
-        .collect();
+        // Buses that are out of service are marked with `x`, we store them as 0
+        .map(|c| match c {
+            "x" => Ok(0),
+            _ => c
+                .parse::<usize>()
+                .map_err(|_| ParseError::new(13, input, c, ParseErrorKind::InvalidNumber)),
+        })
+        .collect::<Result<_, _>>()?;
 
-    Schedule {
+    Ok(Schedule {
         leave_time,
         bus_options,
-    }
+    })
 }
 
 #[aoc(day13, part1, naive)]
@@ -189,3 +206,54 @@ pub fn solve_part1_naive(input: &Schedule) -> usize {
 pub fn solve_part2_crt(input: &Schedule) -> usize {
     input.get_first_unique_crt_solution()
 }
+
+/// Registers day 13 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Schedule;
+
+    const DAY: u8 = 13;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("crt"), |input| {
+                solve_part2_crt(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>("939\n7,13,x,x,59,x,31,19", Part::One, "295");
+    }
+
+    #[test]
+    fn part2_examples() {
+        for (buses, expected) in [
+            ("7,13,x,x,59,x,31,19", "1068781"),
+            ("17,x,13,19", "3417"),
+            ("67,7,59,61", "754018"),
+            ("67,x,7,59,61", "779210"),
+            ("67,7,x,59,61", "1261476"),
+            ("1789,37,47,1889", "1202161486"),
+        ]
+        .iter()
+        {
+            assert_variants::<Solution>(&format!("939\n{}", buses), Part::Two, expected);
+        }
+    }
+}
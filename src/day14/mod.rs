@@ -1,9 +1,12 @@
 use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
 lazy_static! {
-    static ref MASK: Regex = Regex::new(r"mask = ([01X]+)").unwrap();
-    static ref MEM: Regex = Regex::new(r"mem\[(\d+)\] = (\d+)").unwrap();
+    static ref MASK: Regex = Regex::new(r"^mask = ([01X]+)$").unwrap();
+    static ref MEM: Regex = Regex::new(r"^mem\[(\d+)\] = (\d+)$").unwrap();
 }
 
 #[derive(Debug, Clone)]
@@ -13,18 +16,26 @@ pub enum Op {
 }
 
 impl Op {
-    pub fn from_str(input: &str) -> Self {
-        if MASK.is_match(input) {
-            let cap = MASK.captures(input).unwrap();
-            Op::Mask(cap[1].to_string())
-        } else if MEM.is_match(input) {
-            let cap = MEM.captures(input).unwrap();
-            Op::Mem(
-                cap[1].parse::<usize>().unwrap(),
-                cap[2].parse::<usize>().unwrap(),
-            )
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let parse = |val: &str| {
+            val.parse::<usize>()
+                .map_err(|_| ParseError::new(14, input, val, ParseErrorKind::InvalidNumber))
+        };
+
+        if let Some(cap) = MASK.captures(input) {
+            Ok(Op::Mask(cap[1].to_string()))
+        } else if let Some(cap) = MEM.captures(input) {
+            Ok(Op::Mem(
+                parse(cap.get(1).unwrap().as_str())?,
+                parse(cap.get(2).unwrap().as_str())?,
+            ))
         } else {
-            unreachable!("Invalid input given to Op::from_str")
+            Err(ParseError::new(
+                14,
+                input,
+                input,
+                ParseErrorKind::Malformed("`mask = <bits>` or `mem[<addr>] = <value>`"),
+            ))
         }
     }
 }
@@ -136,8 +147,11 @@ impl BitmaskSystem {
 }
 
 #[aoc_generator(day14)]
-pub fn input_generator(input: &str) -> Vec<Op> {
-    input.lines().map(Op::from_str).collect()
+pub fn input_generator(input: &str) -> Result<Vec<Op>, ParseError> {
+    input
+        .lines()
+        .map(|line| Op::from_str(line).map_err(|err| err.within(input, line)))
+        .collect()
 }
 
 #[aoc(day14, part1, naive)]
@@ -157,3 +171,52 @@ pub fn solve_part2_naive(input: &[Op]) -> usize {
     }
     bitsys.sum_memory()
 }
+
+/// Registers day 14 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<Op>;
+
+    const DAY: u8 = 14;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("naive"), |input| {
+                solve_part2_naive(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn part1_example() {
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0";
+        assert_variants::<Solution>(input, Part::One, "165");
+    }
+
+    #[test]
+    fn part2_example() {
+        // The part 1 example would write to 2^34 addresses in part 2, so part 2 has its own example
+        let input = "mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1";
+        assert_variants::<Solution>(input, Part::Two, "208");
+    }
+}
@@ -1,6 +1,9 @@
 use fnv::{FnvHashMap, FnvHashSet};
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
 #[derive(Debug, Clone)]
 pub struct MemoryGame {
     turn: usize,
@@ -37,8 +40,14 @@ impl MemoryGame {
 }
 
 #[aoc_generator(day15)]
-pub fn input_generator(input: &str) -> Vec<usize> {
-    input.split(',').map(|x| x.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<usize>, ParseError> {
+    input
+        .split(',')
+        .map(|x| {
+            x.parse()
+                .map_err(|_| ParseError::new(15, input, x, ParseErrorKind::InvalidNumber))
+        })
+        .collect()
 }
 
 #[aoc(day15, part1, naive)]
@@ -54,3 +63,55 @@ pub fn solve_part2_naive(input: &[usize]) -> usize {
     input.iter().for_each(|x| mem_game.insert_value(*x));
     mem_game.generate_until(30000000)
 }
+
+/// Registers day 15 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<usize>;
+
+    const DAY: u8 = 15;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("naive"), |input| {
+                solve_part2_naive(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn part1_examples() {
+        for (input, expected) in [
+            ("0,3,6", "436"),
+            ("1,3,2", "1"),
+            ("2,1,3", "10"),
+            ("1,2,3", "27"),
+            ("2,3,1", "78"),
+            ("3,2,1", "438"),
+            ("3,1,2", "1836"),
+        ]
+        .iter()
+        {
+            assert_variants::<Solution>(input, Part::One, expected);
+        }
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>("0,3,6", Part::Two, "175594");
+    }
+}
@@ -2,8 +2,11 @@ use fnv::{FnvHashMap, FnvHashSet};
 use regex::Regex;
 use std::ops::RangeInclusive;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
 lazy_static! {
-    static ref FIELDS: Regex = Regex::new(r"([\w ]+): (\d+)-(\d+) or (\d+)-(\d+)").unwrap();
+    static ref FIELDS: Regex = Regex::new(r"^([\w ]+): (\d+)-(\d+) or (\d+)-(\d+)$").unwrap();
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -18,23 +21,59 @@ pub struct TrainTickets {
     other_tickets: FnvHashSet<Ticket>,
 }
 
+/// Parse a comma separated list of values into a `Ticket`
+fn parse_ticket(input: &str, line: &str) -> Result<Ticket, ParseError> {
+    let values = line
+        .split(',')
+        .map(|x| {
+            x.parse::<usize>()
+                .map_err(|_| ParseError::new(16, input, x, ParseErrorKind::InvalidNumber))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Ticket { values })
+}
+
 #[aoc_generator(day16)]
-pub fn input_generator(input: &str) -> TrainTickets {
+pub fn input_generator(input: &str) -> Result<TrainTickets, ParseError> {
     let groups: Vec<&str> = input.split("\n\n").collect();
+    if groups.len() != 3 {
+        return Err(ParseError::new(
+            16,
+            input,
+            input,
+            ParseErrorKind::Malformed(
+                "field rules, your ticket and nearby tickets separated by blank lines",
+            ),
+        ));
+    }
+
+    let parse = |val: &str| {
+        val.parse::<usize>()
+            .map_err(|_| ParseError::new(16, input, val, ParseErrorKind::InvalidNumber))
+    };
 
     let mut fields = FnvHashMap::default();
-    for field in FIELDS.captures_iter(groups[0]) {
+    for line in groups[0].lines() {
+        let field = FIELDS.captures(line).ok_or_else(|| {
+            ParseError::new(
+                16,
+                input,
+                line,
+                ParseErrorKind::Malformed("`<name>: <min>-<max> or <min>-<max>`"),
+            )
+        })?;
         let mut ranges: FnvHashSet<RangeInclusive<usize>> = FnvHashSet::default();
 
         let first_range = RangeInclusive::new(
-            field[2].parse::<usize>().unwrap(),
-            field[3].parse::<usize>().unwrap(),
+            parse(field.get(2).unwrap().as_str())?,
+            parse(field.get(3).unwrap().as_str())?,
         );
         ranges.insert(first_range);
 
         let second_range = RangeInclusive::new(
-            field[4].parse::<usize>().unwrap(),
-            field[5].parse::<usize>().unwrap(),
+            parse(field.get(4).unwrap().as_str())?,
+            parse(field.get(5).unwrap().as_str())?,
         );
         ranges.insert(second_range);
 
@@ -42,31 +81,29 @@ pub fn input_generator(input: &str) -> TrainTickets {
     }
 
     let my_ticket: Ticket = match groups[1].splitn(2, "\n").skip(1).next() {
-        Some(str_ticket) => {
-            let mut ticket_vec = Vec::new();
-            str_ticket
-                .split(',')
-                .for_each(|x| ticket_vec.push(x.parse::<usize>().unwrap()));
-            Ticket { values: ticket_vec }
+        Some(str_ticket) => parse_ticket(input, str_ticket)?,
+        None => {
+            return Err(ParseError::new(
+                16,
+                input,
+                groups[1],
+                ParseErrorKind::Missing("your ticket"),
+            ))
         }
-        None => unreachable!(),
     };
 
     let mut other_tickets = FnvHashSet::default();
-    groups[2].splitn(2, "\n").skip(1).for_each(|str_ticket| {
-        str_ticket.lines().for_each(|line| {
-            let mut ticket_vec = Vec::new();
-            line.split(',')
-                .for_each(|x| ticket_vec.push(x.parse::<usize>().unwrap()));
-            other_tickets.insert(Ticket { values: ticket_vec });
-        });
-    });
-
-    TrainTickets {
+    if let Some(str_ticket) = groups[2].splitn(2, "\n").skip(1).next() {
+        for line in str_ticket.lines() {
+            other_tickets.insert(parse_ticket(input, line)?);
+        }
+    }
+
+    Ok(TrainTickets {
         fields,
         my_ticket,
         other_tickets,
-    }
+    })
 }
 
 #[aoc(day16, part1, naive)]
@@ -163,3 +200,68 @@ fn remove_duplicate_indexes(
 
     concrete_results
 }
+
+/// Registers day 16 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = TrainTickets;
+
+    const DAY: u8 = 16;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("naive"), |input| {
+                solve_part2_naive(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn part1_example() {
+        let input = "class: 1-3 or 5-7
+row: 6-11 or 33-44
+seat: 13-40 or 45-50
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12";
+        assert_variants::<Solution>(input, Part::One, "71");
+    }
+
+    #[test]
+    fn part2_example() {
+        // The puzzle's example has no departure fields, so two of them have been renamed. Class is the second field
+        // and seat is the third, giving 12 * 13.
+        let input = "departure class: 0-1 or 4-19
+row: 0-5 or 8-19
+departure seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9";
+        assert_variants::<Solution>(input, Part::Two, "156");
+    }
+}
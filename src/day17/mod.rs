@@ -4,6 +4,9 @@ use factorial::Factorial;
 use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ConwayCube {
     Active,
@@ -11,11 +14,11 @@ pub enum ConwayCube {
 }
 
 impl ConwayCube {
-    fn from_char(input: &char) -> Self {
+    fn from_char(input: &char) -> Result<Self, ParseErrorKind> {
         match input {
-            '.' => Self::Inactive,
-            '#' => Self::Active,
-            _ => unreachable!("Unhandled ConwayCube activity character"),
+            '.' => Ok(Self::Inactive),
+            '#' => Ok(Self::Active),
+            _ => Err(ParseErrorKind::UnexpectedChar(*input)),
         }
     }
 
@@ -47,6 +50,38 @@ pub struct PocketDimension {
 }
 
 impl PocketDimension {
+    /// Run one cycle of the simulation
+    pub fn step(&mut self) {
+        self.populate_all_neighbors();
+
+        let coords: Vec<Coords> = self.cubes.keys().copied().collect();
+        for coord in coords {
+            self.update_cube(&coord);
+        }
+
+        self.apply_update_map();
+    }
+
+    /// Returns `true` if the cube at `(x, y, z, w)` is active
+    pub fn is_active(&self, x: i32, y: i32, z: i32, w: i32) -> bool {
+        matches!(
+            self.cubes.get(&Coords(x, y, z, w)),
+            Some(ConwayCube::Active)
+        )
+    }
+
+    /// Coordinates of every active cube as `(x, y, z, w)`, in order
+    pub fn active_cubes(&self) -> Vec<(i32, i32, i32, i32)> {
+        let mut active: Vec<(i32, i32, i32, i32)> = self
+            .cubes
+            .iter()
+            .filter(|(_, cube)| cube.is_active())
+            .map(|(coords, _)| (coords.0, coords.1, coords.2, coords.3))
+            .collect();
+        active.sort_unstable();
+        active
+    }
+
     fn update_cube(&mut self, coords: &Coords) {
         let active_neighbors = self.count_active_neighbors(coords);
 
@@ -304,31 +339,31 @@ pub fn get_neighbors_4d(input: Coords) -> [Coords; 80] {
 // }
 
 #[aoc_generator(day17, part2)]
-pub fn input_generator_p2(input: &str) -> PocketDimension {
+pub fn input_generator_p2(input: &str) -> Result<PocketDimension, ParseError> {
     let mut cubes = FnvHashMap::default();
     let mut neighborhood_map = FnvHashMap::default();
     let mut update_map = FnvHashMap::default();
 
-    input.lines().enumerate().for_each(|(idx, line)| {
-        line.chars().enumerate().for_each(|(x_idx, c)| {
-            cubes.insert(
-                Coords(x_idx as i32, idx as i32, 0, 0),
-                ConwayCube::from_char(&c),
-            );
+    for (idx, line) in input.lines().enumerate() {
+        for (x_idx, (char_idx, c)) in line.char_indices().enumerate() {
+            let cube = ConwayCube::from_char(&c).map_err(|kind| {
+                ParseError::new(17, input, &line[char_idx..char_idx + c.len_utf8()], kind)
+            })?;
+            cubes.insert(Coords(x_idx as i32, idx as i32, 0, 0), cube);
             update_map.insert(Coords(x_idx as i32, idx as i32, 0, 0), false);
-        });
-    });
+        }
+    }
 
     cubes.iter().for_each(|(coords, _)| {
         let neighbors = get_neighbors_4d(*coords);
         neighborhood_map.insert(*coords, neighbors);
     });
 
-    PocketDimension {
+    Ok(PocketDimension {
         cubes,
         neighborhood_map,
         update_map,
-    }
+    })
 }
 
 // #[aoc(day17, part1, naive)]
@@ -353,14 +388,49 @@ pub fn solve_part2_naive(input: &PocketDimension) -> usize {
     let mut pd = input.clone();
 
     for _ in 0..6 {
-        pd.populate_all_neighbors();
+        pd.step();
+    }
+    return pd.count_active_cubes();
+}
 
-        let coords: Vec<Coords> = pd.cubes.keys().map(|x| *x).collect();
-        for coord in coords {
-            pd.update_cube(&coord);
-        }
+/// Registers day 17 with the crate's own runner
+pub struct Solution;
 
-        pd.apply_update_map();
+impl Solver for Solution {
+    type Input = PocketDimension;
+
+    const DAY: u8 = 17;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator_p2(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![Variant::new(Part::Two, Some("naive"), |input| {
+            solve_part2_naive(input).to_string()
+        })]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(".#.\n..#\n###", Part::Two, "848");
+    }
+
+    #[test]
+    fn active_cubes() {
+        let mut pd = input_generator_p2(".#.\n..#\n###").unwrap();
+        assert!(pd.is_active(1, 0, 0, 0));
+        assert!(!pd.is_active(0, 0, 0, 0));
+        assert_eq!(pd.active_cubes().len(), 5);
+
+        pd.step();
+        assert_eq!(pd.active_cubes().len(), 29);
+        assert!(pd.is_active(0, 1, -1, -1));
     }
-    return pd.count_active_cubes();
 }
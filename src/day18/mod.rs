@@ -5,6 +5,9 @@ use factorial::Factorial;
 use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
 use regex::{Captures, Regex};
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
 lazy_static! {
     static ref MATH: Regex = Regex::new(r"^(\d+) (\*|\+) (\d+)").unwrap();
     static ref MATH_PLUS: Regex = Regex::new(r"(\d+) \+ (\d+)").unwrap();
@@ -93,8 +96,21 @@ pub fn resolve_parens(input: &str, eval_func: fn(&str) -> usize) -> String {
 }
 
 #[aoc_generator(day18)]
-pub fn input_generator(input: &str) -> String {
-    input.to_string()
+pub fn input_generator(input: &str) -> Result<String, ParseError> {
+    // The string based evaluators assume they only ever see numbers, operators and parenthesis
+    if let Some((idx, c)) = input
+        .char_indices()
+        .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, ' ' | '\n' | '+' | '*' | '(' | ')')))
+    {
+        return Err(ParseError::new(
+            18,
+            input,
+            &input[idx..idx + c.len_utf8()],
+            ParseErrorKind::UnexpectedChar(c),
+        ));
+    }
+
+    Ok(input.to_string())
 }
 
 #[aoc(day18, part1, naive)]
@@ -138,3 +154,78 @@ pub fn solve_part2_naive(input: &String) -> usize {
         })
         .sum()
 }
+
+/// Registers day 18 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = String;
+
+    const DAY: u8 = 18;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::One, Some("eval"), |input| {
+                solve_part1_eval(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("naive"), |input| {
+                solve_part2_naive(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLES: [(&str, &str, &str); 6] = [
+        ("1 + 2 * 3 + 4 * 5 + 6", "71", "231"),
+        ("1 + (2 * 3) + (4 * (5 + 6))", "51", "51"),
+        ("2 * 3 + (4 * 5)", "26", "46"),
+        ("5 + (8 * 3 + 9 + 3 * 4 * 3)", "437", "1445"),
+        (
+            "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))",
+            "12240",
+            "669060",
+        ),
+        (
+            "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+            "13632",
+            "23340",
+        ),
+    ];
+
+    #[test]
+    fn part1_examples() {
+        for (expression, expected, _) in EXAMPLES.iter() {
+            assert_variants::<Solution>(expression, Part::One, expected);
+        }
+    }
+
+    #[test]
+    fn part2_examples() {
+        for (expression, _, expected) in EXAMPLES.iter() {
+            assert_variants::<Solution>(expression, Part::Two, expected);
+        }
+    }
+
+    #[test]
+    fn sums_every_line() {
+        let input = EXAMPLES
+            .iter()
+            .map(|(expression, _, _)| *expression)
+            .collect::<Vec<&str>>()
+            .join("\n");
+        assert_variants::<Solution>(&input, Part::One, "26457");
+        assert_variants::<Solution>(&input, Part::Two, "694173");
+    }
+}
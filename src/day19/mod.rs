@@ -0,0 +1,237 @@
+use fnv::FnvHashMap;
+use regex::Regex;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+lazy_static! {
+    static ref RULE: Regex = Regex::new(r"^(\d+): (.+)$").unwrap();
+    static ref LITERAL: Regex = Regex::new(r#"^"(.)"$"#).unwrap();
+}
+
+/// A single rule of the message grammar
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// Matches exactly one character
+    Literal(char),
+    /// Matches if any of the sequences of sub-rules match
+    Alternatives(Vec<Vec<usize>>),
+}
+
+/// All of the rules and the received messages to check against them
+#[derive(Debug, Clone)]
+pub struct MessageData {
+    rules: FnvHashMap<usize, Rule>,
+    messages: Vec<String>,
+}
+
+impl MessageData {
+    /// Check if the whole of `message` matches `rule`
+    pub fn is_match(&self, rule: usize, message: &str) -> bool {
+        let message: Vec<char> = message.chars().collect();
+
+        self.match_rule(rule, &message, 0).contains(&message.len())
+    }
+
+    /// Match `rule` against `message` starting at `start`, returning every position the match could end at. Returning
+    /// all of the possible ends instead of the first is what lets the looping rules from Part 2 work, since a looping
+    /// rule can consume a different amount of the message depending on what follows it.
+    fn match_rule(&self, rule: usize, message: &[char], start: usize) -> Vec<usize> {
+        match self.rules.get(&rule) {
+            Some(Rule::Literal(c)) => {
+                if message.get(start) == Some(c) {
+                    vec![start + 1]
+                } else {
+                    vec![]
+                }
+            }
+            Some(Rule::Alternatives(alternatives)) => {
+                let mut ends = Vec::new();
+                for sequence in alternatives {
+                    // Walk the sequence, keeping track of every position we could be at after each sub-rule
+                    let mut positions = vec![start];
+                    for sub_rule in sequence {
+                        positions = positions
+                            .iter()
+                            // Every rule consumes at least one character, so we can't match past the end. This is
+                            // also what stops the recursive rules from looping forever.
+                            .filter(|position| **position < message.len())
+                            .flat_map(|position| self.match_rule(*sub_rule, message, *position))
+                            .collect();
+
+                        if positions.is_empty() {
+                            break;
+                        }
+                    }
+                    ends.extend(positions);
+                }
+                ends
+            }
+            None => vec![],
+        }
+    }
+
+    /// Count the messages that completely match rule 0
+    pub fn count_matches(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|message| self.is_match(0, message))
+            .count()
+    }
+}
+
+/// Parse the right hand side of a rule, either a quoted character or alternatives of rule numbers
+fn parse_rule(input: &str, body: &str) -> Result<Rule, ParseError> {
+    if let Some(cap) = LITERAL.captures(body) {
+        return Ok(Rule::Literal(cap[1].chars().next().unwrap()));
+    }
+
+    body.split('|')
+        .map(|sequence| {
+            sequence
+                .split_whitespace()
+                .map(|rule| {
+                    rule.parse::<usize>().map_err(|_| {
+                        ParseError::new(19, input, rule, ParseErrorKind::InvalidNumber)
+                    })
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()
+        .map(Rule::Alternatives)
+}
+
+#[aoc_generator(day19)]
+pub fn input_generator(input: &str) -> Result<MessageData, ParseError> {
+    let mut sections = input.splitn(2, "\n\n");
+    let rule_section = sections.next().unwrap();
+    let messages = sections
+        .next()
+        .ok_or_else(|| ParseError::new(19, input, input, ParseErrorKind::Missing("messages")))?
+        .lines()
+        .map(String::from)
+        .collect();
+
+    let mut rules = FnvHashMap::default();
+    for line in rule_section.lines() {
+        let cap = RULE.captures(line).ok_or_else(|| {
+            ParseError::new(
+                19,
+                input,
+                line,
+                ParseErrorKind::Malformed("`<id>: <rules>` or `<id>: \"<char>\"`"),
+            )
+        })?;
+
+        let id = cap.get(1).unwrap().as_str();
+        let id = id
+            .parse::<usize>()
+            .map_err(|_| ParseError::new(19, input, id, ParseErrorKind::InvalidNumber))?;
+
+        rules.insert(id, parse_rule(input, cap.get(2).unwrap().as_str())?);
+    }
+
+    Ok(MessageData { rules, messages })
+}
+
+#[aoc(day19, part1)]
+pub fn solve_part1(input: &MessageData) -> usize {
+    input.count_matches()
+}
+
+#[aoc(day19, part2)]
+pub fn solve_part2(input: &MessageData) -> usize {
+    let mut data = input.clone();
+    // 8: 42 | 42 8
+    data.rules
+        .insert(8, Rule::Alternatives(vec![vec![42], vec![42, 8]]));
+    // 11: 42 31 | 42 11 31
+    data.rules
+        .insert(11, Rule::Alternatives(vec![vec![42, 31], vec![42, 11, 31]]));
+
+    data.count_matches()
+}
+
+/// Registers day 19 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = MessageData;
+
+    const DAY: u8 = 19;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = r#"42: 9 14 | 10 1
+9: 14 27 | 1 26
+10: 23 14 | 28 1
+1: "a"
+11: 42 31
+5: 1 14 | 15 1
+19: 14 1 | 14 14
+12: 24 14 | 19 1
+16: 15 1 | 14 14
+31: 14 17 | 1 13
+6: 14 14 | 1 14
+2: 1 24 | 14 4
+0: 8 11
+13: 14 3 | 1 12
+15: 1 | 14
+17: 14 2 | 1 7
+23: 25 1 | 22 14
+28: 16 1
+4: 1 1
+20: 14 14 | 1 15
+3: 5 14 | 16 1
+27: 1 6 | 14 18
+14: "b"
+21: 14 1 | 1 14
+25: 1 1 | 1 14
+22: 14 14
+8: 42
+26: 14 22 | 1 20
+18: 15 15
+7: 14 5 | 1 21
+24: 14 1
+
+abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+bbabbbbaabaabba
+babbbbaabbbbbabbbbbbaabaaabaaa
+aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+bbbbbbbaaaabbbbaaabbabaaa
+bbbababbbbaaaaaaaabbababaaababaabab
+ababaaaaaabaaab
+ababaaaaabbbaba
+baabbaaaabbaaaababbaababb
+abbbbabbbbaaaababbbbbbaaaababb
+aaaaabbaabaaaaababaa
+aaaabbaaaabbaaa
+aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+babaaabbbaaabaababbaabababaaab
+aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"#;
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "3");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "12");
+    }
+}
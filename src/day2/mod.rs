@@ -1,7 +1,17 @@
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod policy;
+
+pub use policy::{
+    CharClass, CharCount, CharPositions, ClassCount, EntryPolicy, EntryReport, MinLength,
+    PasswordPolicy, PolicyFailure, RegexPolicy, Validator,
+};
+
 lazy_static! {
-    static ref PASSWORD_ENTRY_REGEX: Regex = Regex::new(r"(\d+)-(\d+) (.): (\w+)").unwrap();
+    static ref PASSWORD_ENTRY_REGEX: Regex = Regex::new(r"^(\d+)-(\d+) (.): (\S+)$").unwrap();
 }
 
 #[derive(Debug, Clone)]
@@ -13,78 +23,206 @@ pub struct PasswordEntry {
 }
 
 impl PasswordEntry {
-    pub fn from_str(input: &str) -> Self {
-        let captures = PASSWORD_ENTRY_REGEX
-            .captures(input)
-            .expect("Error parsing input with regex");
-
-        PasswordEntry {
-            min: captures
-                .get(1)
-                .expect("Missing match for minimum value")
-                .as_str()
-                .parse()
-                .expect("Error parsing regex value for minimum value"),
-            max: captures
-                .get(2)
-                .expect("Missing match for maximum value")
-                .as_str()
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let captures = PASSWORD_ENTRY_REGEX.captures(input).ok_or_else(|| {
+            ParseError::new(
+                2,
+                input,
+                input,
+                ParseErrorKind::Malformed("`<min>-<max> <char>: <password>`"),
+            )
+        })?;
+
+        let parse_limit = |idx: usize| {
+            let limit = captures.get(idx).unwrap().as_str();
+            limit
                 .parse()
-                .expect("Error parsing regex value for maximum value"),
-            checked_char: captures
-                .get(3)
-                .expect("Missing match for check_char value")
-                .as_str()
-                .chars()
-                .next()
-                .expect("Error parsing regex value for checked_char"),
-            password: captures
-                .get(4)
-                .expect("Missing match for password value")
-                .as_str()
-                .to_string(),
+                .map_err(|_| ParseError::new(2, input, limit, ParseErrorKind::InvalidNumber))
+        };
+
+        Ok(PasswordEntry {
+            min: parse_limit(1)?,
+            max: parse_limit(2)?,
+            // The regex guarantees a single character here
+            checked_char: captures[3].chars().next().unwrap(),
+            password: captures[4].to_string(),
+        })
+    }
+
+    /// The sled rental policy this entry describes, `checked_char` has to appear `min` to `max` times
+    pub fn sled_rental_policy(&self) -> CharCount {
+        CharCount {
+            checked_char: self.checked_char,
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    /// The Official Toboggan Corporate Policy this entry describes, `checked_char` has to be at exactly one of the
+    /// 1-based positions `min` and `max`
+    pub fn toboggan_policy(&self) -> CharPositions {
+        CharPositions {
+            checked_char: self.checked_char,
+            first: self.min,
+            second: self.max,
         }
     }
 
     /// Check if the number of occurrences of `check_char` in `password` is
     /// greater than or equal to `min` and less than or equal to `max`
     pub fn is_pass_within_limits(&self) -> bool {
-        let chars = self
-            .password
-            .chars()
-            .filter(|&c| c == self.checked_char)
-            .count();
-
-        self.min <= chars && chars <= self.max
+        self.sled_rental_policy().check(&self.password).is_ok()
     }
 
     /// Check if character `password[min-1]` is equal to `checked_char` XOR if
-    /// character `password[max-1]` is equal to `checked_char`
+    /// character `password[max-1]` is equal to `checked_char`. Positions past the end of the password never match.
     pub fn do_pass_chars_match(&self) -> bool {
-        (self.password.as_bytes()[self.min - 1] == self.checked_char as u8)
-            ^ (self.password.as_bytes()[self.max - 1] == self.checked_char as u8)
+        self.toboggan_policy().check(&self.password).is_ok()
     }
 }
 
 #[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<PasswordEntry> {
-    let vec: Vec<PasswordEntry> = input.lines().map(PasswordEntry::from_str).collect();
-
-    vec
+pub fn input_generator(input: &str) -> Result<Vec<PasswordEntry>, ParseError> {
+    input
+        .lines()
+        .map(|line| PasswordEntry::from_str(line).map_err(|err| err.within(input, line)))
+        .collect()
 }
 
 #[aoc(day2, part1)]
 pub fn solve_part1(input: &Vec<PasswordEntry>) -> usize {
-    input
-        .iter()
-        .filter(|pw| pw.is_pass_within_limits() == true)
-        .count()
+    let validator = Validator::sled_rental();
+    input.iter().filter(|pw| validator.is_valid(pw)).count()
 }
 
 #[aoc(day2, part2)]
 pub fn solve_part2(input: &Vec<PasswordEntry>) -> usize {
-    input
-        .iter()
-        .filter(|pw| pw.do_pass_chars_match() == true)
-        .count()
+    let validator = Validator::toboggan();
+    input.iter().filter(|pw| validator.is_valid(pw)).count()
+}
+
+/// Registers day 2 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<PasswordEntry>;
+
+    const DAY: u8 = 2;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "1-3 a: abcde
+1-3 b: cdefg
+2-9 c: ccccccccc";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "2");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "1");
+    }
+
+    #[test]
+    fn positions_past_the_end() {
+        let entry = PasswordEntry::from_str("2-9 c: ccc").unwrap();
+        assert!(entry.do_pass_chars_match());
+
+        let entry = PasswordEntry::from_str("4-9 c: ccc").unwrap();
+        assert!(!entry.do_pass_chars_match());
+        assert_eq!(
+            entry.toboggan_policy().check(&entry.password),
+            Err(String::from(
+                "neither position 4 nor 9 is 'c' (positions 4 and 9 are outside the 3 character password)"
+            ))
+        );
+
+        let entry = PasswordEntry::from_str("0-1 c: cab").unwrap();
+        assert!(entry.do_pass_chars_match());
+    }
+
+    #[test]
+    fn multibyte_passwords() {
+        let entry = PasswordEntry::from_str("2-3 é: aéb").unwrap();
+        assert!(entry.do_pass_chars_match());
+        assert!(!entry.is_pass_within_limits());
+    }
+
+    #[test]
+    fn reports_every_failure() {
+        let entries = input_generator(EXAMPLE).unwrap();
+        let validator = Validator::sled_rental()
+            .with_policy(MinLength(6))
+            .with_policy(ClassCount {
+                class: CharClass::AnyOf(String::from("aeiou")),
+                min: 1,
+                max: Some(1),
+            })
+            .with_policy(RegexPolicy::forbid(Regex::new("c{3}").unwrap()));
+
+        let failures: Vec<Vec<String>> = validator
+            .audit(&entries)
+            .iter()
+            .map(|report| {
+                report
+                    .failures
+                    .iter()
+                    .map(|failure| failure.policy.clone())
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(
+            failures,
+            vec![
+                vec![
+                    String::from("at least 6 characters"),
+                    String::from("1-1 any of \"aeiou\""),
+                ],
+                vec![
+                    String::from("1-3 of 'b'"),
+                    String::from("at least 6 characters"),
+                ],
+                vec![
+                    String::from("1-1 any of \"aeiou\""),
+                    String::from("must not match /c{3}/"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn report_display() {
+        let entries = input_generator("1-3 b: cdefg").unwrap();
+        let validator = Validator::sled_rental().with_policy(ClassCount {
+            class: CharClass::Digit,
+            min: 1,
+            max: None,
+        });
+        let report = &validator.audit(&entries)[0];
+
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.to_string(),
+            "line 1 (\"cdefg\"): 1-3 of 'b': contains 'b' 0 time(s), expected between 1 and 3; \
+             at least 1 digits: only has 0 digits, needs at least 1"
+        );
+    }
 }
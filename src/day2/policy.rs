@@ -0,0 +1,369 @@
+use std::fmt;
+
+use regex::Regex;
+
+use super::PasswordEntry;
+
+/// A rule that a password has to follow
+pub trait PasswordPolicy {
+    /// Short description of the policy used when reporting failures
+    fn name(&self) -> String;
+
+    /// Check `password` against the policy, returning why it doesn't comply if it doesn't
+    fn check(&self, password: &str) -> Result<(), String>;
+}
+
+/// The sled rental policy, `checked_char` has to appear between `min` and `max` times (inclusive)
+#[derive(Debug, Clone)]
+pub struct CharCount {
+    pub checked_char: char,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PasswordPolicy for CharCount {
+    fn name(&self) -> String {
+        format!("{}-{} of {:?}", self.min, self.max, self.checked_char)
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let count = password.chars().filter(|&c| c == self.checked_char).count();
+        if self.min <= count && count <= self.max {
+            Ok(())
+        } else {
+            Err(format!(
+                "contains {:?} {} time(s), expected between {} and {}",
+                self.checked_char, count, self.min, self.max
+            ))
+        }
+    }
+}
+
+/// The Official Toboggan Corporate Policy, exactly one of the (1-based) positions `first` and `second` has to be
+/// `checked_char`. Positions past the end of the password, or position 0, never match.
+#[derive(Debug, Clone)]
+pub struct CharPositions {
+    pub checked_char: char,
+    pub first: usize,
+    pub second: usize,
+}
+
+impl CharPositions {
+    fn is_at(&self, password: &str, position: usize) -> bool {
+        position
+            .checked_sub(1)
+            .and_then(|idx| password.chars().nth(idx))
+            .map_or(false, |c| c == self.checked_char)
+    }
+}
+
+impl PasswordPolicy for CharPositions {
+    fn name(&self) -> String {
+        format!(
+            "{:?} at exactly one of {} and {}",
+            self.checked_char, self.first, self.second
+        )
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        match (
+            self.is_at(password, self.first),
+            self.is_at(password, self.second),
+        ) {
+            (true, false) | (false, true) => Ok(()),
+            (true, true) => Err(format!(
+                "both position {} and {} are {:?}",
+                self.first, self.second, self.checked_char
+            )),
+            (false, false) => {
+                let length = password.chars().count();
+                let out_of_range: Vec<String> = [self.first, self.second]
+                    .iter()
+                    .filter(|position| **position == 0 || **position > length)
+                    .map(|position| position.to_string())
+                    .collect();
+                let reason = format!(
+                    "neither position {} nor {} is {:?}",
+                    self.first, self.second, self.checked_char
+                );
+
+                match out_of_range.len() {
+                    0 => Err(reason),
+                    1 => Err(format!(
+                        "{} (position {} is outside the {} character password)",
+                        reason, out_of_range[0], length
+                    )),
+                    _ => Err(format!(
+                        "{} (positions {} are outside the {} character password)",
+                        reason,
+                        out_of_range.join(" and "),
+                        length
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// A group of characters that a policy can count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    /// Anything that isn't alphanumeric or whitespace
+    Symbol,
+    /// Any of the listed characters
+    AnyOf(String),
+}
+
+impl CharClass {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            Self::Lowercase => c.is_lowercase(),
+            Self::Uppercase => c.is_uppercase(),
+            Self::Digit => c.is_ascii_digit(),
+            Self::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+            Self::AnyOf(chars) => chars.contains(c),
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lowercase => write!(f, "lowercase letters"),
+            Self::Uppercase => write!(f, "uppercase letters"),
+            Self::Digit => write!(f, "digits"),
+            Self::Symbol => write!(f, "symbols"),
+            Self::AnyOf(chars) => write!(f, "any of {:?}", chars),
+        }
+    }
+}
+
+/// At least `min`, and at most `max` if there is one, characters from `class`
+#[derive(Debug, Clone)]
+pub struct ClassCount {
+    pub class: CharClass,
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl PasswordPolicy for ClassCount {
+    fn name(&self) -> String {
+        match self.max {
+            Some(max) => format!("{}-{} {}", self.min, max, self.class),
+            None => format!("at least {} {}", self.min, self.class),
+        }
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let count = password.chars().filter(|c| self.class.contains(*c)).count();
+        if count < self.min {
+            Err(format!(
+                "only has {} {}, needs at least {}",
+                count, self.class, self.min
+            ))
+        } else if let Some(max) = self.max.filter(|max| count > *max) {
+            Err(format!(
+                "has {} {}, allows at most {}",
+                count, self.class, max
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Password has to be at least this many characters long
+#[derive(Debug, Clone)]
+pub struct MinLength(pub usize);
+
+impl PasswordPolicy for MinLength {
+    fn name(&self) -> String {
+        format!("at least {} characters", self.0)
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length >= self.0 {
+            Ok(())
+        } else {
+            Err(format!(
+                "is {} character(s) long, needs at least {}",
+                length, self.0
+            ))
+        }
+    }
+}
+
+/// Password has to match, or with `forbid` must not match, a regular expression
+#[derive(Debug, Clone)]
+pub struct RegexPolicy {
+    pub regex: Regex,
+    pub forbid: bool,
+}
+
+impl RegexPolicy {
+    /// Passwords have to match `regex`
+    pub fn require(regex: Regex) -> Self {
+        RegexPolicy {
+            regex,
+            forbid: false,
+        }
+    }
+
+    /// Passwords must not match `regex`
+    pub fn forbid(regex: Regex) -> Self {
+        RegexPolicy {
+            regex,
+            forbid: true,
+        }
+    }
+}
+
+impl PasswordPolicy for RegexPolicy {
+    fn name(&self) -> String {
+        if self.forbid {
+            format!("must not match /{}/", self.regex)
+        } else {
+            format!("must match /{}/", self.regex)
+        }
+    }
+
+    fn check(&self, password: &str) -> Result<(), String> {
+        match (self.regex.find(password), self.forbid) {
+            (Some(_), false) | (None, true) => Ok(()),
+            (None, false) => Err(format!("doesn't match /{}/", self.regex)),
+            (Some(found), true) => Err(format!(
+                "contains {:?}, which matches /{}/",
+                found.as_str(),
+                self.regex
+            )),
+        }
+    }
+}
+
+/// A policy that a password broke, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyFailure {
+    pub policy: String,
+    pub reason: String,
+}
+
+impl fmt::Display for PolicyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.policy, self.reason)
+    }
+}
+
+/// The result of checking one entry of a password list
+#[derive(Debug, Clone)]
+pub struct EntryReport<'a> {
+    /// Line of the entry in the password list, starting at 1
+    pub line: usize,
+    pub entry: &'a PasswordEntry,
+    /// Every policy the entry broke, empty if the password is valid
+    pub failures: Vec<PolicyFailure>,
+}
+
+impl<'a> EntryReport<'a> {
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl<'a> fmt::Display for EntryReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} ({:?}): ", self.line, self.entry.password)?;
+        if self.failures.is_empty() {
+            return write!(f, "ok");
+        }
+
+        for (idx, failure) in self.failures.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", failure)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the policy an entry carries for itself, see `Validator::with_entry_policy`
+pub type EntryPolicy = fn(&PasswordEntry) -> Box<dyn PasswordPolicy>;
+
+/// Checks password entries against a set of policies. Each entry in the puzzle input carries the numbers and
+/// character for its own policy, an entry policy turns those into a policy which is checked alongside the policies
+/// shared by every entry.
+#[derive(Default)]
+pub struct Validator {
+    entry_policy: Option<EntryPolicy>,
+    policies: Vec<Box<dyn PasswordPolicy>>,
+}
+
+impl Validator {
+    /// Validator with no policies, every password is valid until some are added
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validator applying each entry's own sled rental policy
+    pub fn sled_rental() -> Self {
+        Self::new().with_entry_policy(|entry| Box::new(entry.sled_rental_policy()))
+    }
+
+    /// Validator applying each entry's own Official Toboggan Corporate Policy
+    pub fn toboggan() -> Self {
+        Self::new().with_entry_policy(|entry| Box::new(entry.toboggan_policy()))
+    }
+
+    /// Build the policy for each entry from the entry itself with `entry_policy`
+    pub fn with_entry_policy(mut self, entry_policy: EntryPolicy) -> Self {
+        self.entry_policy = Some(entry_policy);
+        self
+    }
+
+    /// Check every entry against `policy` as well
+    pub fn with_policy<P: PasswordPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policies.push(Box::new(policy));
+        self
+    }
+
+    /// Check a single entry, returning every policy it broke
+    pub fn check(&self, entry: &PasswordEntry) -> Vec<PolicyFailure> {
+        let entry_policy = self.entry_policy.map(|build| build(entry));
+
+        entry_policy
+            .iter()
+            .chain(self.policies.iter())
+            .filter_map(|policy| {
+                policy
+                    .check(&entry.password)
+                    .err()
+                    .map(|reason| PolicyFailure {
+                        policy: policy.name(),
+                        reason,
+                    })
+            })
+            .collect()
+    }
+
+    pub fn is_valid(&self, entry: &PasswordEntry) -> bool {
+        self.check(entry).is_empty()
+    }
+
+    /// Check every entry of a password list
+    pub fn audit<'a>(&self, entries: &'a [PasswordEntry]) -> Vec<EntryReport<'a>> {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| EntryReport {
+                line: idx + 1,
+                entry,
+                failures: self.check(entry),
+            })
+            .collect()
+    }
+}
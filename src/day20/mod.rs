@@ -0,0 +1,506 @@
+use fnv::{FnvHashMap, FnvHashSet};
+use regex::Regex;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+lazy_static! {
+    static ref TILE_HEADER: Regex = Regex::new(r"^Tile (\d+):$").unwrap();
+}
+
+/// The sea monster we are hunting for in Part 2, `#` are the parts of the monster
+const SEA_MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    ##    ###",
+    " #  #  #  #  #  #   ",
+];
+
+/// A square grid of pixels, `true` is a `#`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    size: usize,
+    pixels: Vec<Vec<bool>>,
+}
+
+impl Image {
+    /// Rotate the image 90 degrees clockwise
+    fn rotate(&self) -> Self {
+        let pixels = (0..self.size)
+            .map(|y| {
+                (0..self.size)
+                    .map(|x| self.pixels[self.size - 1 - x][y])
+                    .collect()
+            })
+            .collect();
+
+        Image {
+            size: self.size,
+            pixels,
+        }
+    }
+
+    /// Mirror the image left to right
+    fn flip(&self) -> Self {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+
+        Image {
+            size: self.size,
+            pixels,
+        }
+    }
+
+    /// All 8 rotations and reflections of the image
+    fn orientations(&self) -> Vec<Self> {
+        let mut orientations = Vec::with_capacity(8);
+        let mut current = self.clone();
+        for _ in 0..4 {
+            orientations.push(current.flip());
+            let next = current.rotate();
+            orientations.push(current);
+            current = next;
+        }
+
+        orientations
+    }
+
+    fn top(&self) -> Vec<bool> {
+        self.pixels[0].clone()
+    }
+
+    fn bottom(&self) -> Vec<bool> {
+        self.pixels[self.size - 1].clone()
+    }
+
+    fn left(&self) -> Vec<bool> {
+        self.pixels.iter().map(|row| row[0]).collect()
+    }
+
+    fn right(&self) -> Vec<bool> {
+        self.pixels.iter().map(|row| row[self.size - 1]).collect()
+    }
+
+    /// All four edges, read clockwise so that two matching tiles will have one edge reversed from the other
+    fn edges(&self) -> [Vec<bool>; 4] {
+        [self.top(), self.right(), self.bottom(), self.left()]
+    }
+
+    fn count_set(&self) -> usize {
+        self.pixels
+            .iter()
+            .map(|row| row.iter().filter(|pixel| **pixel).count())
+            .sum()
+    }
+}
+
+/// One piece of the satellite image
+#[derive(Debug, Clone)]
+pub struct Tile {
+    id: u64,
+    image: Image,
+}
+
+#[derive(Debug, Clone)]
+pub struct Jigsaw {
+    tiles: Vec<Tile>,
+}
+
+impl Jigsaw {
+    /// Count how many other tiles share each edge of every tile, keyed by tile id. Edges are compared in both
+    /// directions since the other tile may be flipped.
+    fn edge_matches(&self) -> FnvHashMap<u64, [usize; 4]> {
+        let mut edge_counts: FnvHashMap<Vec<bool>, FnvHashSet<u64>> = FnvHashMap::default();
+        for tile in &self.tiles {
+            for edge in tile.image.edges().iter() {
+                let reversed: Vec<bool> = edge.iter().rev().copied().collect();
+                edge_counts.entry(edge.clone()).or_default().insert(tile.id);
+                edge_counts.entry(reversed).or_default().insert(tile.id);
+            }
+        }
+
+        self.tiles
+            .iter()
+            .map(|tile| {
+                let edges = tile.image.edges();
+                let mut matches = [0; 4];
+                for (idx, edge) in edges.iter().enumerate() {
+                    matches[idx] = edge_counts[edge].len() - 1;
+                }
+                (tile.id, matches)
+            })
+            .collect()
+    }
+
+    /// Corner tiles are the only tiles with two edges that don't line up with any other tile
+    pub fn corners(&self) -> Vec<u64> {
+        self.edge_matches()
+            .iter()
+            .filter(|(_, matches)| matches.iter().filter(|count| **count == 0).count() == 2)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Put the jigsaw together, returning the oriented tiles in row-major order. Returns `None` if the tiles can't be
+    /// assembled into a square.
+    pub fn assemble(&self) -> Option<Vec<Vec<Image>>> {
+        let side = (self.tiles.len() as f64).sqrt() as usize;
+        if side * side != self.tiles.len() {
+            return None;
+        }
+
+        // A jigsaw of a single tile has no matched edges at all, so its corner has all four unmatched
+        let edge_matches = self.edge_matches();
+        let corner = self.tiles.iter().find(|tile| {
+            edge_matches[&tile.id]
+                .iter()
+                .filter(|count| **count == 0)
+                .count()
+                >= 2
+        })?;
+
+        // Orient our starting corner so that its unmatched edges are on the top and left, which means everything
+        // else can be placed by only looking up and to the left
+        let unmatched = |edge: &Vec<bool>| {
+            let reversed: Vec<bool> = edge.iter().rev().copied().collect();
+            !self.tiles.iter().any(|tile| {
+                tile.id != corner.id
+                    && tile
+                        .image
+                        .edges()
+                        .iter()
+                        .any(|other| *other == *edge || *other == reversed)
+            })
+        };
+        let start = corner
+            .image
+            .orientations()
+            .into_iter()
+            .find(|image| unmatched(&image.top()) && unmatched(&image.left()))?;
+
+        let mut used = FnvHashSet::default();
+        used.insert(corner.id);
+        let mut grid: Vec<Vec<Image>> = vec![vec![start]];
+
+        for y in 0..side {
+            for x in 0..side {
+                if x == 0 && y == 0 {
+                    continue;
+                }
+                if x == 0 {
+                    grid.push(Vec::with_capacity(side));
+                }
+
+                let left = if x > 0 {
+                    Some(grid[y][x - 1].right())
+                } else {
+                    None
+                };
+                let above = if y > 0 {
+                    Some(grid[y - 1][x].bottom())
+                } else {
+                    None
+                };
+
+                let (id, image) = self
+                    .tiles
+                    .iter()
+                    .filter(|tile| !used.contains(&tile.id))
+                    .flat_map(|tile| {
+                        tile.image
+                            .orientations()
+                            .into_iter()
+                            .map(move |image| (tile.id, image))
+                    })
+                    .find(|(_, image)| {
+                        left.as_ref().map_or(true, |edge| image.left() == *edge)
+                            && above.as_ref().map_or(true, |edge| image.top() == *edge)
+                    })?;
+
+                used.insert(id);
+                grid[y].push(image);
+            }
+        }
+
+        Some(grid)
+    }
+
+    /// Assemble the jigsaw and strip the borders from each tile to get the actual image
+    pub fn image(&self) -> Option<Image> {
+        let grid = self.assemble()?;
+        let inner = grid[0][0].size - 2;
+        let size = grid.len() * inner;
+
+        let mut pixels = vec![Vec::with_capacity(size); size];
+        for (tile_y, row) in grid.iter().enumerate() {
+            for tile in row {
+                for y in 0..inner {
+                    pixels[tile_y * inner + y].extend_from_slice(&tile.pixels[y + 1][1..=inner]);
+                }
+            }
+        }
+
+        Some(Image { size, pixels })
+    }
+}
+
+/// Find every sea monster in the image (in whichever orientation has them) and return the number of `#` pixels that
+/// are not part of a sea monster
+pub fn water_roughness(image: &Image) -> usize {
+    let monster: Vec<(usize, usize)> = SEA_MONSTER
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '#')
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+    let monster_width = SEA_MONSTER[0].len();
+    let monster_height = SEA_MONSTER.len();
+    // There's no room for a sea monster, and looking for one would run off the edge of the image
+    if image.size < monster_width || image.size < monster_height {
+        return image.count_set();
+    }
+
+    for oriented in image.orientations() {
+        let mut monster_pixels = FnvHashSet::default();
+        for y in 0..=oriented.size - monster_height {
+            for x in 0..=oriented.size - monster_width {
+                if monster
+                    .iter()
+                    .all(|(dx, dy)| oriented.pixels[y + dy][x + dx])
+                {
+                    monster_pixels.extend(monster.iter().map(|(dx, dy)| (x + dx, y + dy)));
+                }
+            }
+        }
+
+        if !monster_pixels.is_empty() {
+            return oriented.count_set() - monster_pixels.len();
+        }
+    }
+
+    image.count_set()
+}
+
+#[aoc_generator(day20)]
+pub fn input_generator(input: &str) -> Result<Jigsaw, ParseError> {
+    let mut tiles = Vec::new();
+    for raw_tile in input.split("\n\n") {
+        let mut lines = raw_tile.lines();
+        let header = lines.next().unwrap_or(raw_tile);
+        let id = TILE_HEADER
+            .captures(header)
+            .ok_or_else(|| {
+                ParseError::new(20, input, header, ParseErrorKind::Malformed("`Tile <id>:`"))
+            })?
+            .get(1)
+            .unwrap()
+            .as_str();
+        let id = id
+            .parse()
+            .map_err(|_| ParseError::new(20, input, id, ParseErrorKind::InvalidNumber))?;
+
+        let mut pixels = Vec::new();
+        for line in lines {
+            let row = line
+                .char_indices()
+                .map(|(idx, c)| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(ParseError::new(
+                        20,
+                        input,
+                        &line[idx..idx + c.len_utf8()],
+                        ParseErrorKind::UnexpectedChar(c),
+                    )),
+                })
+                .collect::<Result<Vec<bool>, _>>()?;
+            pixels.push(row);
+        }
+
+        let size = pixels.len();
+        if size < 3 || pixels.iter().any(|row| row.len() != size) {
+            return Err(ParseError::new(
+                20,
+                input,
+                raw_tile,
+                ParseErrorKind::Malformed("a square tile at least 3 pixels wide"),
+            ));
+        }
+
+        tiles.push(Tile {
+            id,
+            image: Image { size, pixels },
+        });
+    }
+
+    Ok(Jigsaw { tiles })
+}
+
+#[aoc(day20, part1)]
+pub fn solve_part1(input: &Jigsaw) -> u64 {
+    input.corners().iter().product()
+}
+
+#[aoc(day20, part2)]
+pub fn solve_part2(input: &Jigsaw) -> usize {
+    let image = input.image().expect("Unable to assemble the jigsaw");
+    water_roughness(&image)
+}
+
+/// Registers day 20 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Jigsaw;
+
+    const DAY: u8 = 20;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "Tile 2311:
+..##.#..#.
+##..#.....
+#...##..#.
+####.#...#
+##.##.###.
+##...#.###
+.#.#.#..##
+..#....#..
+###...#.#.
+..###..###
+
+Tile 1951:
+#.##...##.
+#.####...#
+.....#..##
+#...######
+.##.#....#
+.###.#####
+###.##.##.
+.###....#.
+..#.#..#.#
+#...##.#..
+
+Tile 1171:
+####...##.
+#..##.#..#
+##.#..#.#.
+.###.####.
+..###.####
+.##....##.
+.#...####.
+#.##.####.
+####..#...
+.....##...
+
+Tile 1427:
+###.##.#..
+.#..#.##..
+.#.##.#..#
+#.#.#.##.#
+....#...##
+...##..##.
+...#.#####
+.#.####.#.
+..#..###.#
+..##.#..#.
+
+Tile 1489:
+##.#.#....
+..##...#..
+.##..##...
+..#...#...
+#####...#.
+#..#.#.#.#
+...#.#.#..
+##.#...##.
+..##.##.##
+###.##.#..
+
+Tile 2473:
+#....####.
+#..#.##...
+#.##..#...
+######.#.#
+.#...#.#.#
+.#########
+.###.#..#.
+########.#
+##...##.#.
+..###.#.#.
+
+Tile 2971:
+..#.#....#
+#...###...
+#.#.###...
+##.##..#..
+.#####..##
+.#..####.#
+#..#.#..#.
+..####.###
+..#.#.###.
+...#.#.#.#
+
+Tile 2729:
+...#.#.#.#
+####.#....
+..#.#.....
+....#..#.#
+.##..##.#.
+.#.####...
+####.#.#..
+##.####...
+##..#.##..
+#.##...##.
+
+Tile 3079:
+#.#.#####.
+.#..######
+..#.......
+######....
+####.#..#.
+.#...#.##.
+#.#####.##
+..#.###...
+..#.......
+..#.###...";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "20899048083289");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "273");
+    }
+
+    #[test]
+    fn single_tile() {
+        let jigsaw = input_generator(EXAMPLE.split("\n\n").next().unwrap()).unwrap();
+        let image = jigsaw.image().unwrap();
+        assert_eq!(image.size, 8);
+        assert_eq!(water_roughness(&image), 29);
+    }
+}
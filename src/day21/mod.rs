@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use regex::Regex;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+lazy_static! {
+    static ref FOOD: Regex = Regex::new(r"^([a-z ]+?)(?: \(contains ([a-z, ]+)\))?$").unwrap();
+}
+
+/// A single food label, its ingredients and the allergens it is known to contain
+#[derive(Debug, Clone)]
+pub struct Food {
+    ingredients: FnvHashSet<String>,
+    allergens: FnvHashSet<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoodList {
+    foods: Vec<Food>,
+}
+
+impl FoodList {
+    /// For every allergen, the set of ingredients that could contain it. Each allergen is in exactly one ingredient,
+    /// so it must be in an ingredient that shows up on every food listing that allergen.
+    fn allergen_candidates(&self) -> FnvHashMap<String, FnvHashSet<String>> {
+        let mut candidates: FnvHashMap<String, FnvHashSet<String>> = FnvHashMap::default();
+        for food in &self.foods {
+            for allergen in &food.allergens {
+                candidates
+                    .entry(allergen.clone())
+                    .and_modify(|ingredients| {
+                        ingredients.retain(|ingredient| food.ingredients.contains(ingredient))
+                    })
+                    .or_insert_with(|| food.ingredients.clone());
+            }
+        }
+
+        candidates
+    }
+
+    /// Work out which ingredient contains which allergen, sorted by allergen. Returns `None` if the allergens can't
+    /// be narrowed down to a single ingredient each.
+    pub fn dangerous_ingredients(&self) -> Option<BTreeMap<String, String>> {
+        let mut candidates = self.allergen_candidates();
+        let mut resolved = BTreeMap::new();
+
+        // Keep pinning down allergens that only have a single candidate and removing that ingredient from everything
+        // else until we have resolved everything
+        while !candidates.is_empty() {
+            let (allergen, ingredient) = candidates
+                .iter()
+                .find(|(_, ingredients)| ingredients.len() == 1)
+                .map(|(allergen, ingredients)| {
+                    (allergen.clone(), ingredients.iter().next().unwrap().clone())
+                })?;
+
+            candidates.remove(&allergen);
+            for ingredients in candidates.values_mut() {
+                ingredients.remove(&ingredient);
+            }
+            resolved.insert(allergen, ingredient);
+        }
+
+        Some(resolved)
+    }
+
+    /// Count how many times an ingredient that can't possibly contain an allergen appears across all foods
+    pub fn count_safe_appearances(&self) -> usize {
+        let unsafe_ingredients: FnvHashSet<String> = self
+            .allergen_candidates()
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+
+        self.foods
+            .iter()
+            .map(|food| {
+                food.ingredients
+                    .iter()
+                    .filter(|ingredient| !unsafe_ingredients.contains(*ingredient))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+#[aoc_generator(day21)]
+pub fn input_generator(input: &str) -> Result<FoodList, ParseError> {
+    let foods = input
+        .lines()
+        .map(|line| {
+            let cap = FOOD.captures(line).ok_or_else(|| {
+                ParseError::new(
+                    21,
+                    input,
+                    line,
+                    ParseErrorKind::Malformed("`<ingredients> (contains <allergens>)`"),
+                )
+            })?;
+
+            Ok(Food {
+                ingredients: cap[1].split_whitespace().map(String::from).collect(),
+                allergens: cap
+                    .get(2)
+                    .map(|allergens| allergens.as_str().split(", ").map(String::from).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(FoodList { foods })
+}
+
+#[aoc(day21, part1)]
+pub fn solve_part1(input: &FoodList) -> usize {
+    input.count_safe_appearances()
+}
+
+#[aoc(day21, part2)]
+pub fn solve_part2(input: &FoodList) -> String {
+    input
+        .dangerous_ingredients()
+        .expect("Unable to resolve every allergen to a single ingredient")
+        .values()
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Registers day 21 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = FoodList;
+
+    const DAY: u8 = 21;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, solve_part2),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
+trh fvjkl sbzzf mxmxvkd (contains dairy)
+sqjhc fvjkl (contains soy)
+sqjhc mxmxvkd sbzzf (contains fish)";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "5");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "mxmxvkd,sqjhc,fvjkl");
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use fnv::FnvHashSet;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+/// The two players of a game of Combat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// Both players' decks, the front of each `VecDeque` is the top of the deck
+#[derive(Debug, Clone)]
+pub struct Decks {
+    player1: VecDeque<usize>,
+    player2: VecDeque<usize>,
+}
+
+impl Decks {
+    /// The score of a deck, the bottom card is worth its value, the next worth double, and so on
+    fn score(deck: &VecDeque<usize>) -> usize {
+        deck.iter()
+            .rev()
+            .enumerate()
+            .map(|(idx, card)| (idx + 1) * card)
+            .sum()
+    }
+
+    fn winning_score(&self, winner: Player) -> usize {
+        match winner {
+            Player::One => Self::score(&self.player1),
+            Player::Two => Self::score(&self.player2),
+        }
+    }
+
+    /// Give both cards to the winner of a round, the winner's card goes underneath first
+    fn award(&mut self, winner: Player, card1: usize, card2: usize) {
+        match winner {
+            Player::One => {
+                self.player1.push_back(card1);
+                self.player1.push_back(card2);
+            }
+            Player::Two => {
+                self.player2.push_back(card2);
+                self.player2.push_back(card1);
+            }
+        }
+    }
+
+    /// Play regular Combat until someone runs out of cards, returning the winner
+    pub fn play(&mut self) -> Player {
+        while !self.player1.is_empty() && !self.player2.is_empty() {
+            let card1 = self.player1.pop_front().unwrap();
+            let card2 = self.player2.pop_front().unwrap();
+
+            let winner = if card1 > card2 {
+                Player::One
+            } else {
+                Player::Two
+            };
+            self.award(winner, card1, card2);
+        }
+
+        if self.player1.is_empty() {
+            Player::Two
+        } else {
+            Player::One
+        }
+    }
+
+    /// Play Recursive Combat, returning the winner
+    pub fn play_recursive(&mut self) -> Player {
+        // If we ever see the same decks twice in one game, player 1 wins to stop the game looping forever
+        let mut seen = FnvHashSet::default();
+
+        while !self.player1.is_empty() && !self.player2.is_empty() {
+            if !seen.insert((self.player1.clone(), self.player2.clone())) {
+                return Player::One;
+            }
+
+            let card1 = self.player1.pop_front().unwrap();
+            let card2 = self.player2.pop_front().unwrap();
+
+            let winner = if self.player1.len() >= card1 && self.player2.len() >= card2 {
+                // Both players have enough cards to recurse, the sub-game is played with copies of the next cards
+                let mut sub_game = Decks {
+                    player1: self.player1.iter().take(card1).copied().collect(),
+                    player2: self.player2.iter().take(card2).copied().collect(),
+                };
+                sub_game.play_recursive()
+            } else if card1 > card2 {
+                Player::One
+            } else {
+                Player::Two
+            };
+            self.award(winner, card1, card2);
+        }
+
+        if self.player1.is_empty() {
+            Player::Two
+        } else {
+            Player::One
+        }
+    }
+}
+
+/// Parse a single `Player N:` section into a deck
+fn parse_deck(input: &str, section: &str) -> Result<VecDeque<usize>, ParseError> {
+    section
+        .lines()
+        .skip(1)
+        .map(|card| {
+            card.parse()
+                .map_err(|_| ParseError::new(22, input, card, ParseErrorKind::InvalidNumber))
+        })
+        .collect()
+}
+
+#[aoc_generator(day22)]
+pub fn input_generator(input: &str) -> Result<Decks, ParseError> {
+    let sections: Vec<&str> = input.split("\n\n").collect();
+    if sections.len() != 2 {
+        return Err(ParseError::new(
+            22,
+            input,
+            input,
+            ParseErrorKind::Malformed("two decks separated by a blank line"),
+        ));
+    }
+
+    Ok(Decks {
+        player1: parse_deck(input, sections[0])?,
+        player2: parse_deck(input, sections[1])?,
+    })
+}
+
+#[aoc(day22, part1)]
+pub fn solve_part1(input: &Decks) -> usize {
+    let mut decks = input.clone();
+    let winner = decks.play();
+    decks.winning_score(winner)
+}
+
+#[aoc(day22, part2)]
+pub fn solve_part2(input: &Decks) -> usize {
+    let mut decks = input.clone();
+    let winner = decks.play_recursive();
+    decks.winning_score(winner)
+}
+
+/// Registers day 22 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Decks;
+
+    const DAY: u8 = 22;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "Player 1:
+9
+2
+6
+3
+1
+
+Player 2:
+5
+8
+4
+7
+10";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "306");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "291");
+    }
+
+    #[test]
+    fn recursive_game_terminates() {
+        let input = "Player 1:\n43\n19\n\nPlayer 2:\n2\n29\n14";
+        let decks = input_generator(input).unwrap();
+        solve_part2(&decks);
+    }
+}
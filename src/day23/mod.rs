@@ -0,0 +1,200 @@
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+/// The circle of cups, stored as a linked list in a `Vec` where `next[label]` is the label of the cup clockwise of
+/// `label`. This makes every move O(1), which is what makes the million cup version of the game possible.
+#[derive(Debug, Clone)]
+pub struct CupCircle {
+    next: Vec<usize>,
+    current: usize,
+}
+
+impl CupCircle {
+    /// Build a circle from the starting labels, padding it with increasing labels until there are `total` cups
+    pub fn new(labels: &[usize], total: usize) -> Self {
+        let total = total.max(labels.len());
+        let order = labels
+            .iter()
+            .copied()
+            .chain(labels.len() + 1..=total)
+            .collect::<Vec<usize>>();
+
+        // Index 0 is unused so labels can be used directly as indexes
+        let mut next = vec![0; total + 1];
+        for (idx, label) in order.iter().enumerate() {
+            next[*label] = order[(idx + 1) % order.len()];
+        }
+
+        CupCircle {
+            next,
+            current: order[0],
+        }
+    }
+
+    /// Play a single move of the crab's game
+    pub fn play_move(&mut self) {
+        let max = self.next.len() - 1;
+
+        // Pick up the three cups after the current cup
+        let first = self.next[self.current];
+        let second = self.next[first];
+        let third = self.next[second];
+        self.next[self.current] = self.next[third];
+
+        // Find the destination label, skipping any cup we are holding
+        let mut destination = self.current;
+        loop {
+            destination = if destination == 1 {
+                max
+            } else {
+                destination - 1
+            };
+            if destination != first && destination != second && destination != third {
+                break;
+            }
+        }
+
+        // Put the picked up cups back after the destination
+        self.next[third] = self.next[destination];
+        self.next[destination] = first;
+
+        self.current = self.next[self.current];
+    }
+
+    pub fn play(&mut self, moves: usize) {
+        for _ in 0..moves {
+            self.play_move();
+        }
+    }
+
+    /// Labels of the cups clockwise of cup 1, not including cup 1
+    pub fn after_one(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut cup = 1;
+        std::iter::from_fn(move || {
+            cup = self.next[cup];
+            if cup == 1 {
+                None
+            } else {
+                Some(cup)
+            }
+        })
+    }
+}
+
+#[aoc_generator(day23)]
+pub fn input_generator(input: &str) -> Result<Vec<usize>, ParseError> {
+    let input = input.trim_end();
+    let labels = input
+        .char_indices()
+        .map(|(idx, c)| {
+            c.to_digit(10).map(|digit| digit as usize).ok_or_else(|| {
+                ParseError::new(
+                    23,
+                    input,
+                    &input[idx..idx + c.len_utf8()],
+                    ParseErrorKind::UnexpectedChar(c),
+                )
+            })
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    // Labels have to be exactly 1 to N, once each, for the linked list to make sense
+    let mut sorted = labels.clone();
+    sorted.sort_unstable();
+    if sorted.is_empty()
+        || sorted
+            .iter()
+            .enumerate()
+            .any(|(idx, label)| *label != idx + 1)
+    {
+        return Err(ParseError::new(
+            23,
+            input,
+            input,
+            ParseErrorKind::Malformed("each cup label from 1 to the number of cups exactly once"),
+        ));
+    }
+
+    // Every move picks up three cups and needs a fourth to put them down after, with fewer there is no destination
+    if labels.len() < 4 {
+        return Err(ParseError::new(
+            23,
+            input,
+            input,
+            ParseErrorKind::Malformed("at least 4 cups"),
+        ));
+    }
+
+    Ok(labels)
+}
+
+#[aoc(day23, part1)]
+pub fn solve_part1(input: &[usize]) -> String {
+    let mut cups = CupCircle::new(input, input.len());
+    cups.play(100);
+    cups.after_one().map(|label| label.to_string()).collect()
+}
+
+#[aoc(day23, part2)]
+pub fn solve_part2(input: &[usize]) -> usize {
+    let mut cups = CupCircle::new(input, 1_000_000);
+    cups.play(10_000_000);
+    cups.after_one().take(2).product()
+}
+
+/// Registers day 23 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<usize>;
+
+    const DAY: u8 = 23;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input)),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn ten_moves() {
+        let mut cups = CupCircle::new(&[3, 8, 9, 1, 2, 5, 4, 6, 7], 9);
+        cups.play(10);
+        assert_eq!(
+            cups.after_one().collect::<Vec<usize>>(),
+            vec![9, 2, 6, 5, 8, 3, 7, 4]
+        );
+    }
+
+    #[test]
+    fn too_few_cups() {
+        for input in &["123", "12", "1"] {
+            assert_eq!(
+                input_generator(input).unwrap_err().kind,
+                ParseErrorKind::Malformed("at least 4 cups")
+            );
+        }
+        assert!(input_generator("1234").is_ok());
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>("389125467", Part::One, "67384529");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>("389125467", Part::Two, "149245887792");
+    }
+}
@@ -0,0 +1,200 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+/// Axial coordinates of a hexagonal tile, (q, r) where q runs east and r runs south-east
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Hex(i32, i32);
+
+impl Hex {
+    /// The six tiles touching this one
+    fn neighbors(self) -> [Hex; 6] {
+        [
+            Hex(self.0 + 1, self.1),
+            Hex(self.0 - 1, self.1),
+            Hex(self.0 + 1, self.1 - 1),
+            Hex(self.0, self.1 - 1),
+            Hex(self.0, self.1 + 1),
+            Hex(self.0 - 1, self.1 + 1),
+        ]
+    }
+}
+
+/// The six directions you can step on a hex grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HexDirection {
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl HexDirection {
+    fn offset(self) -> (i32, i32) {
+        match self {
+            Self::East => (1, 0),
+            Self::West => (-1, 0),
+            Self::NorthEast => (1, -1),
+            Self::NorthWest => (0, -1),
+            Self::SouthEast => (0, 1),
+            Self::SouthWest => (-1, 1),
+        }
+    }
+}
+
+/// Follow a list of directions from the reference tile to find the tile they point at
+fn walk(directions: &[HexDirection]) -> Hex {
+    directions.iter().fold(Hex(0, 0), |hex, direction| {
+        let (dq, dr) = direction.offset();
+        Hex(hex.0 + dq, hex.1 + dr)
+    })
+}
+
+/// Set up the floor by flipping the tile at the end of each path, a tile flipped twice ends up white again
+pub fn initial_black_tiles(paths: &[Vec<HexDirection>]) -> FnvHashSet<Hex> {
+    let mut black = FnvHashSet::default();
+    for path in paths {
+        let tile = walk(path);
+        if !black.remove(&tile) {
+            black.insert(tile);
+        }
+    }
+
+    black
+}
+
+/// Run one day of the art exhibit. Only black tiles and their neighbors can change, so we only need to look at those.
+pub fn next_day(black: &FnvHashSet<Hex>) -> FnvHashSet<Hex> {
+    let mut black_neighbors: FnvHashMap<Hex, usize> = FnvHashMap::default();
+    for tile in black {
+        for neighbor in tile.neighbors().iter() {
+            *black_neighbors.entry(*neighbor).or_insert(0) += 1;
+        }
+    }
+
+    black_neighbors
+        .into_iter()
+        .filter(|(tile, count)| {
+            if black.contains(tile) {
+                *count == 1 || *count == 2
+            } else {
+                *count == 2
+            }
+        })
+        .map(|(tile, _)| tile)
+        .collect()
+}
+
+#[aoc_generator(day24)]
+pub fn input_generator(input: &str) -> Result<Vec<Vec<HexDirection>>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            let mut directions = Vec::new();
+            let mut idx = 0;
+            while idx < line.len() {
+                let rest = &line[idx..];
+                let (direction, len) = if rest.starts_with("se") {
+                    (HexDirection::SouthEast, 2)
+                } else if rest.starts_with("sw") {
+                    (HexDirection::SouthWest, 2)
+                } else if rest.starts_with("ne") {
+                    (HexDirection::NorthEast, 2)
+                } else if rest.starts_with("nw") {
+                    (HexDirection::NorthWest, 2)
+                } else if rest.starts_with('e') {
+                    (HexDirection::East, 1)
+                } else if rest.starts_with('w') {
+                    (HexDirection::West, 1)
+                } else {
+                    let c = rest.chars().next().unwrap();
+                    return Err(ParseError::new(
+                        24,
+                        input,
+                        &rest[..c.len_utf8()],
+                        ParseErrorKind::UnexpectedChar(c),
+                    ));
+                };
+                directions.push(direction);
+                idx += len;
+            }
+
+            Ok(directions)
+        })
+        .collect()
+}
+
+#[aoc(day24, part1)]
+pub fn solve_part1(input: &[Vec<HexDirection>]) -> usize {
+    initial_black_tiles(input).len()
+}
+
+#[aoc(day24, part2)]
+pub fn solve_part2(input: &[Vec<HexDirection>]) -> usize {
+    let mut black = initial_black_tiles(input);
+    for _ in 0..100 {
+        black = next_day(&black);
+    }
+
+    black.len()
+}
+
+/// Registers day 24 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<Vec<HexDirection>>;
+
+    const DAY: u8 = 24;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn walks_to_the_right_tile() {
+        // From the puzzle text: `esew` ends up south east of the reference tile and `nwwswee` ends up back on it
+        let paths = input_generator("esew\nnwwswee").unwrap();
+        assert_eq!(walk(&paths[0]), Hex(0, 0).neighbors()[4]);
+        assert_eq!(walk(&paths[1]), Hex(0, 0));
+    }
+
+    #[test]
+    fn part1_flips_tiles_back() {
+        assert_variants::<Solution>("esew\nnwwswee\nesew", Part::One, "1");
+        assert_variants::<Solution>("e\nw\nnwwswee", Part::One, "3");
+    }
+
+    #[test]
+    fn next_day_rules() {
+        // Two touching black tiles both stay black, and the two white tiles touching both of them turn black
+        let mut black = FnvHashSet::default();
+        black.insert(Hex(0, 0));
+        black.insert(Hex(1, 0));
+        let black = next_day(&black);
+        assert_eq!(black.len(), 4);
+        assert!(black.contains(&Hex(1, -1)));
+        assert!(black.contains(&Hex(0, 1)));
+
+        // A lone black tile has no black neighbors so it turns white
+        let mut lone = FnvHashSet::default();
+        lone.insert(Hex(0, 0));
+        assert!(next_day(&lone).is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use fnv::FnvHashMap;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+/// Every transform is done modulo this prime
+const MODULUS: u64 = 20_201_227;
+/// Subject number used to generate public keys
+const SUBJECT: u64 = 7;
+
+/// The public keys of the card and door
+#[derive(Debug, Clone, Copy)]
+pub struct PublicKeys {
+    card: u64,
+    door: u64,
+}
+
+/// Modular exponentiation by squaring, this is the handshake's transform with a known loop size
+pub fn transform(subject: u64, loop_size: u64) -> u64 {
+    let mut result = 1;
+    let mut base = subject % MODULUS;
+    let mut exponent = loop_size;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % MODULUS;
+        }
+        base = base * base % MODULUS;
+        exponent >>= 1;
+    }
+
+    result
+}
+
+/// Find the loop size that turns `SUBJECT` into `public_key` by running the transform one step at a time
+pub fn loop_size_naive(public_key: u64) -> Option<u64> {
+    let mut value = 1;
+    for loop_size in 0..MODULUS {
+        if value == public_key {
+            return Some(loop_size);
+        }
+        value = value * SUBJECT % MODULUS;
+    }
+
+    None
+}
+
+/// Find the loop size that turns `SUBJECT` into `public_key` using baby-step giant-step, which only takes
+/// O(sqrt(MODULUS)) steps instead of O(MODULUS)
+pub fn loop_size_bsgs(public_key: u64) -> Option<u64> {
+    let step = (MODULUS as f64).sqrt().ceil() as u64;
+
+    // Baby steps, remember SUBJECT^j for every j below our step size
+    let mut baby_steps = FnvHashMap::default();
+    let mut value = 1;
+    for j in 0..step {
+        baby_steps.entry(value).or_insert(j);
+        value = value * SUBJECT % MODULUS;
+    }
+
+    // Giant steps, multiply the key by SUBJECT^-step until we land on one of the baby steps. MODULUS is prime so the
+    // inverse is SUBJECT^(MODULUS - 1 - step)
+    let giant = transform(SUBJECT, MODULUS - 1 - step);
+    let mut value = public_key % MODULUS;
+    for i in 0..step {
+        if let Some(j) = baby_steps.get(&value) {
+            return Some(i * step + j);
+        }
+        value = value * giant % MODULUS;
+    }
+
+    None
+}
+
+#[aoc_generator(day25)]
+pub fn input_generator(input: &str) -> Result<PublicKeys, ParseError> {
+    let keys = input
+        .lines()
+        .map(|line| {
+            line.parse::<u64>()
+                .map_err(|_| ParseError::new(25, input, line, ParseErrorKind::InvalidNumber))
+        })
+        .collect::<Result<Vec<u64>, _>>()?;
+
+    if keys.len() != 2 {
+        return Err(ParseError::new(
+            25,
+            input,
+            input,
+            ParseErrorKind::Malformed(
+                "the card's public key and the door's public key on separate lines",
+            ),
+        ));
+    }
+
+    Ok(PublicKeys {
+        card: keys[0],
+        door: keys[1],
+    })
+}
+
+#[aoc(day25, part1, naive)]
+pub fn solve_part1_naive(input: &PublicKeys) -> u64 {
+    let loop_size = loop_size_naive(input.card).expect("Unable to find the card's loop size");
+    transform(input.door, loop_size)
+}
+
+#[aoc(day25, part1, bsgs)]
+pub fn solve_part1_bsgs(input: &PublicKeys) -> u64 {
+    let loop_size = loop_size_bsgs(input.card).expect("Unable to find the card's loop size");
+    transform(input.door, loop_size)
+}
+
+/// Registers day 25 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = PublicKeys;
+
+    const DAY: u8 = 25;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("naive"), |input| {
+                solve_part1_naive(input).to_string()
+            }),
+            Variant::new(Part::One, Some("bsgs"), |input| {
+                solve_part1_bsgs(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn loop_sizes() {
+        for loop_size_fn in [loop_size_naive, loop_size_bsgs].iter() {
+            assert_eq!(loop_size_fn(5_764_801), Some(8));
+            assert_eq!(loop_size_fn(17_807_724), Some(11));
+        }
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>("5764801\n17807724", Part::One, "14897079");
+    }
+}
@@ -1,14 +1,43 @@
-use fnv::FnvHashMap;
-// use std::collections::HashMap;
-use std::convert::From;
+use std::convert::TryFrom;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
 
 /// Wrapper type for coordinates (X, Y)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Coord(usize, usize);
+pub struct Coord(pub usize, pub usize);
+
+/// Wrapper type for slope (X, Y) or (Run, Rise), the toboggan moves `run` columns right and `rise` rows down on every
+/// step. A negative run moves left, and a negative rise starts from the bottom of the map and moves up. A rise of 0
+/// never leaves the starting row, so it only visits the starting tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slope(pub isize, pub isize);
+
+impl Slope {
+    /// Slope that moves `numerator / denominator` columns for every row, only stopping on rows where that lands on a
+    /// whole column. For example half a column per row visits every other row, the same as `Slope(1, 2)`. Returns
+    /// `None` if `denominator` is 0.
+    pub fn per_row(numerator: isize, denominator: isize) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let divisor = gcd(numerator.abs(), denominator.abs());
+        let sign = denominator.signum();
+        Some(Slope(
+            sign * numerator / divisor,
+            sign * denominator / divisor,
+        ))
+    }
+}
 
-/// Wrapper tyep for slope (X, Y) or (Run, Rise)
-#[derive(Debug, Clone, Copy)]
-pub struct Slope(usize, usize);
+fn gcd(a: isize, b: isize) -> isize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
 
 /// This represents the two types of tiles we can have, empty or with a tree
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -17,75 +46,173 @@ pub enum Tile {
     Tree,
 }
 
-impl From<char> for Tile {
-    fn from(input: char) -> Self {
+impl TryFrom<char> for Tile {
+    type Error = ParseErrorKind;
+
+    fn try_from(input: char) -> Result<Self, Self::Error> {
         match input {
-            '.' => Tile::Empty,
-            '#' => Tile::Tree,
-            _ => panic!(format!("Unrecognized character : {}", input)),
+            '.' => Ok(Tile::Empty),
+            '#' => Ok(Tile::Tree),
+            _ => Err(ParseErrorKind::UnexpectedChar(input)),
         }
     }
 }
 
 /// Represents the size of the provided map, we can use this to build an "infinite" representation
-/// since the map repeats itself.
+/// since the map repeats itself. Each row is packed into `u64` words with one bit per tile, set for trees.
 #[derive(Debug, Clone)]
 pub struct Map {
     height: usize,
     width: usize,
-    tiles: FnvHashMap<Coord, Tile>,
+    /// Number of `u64` words used by each row
+    row_words: usize,
+    trees: Vec<u64>,
 }
 
 impl Map {
-    /// Converts a &str of '.' and '#' in rows ending with new lines into a `Map`
-    /// This assumes that all rows are the same size, and does not check to verify
-    pub fn from_str(input: &str) -> Self {
-        let mut tiles = FnvHashMap::default();
+    /// Converts a &str of '.' and '#' in rows ending with new lines into a `Map`. Every row has to be the same width.
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let width = input.lines().next().map_or(0, |line| line.chars().count());
+        let row_words = (width + 63) / 64;
+        let mut trees = Vec::new();
         let mut height = 0;
-        let mut width = 0;
-        input.lines().for_each(|line| {
-            width = 0;
-            line.chars().for_each(|c| {
-                let tile = Tile::from(c);
-                tiles.insert(Coord(width, height), tile);
-                width += 1;
-            });
+
+        for line in input.lines() {
+            let mut row = vec![0u64; row_words];
+            let mut x = 0;
+            for (idx, c) in line.char_indices() {
+                let fragment = &line[idx..idx + c.len_utf8()];
+                let tile =
+                    Tile::try_from(c).map_err(|kind| ParseError::new(3, input, fragment, kind))?;
+                if x >= width {
+                    return Err(ParseError::new(
+                        3,
+                        input,
+                        fragment,
+                        ParseErrorKind::Malformed("every row to be as wide as the first"),
+                    ));
+                }
+                if tile == Tile::Tree {
+                    row[x / 64] |= 1 << (x % 64);
+                }
+                x += 1;
+            }
+
+            if x != width {
+                return Err(ParseError::new(
+                    3,
+                    input,
+                    line,
+                    ParseErrorKind::Malformed("every row to be as wide as the first"),
+                ));
+            }
+
+            trees.extend(row);
             height += 1;
-        });
+        }
 
-        Map {
+        Ok(Map {
             height,
-            width: width,
-            tiles: tiles,
+            width,
+            row_words,
+            trees,
+        })
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The tile at `coord`, the map repeats to the right forever so any x works. Returns `None` below the map.
+    pub fn get(&self, coord: Coord) -> Option<Tile> {
+        if coord.1 >= self.height || self.width == 0 {
+            return None;
+        }
+
+        let x = coord.0 % self.width;
+        let word = self.trees[coord.1 * self.row_words + x / 64];
+        if word & (1 << (x % 64)) != 0 {
+            Some(Tile::Tree)
+        } else {
+            Some(Tile::Empty)
+        }
+    }
+
+    /// Follow `slope` from the top left corner (or the bottom left corner if it goes up) until we leave the map,
+    /// yielding every tile we stop on. Coordinates are wrapped back into the original piece of the map.
+    pub fn trace(&self, slope: Slope) -> Trace<'_> {
+        let y = if slope.1 < 0 {
+            self.height as isize - 1
+        } else {
+            0
+        };
+
+        Trace {
+            map: self,
+            slope,
+            position: Some((0, y)),
         }
     }
 
     /// Count the trees within the `Map` using a given slope, returning the number of trees
     /// encountered.
     pub fn count_trees_on_slope(&self, input: &Slope) -> usize {
-        let mut position = Coord(0, 0);
-        let mut trees = 0;
-
-        while position.1 < self.height {
-            if self
-                .tiles
-                .get(&position)
-                .expect("Tile is missing from map!")
-                == &Tile::Tree
-            {
-                trees += 1;
-            }
+        self.trace(*input)
+            .filter(|(_, tile)| *tile == Tile::Tree)
+            .count()
+    }
 
-            position.0 = (position.0 + input.0) % self.width;
-            position.1 += input.1;
+    /// The candidate slope that hits the fewest trees, along with how many trees it hits. The earliest candidate wins
+    /// any ties, returns `None` if there are no candidates.
+    pub fn best_slope(&self, candidates: &[Slope]) -> Option<(Slope, usize)> {
+        candidates
+            .iter()
+            .map(|slope| (*slope, self.count_trees_on_slope(slope)))
+            .fold(None, |best: Option<(Slope, usize)>, candidate| match best {
+                Some(best) if best.1 <= candidate.1 => Some(best),
+                _ => Some(candidate),
+            })
+    }
+}
+
+/// Iterator over the tiles visited on a slope, created by `Map::trace`
+#[derive(Debug, Clone)]
+pub struct Trace<'a> {
+    map: &'a Map,
+    slope: Slope,
+    /// Unwrapped position of the next tile, `None` once we have left the map
+    position: Option<(isize, isize)>,
+}
+
+impl<'a> Iterator for Trace<'a> {
+    type Item = (Coord, Tile);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, y) = self.position?;
+        if y < 0 || y as usize >= self.map.height || self.map.width == 0 {
+            self.position = None;
+            return None;
         }
 
-        trees
+        let coord = Coord(x.rem_euclid(self.map.width as isize) as usize, y as usize);
+        let tile = self.map.get(coord)?;
+
+        self.position = if self.slope.1 == 0 {
+            None
+        } else {
+            Some((x + self.slope.0, y + self.slope.1))
+        };
+
+        Some((coord, tile))
     }
 }
 
 #[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Map {
+pub fn input_generator(input: &str) -> Result<Map, ParseError> {
     Map::from_str(input)
 }
 
@@ -102,3 +229,157 @@ pub fn solve_part2(input: &Map) -> usize {
         * input.count_trees_on_slope(&Slope(7, 1))
         * input.count_trees_on_slope(&Slope(1, 2))
 }
+
+/// Registers day 3 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Map;
+
+    const DAY: u8 = 3;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "7");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "336");
+    }
+
+    fn map(rows: &[&str]) -> Map {
+        Map::from_str(&rows.join("\n")).unwrap()
+    }
+
+    #[test]
+    fn trace_visits_each_step() {
+        let map = input_generator(EXAMPLE).unwrap();
+        let visited: Vec<(Coord, Tile)> = map.trace(Slope(3, 1)).take(4).collect();
+
+        assert_eq!(
+            visited,
+            vec![
+                (Coord(0, 0), Tile::Empty),
+                (Coord(3, 1), Tile::Empty),
+                (Coord(6, 2), Tile::Tree),
+                (Coord(9, 3), Tile::Empty),
+            ]
+        );
+        assert_eq!(map.trace(Slope(3, 1)).count(), 11);
+        assert_eq!(map.trace(Slope(1, 2)).count(), 6);
+    }
+
+    #[test]
+    fn negative_slopes() {
+        let map = map(&["#..", ".#.", "..#"]);
+
+        // Going left wraps around to the right hand side
+        assert_eq!(
+            map.trace(Slope(-1, 1))
+                .map(|(coord, _)| coord)
+                .collect::<Vec<Coord>>(),
+            vec![Coord(0, 0), Coord(2, 1), Coord(1, 2)]
+        );
+        // Going up starts from the bottom row
+        assert_eq!(
+            map.trace(Slope(1, -1))
+                .map(|(coord, _)| coord)
+                .collect::<Vec<Coord>>(),
+            vec![Coord(0, 2), Coord(1, 1), Coord(2, 0)]
+        );
+        assert_eq!(map.count_trees_on_slope(&Slope(1, 1)), 3);
+        assert_eq!(map.count_trees_on_slope(&Slope(-1, -1)), 0);
+    }
+
+    #[test]
+    fn flat_slope_stops() {
+        let map = map(&["#..", ".#."]);
+        assert_eq!(map.trace(Slope(1, 0)).count(), 1);
+    }
+
+    #[test]
+    fn fractional_slopes() {
+        assert_eq!(Slope::per_row(1, 2), Some(Slope(1, 2)));
+        assert_eq!(Slope::per_row(3, 6), Some(Slope(1, 2)));
+        assert_eq!(Slope::per_row(3, -2), Some(Slope(-3, 2)));
+        assert_eq!(Slope::per_row(0, 4), Some(Slope(0, 1)));
+        assert_eq!(Slope::per_row(1, 0), None);
+
+        let map = input_generator(EXAMPLE).unwrap();
+        assert_eq!(map.count_trees_on_slope(&Slope::per_row(1, 2).unwrap()), 2);
+    }
+
+    #[test]
+    fn best_slope() {
+        let map = input_generator(EXAMPLE).unwrap();
+        let candidates = [
+            Slope(1, 1),
+            Slope(3, 1),
+            Slope(5, 1),
+            Slope(7, 1),
+            Slope(1, 2),
+        ];
+
+        // Right 1, down 1 and right 1, down 2 both hit 2 trees, the first one wins
+        assert_eq!(map.best_slope(&candidates), Some((Slope(1, 1), 2)));
+        assert_eq!(map.best_slope(&candidates[1..]), Some((Slope(1, 2), 2)));
+        assert_eq!(map.best_slope(&[]), None);
+    }
+
+    #[test]
+    fn wide_rows() {
+        // Rows wider than a single word, with trees on either side of the word boundary
+        let row: String = (0..130)
+            .map(|x| if x % 63 == 0 || x == 64 { '#' } else { '.' })
+            .collect();
+        let map = map(&[&row, &row]);
+
+        for x in 0..260 {
+            let expected = match x % 130 {
+                0 | 63 | 64 | 126 => Tile::Tree,
+                _ => Tile::Empty,
+            };
+            assert_eq!(map.get(Coord(x, 1)), Some(expected), "x = {}", x);
+        }
+        assert_eq!(map.get(Coord(0, 2)), None);
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let err = Map::from_str("..#\n.#\n#..").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+
+        let err = Map::from_str("..#\n.#..\n#..").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+    }
+}
@@ -1,66 +1,120 @@
+use fnv::FnvHashMap;
 use regex::Regex;
 
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod schema;
+
+pub use schema::{Constraint, FieldFailure, FieldReport, FieldRule, PassportReport, Schema};
+
+/// Part 1's policy, every field except `cid` has to be there
+pub const PRESENCE_SCHEMA: &str = r#"[byr]
+required = true
+
+[iyr]
+required = true
+
+[eyr]
+required = true
+
+[hgt]
+required = true
+
+[hcl]
+required = true
+
+[ecl]
+required = true
+
+[pid]
+required = true
+
+[cid]
+required = false
+"#;
+
+/// Part 2's policy, every field except `cid` has to be there and hold a sensible value
+pub const STRICT_SCHEMA: &str = r#"# Birth Year
+[byr]
+required = true
+regex = '[0-9]{4}'
+range = [1920, 2002]
+
+# Issue Year
+[iyr]
+required = true
+regex = '[0-9]{4}'
+range = [2010, 2020]
+
+# Expiration Year
+[eyr]
+required = true
+regex = '[0-9]{4}'
+range = [2020, 2030]
+
+# Height
+[hgt]
+required = true
+units = { cm = [150, 193], in = [59, 76] }
+
+# Hair Color
+[hcl]
+required = true
+regex = '#[0-9a-f]{6}'
+
+# Eye Color
+[ecl]
+required = true
+one_of = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]
+
+# Passport ID, a nine-digit number including leading zeroes
+[pid]
+required = true
+regex = '[0-9]{9}'
+
+# Country ID, ignored whether it is there or not
+[cid]
+"#;
+
 lazy_static! {
-    static ref PASSPORT_ENTRIES: Regex = Regex::new(r"([a-z]{3}):([^ \n]*)").unwrap();
-    static ref HEIGHT: Regex = Regex::new(r"(\d+)(in|cm)").unwrap();
-    static ref HAIR_COLOR: Regex = Regex::new(r"#[0-9a-f]{6}$").unwrap();
+    static ref PASSPORT_ENTRIES: Regex = Regex::new(r"^([a-z]{3}):(\S*)$").unwrap();
+    static ref PRESENCE: Schema = Schema::from_str(PRESENCE_SCHEMA).unwrap();
+    static ref STRICT: Schema = Schema::from_str(STRICT_SCHEMA).unwrap();
 }
 
-/// Holds all passport data fields
+/// Holds all passport data fields, keyed by their three letter name
 #[derive(Debug, Clone, Default)]
 pub struct PassportData {
-    /// Birth Year
-    byr: String,
-    /// Issue Year
-    iyr: String,
-    /// Expiration Year
-    eyr: String,
-    /// Height
-    hgt: String,
-    /// Hair Color
-    hcl: String,
-    /// Eye Color
-    ecl: String,
-    /// Passport ID
-    pid: String,
-    /// Country ID
-    cid: Option<String>,
+    fields: FnvHashMap<String, String>,
 }
 
 impl PassportData {
-    pub fn from_str(input: &str) -> Self {
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
         let mut init = PassportData::default();
 
-        for cap in PASSPORT_ENTRIES.captures_iter(input) {
-            match &cap[1] {
-                "byr" => init.byr = cap[2].into(),
-                "iyr" => init.iyr = cap[2].into(),
-                "eyr" => init.eyr = cap[2].into(),
-                "hgt" => init.hgt = cap[2].into(),
-                "hcl" => init.hcl = cap[2].into(),
-                "ecl" => init.ecl = cap[2].into(),
-                "pid" => init.pid = cap[2].into(),
-                "cid" => init.cid = Some(cap[2].into()),
-                _ => {}
-            }
+        for entry in input.split_whitespace() {
+            let cap = PASSPORT_ENTRIES.captures(entry).ok_or_else(|| {
+                ParseError::new(4, input, entry, ParseErrorKind::Malformed("`key:value`"))
+            })?;
+
+            init.fields.insert(cap[1].to_string(), cap[2].to_string());
         }
 
-        init
+        Ok(init)
+    }
+
+    /// The value of `field`, if the passport has it
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(String::as_str)
     }
 
-    /// Check that all fields, except `cid`, contain data of some sort by checking against the default
-    /// value of `String`
+    /// Check that all fields, except `cid`, contain data of some sort
     pub fn is_valid(&self) -> bool {
-        self.byr != String::default()
-            && self.iyr != String::default()
-            && self.eyr != String::default()
-            && self.hgt != String::default()
-            && self.hcl != String::default()
-            && self.ecl != String::default()
-            && self.pid != String::default()
+        PRESENCE.is_valid(self)
     }
 
-    /// Check that all fields within the `PassportData` are valid given the constraints:
+    /// Check that all fields within the `PassportData` are valid given the constraints in `STRICT_SCHEMA`:
     /// byr (Birth Year) - four digits; at least 1920 and at most 2002.
     /// iyr (Issue Year) - four digits; at least 2010 and at most 2020.
     /// eyr (Expiration Year) - four digits; at least 2020 and at most 2030.
@@ -72,83 +126,23 @@ impl PassportData {
     /// pid (Passport ID) - a nine-digit number, including leading zeroes.
     /// cid (Country ID) - ignored, missing or not.
     pub fn is_valid_constrained(&self) -> bool {
-        is_valid_range(&self.byr, 1920, 2002)
-            && is_valid_range(&self.iyr, 2010, 2020)
-            && is_valid_range(&self.eyr, 2020, 2030)
-            && self.is_height_valid()
-            && self.is_hair_color_valid()
-            && self.is_eye_color_valid()
-            && self.is_passport_id_valid()
+        STRICT.is_valid(self)
     }
 
-    /// Check if `self.hgt` is valid by verifying it contains `in` or `cm` and check those
-    /// values are within a set of bounds using `is_valid_range` generic helper
-    fn is_height_valid(&self) -> bool {
-        if let Some(caps) = HEIGHT.captures(&self.hgt) {
-            match &caps[2] {
-                "in" => is_valid_range(&caps[1], 59, 76),
-                "cm" => is_valid_range(&caps[1], 150, 193),
-                _ => false,
-            }
-        } else {
-            false
-        }
-    }
-
-    /// Check if `self.hcl` is valid by verifying it matches the format of a HTML hex color code
-    /// "#[0-9a-f]{6}$" in Regex form
-    fn is_hair_color_valid(&self) -> bool {
-        if let Some(_) = HAIR_COLOR.captures(&self.hcl) {
-            true
-        } else {
-            false
-        }
-    }
-
-    /// Check if `self.ecl` is valid by verifying it is one of 7 valid variants
-    fn is_eye_color_valid(&self) -> bool {
-        match self.ecl.as_str() {
-            "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth" => true,
-            _ => false,
-        }
-    }
-
-    /// Check if `self.pid` is valid by verifying the length is 9 and it parses into a valid number
-    fn is_passport_id_valid(&self) -> bool {
-        if self.pid.len() != 9 {
-            return false;
-        }
-
-        if let Ok(_) = self.pid.parse::<usize>() {
-            true
-        } else {
-            false
-        }
-    }
-}
-
-/// Generic helper that takes a &str, parses it to check it is a valid `usize` then checks it is not less than `min`
-/// or greater than `max`
-fn is_valid_range(value: &str, min: usize, max: usize) -> bool {
-    if let Ok(val) = value.parse::<usize>() {
-        if (min <= val) && (val <= max) {
-            true
-        } else {
-            false
-        }
-    } else {
-        false
+    /// Check the passport against `schema`, reporting which constraint each field broke
+    pub fn validate(&self, schema: &Schema) -> PassportReport {
+        schema.validate(self)
     }
 }
 
 #[aoc_generator(day4)]
-pub fn input_generator(input: &str) -> Vec<PassportData> {
-    let vec: Vec<PassportData> = input
+pub fn input_generator(input: &str) -> Result<Vec<PassportData>, ParseError> {
+    input
         .split("\n\n")
-        .map(|raw_passport| PassportData::from_str(raw_passport))
-        .collect();
-
-    vec
+        .map(|raw_passport| {
+            PassportData::from_str(raw_passport).map_err(|err| err.within(input, raw_passport))
+        })
+        .collect()
 }
 
 #[aoc(day4, part1)]
@@ -163,3 +157,223 @@ pub fn solve_part2(input: &[PassportData]) -> usize {
         .filter(|ppd| ppd.is_valid_constrained() == true)
         .count()
 }
+
+/// Registers day 4 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<PassportData>;
+
+    const DAY: u8 = 4;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in";
+
+    const INVALID: &str = "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007";
+
+    const VALID: &str = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:#888785
+hgt:164cm byr:2001 iyr:2015 cid:88
+pid:545766238 ecl:hzl
+eyr:2022
+
+iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "2");
+    }
+
+    #[test]
+    fn part2_examples() {
+        assert_variants::<Solution>(INVALID, Part::Two, "0");
+        assert_variants::<Solution>(VALID, Part::Two, "4");
+    }
+
+    #[test]
+    fn field_reports() {
+        let passport = PassportData::from_str(
+            "eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926",
+        )
+        .unwrap();
+        let report = passport.validate(&STRICT);
+
+        assert!(!report.is_valid());
+        let failures: Vec<(&str, &[FieldFailure])> = report
+            .failures()
+            .map(|field| (field.field.as_str(), field.failures.as_slice()))
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (
+                    "eyr",
+                    &[FieldFailure::OutOfRange {
+                        min: 2020,
+                        max: 2030
+                    }][..]
+                ),
+                ("hgt", &[FieldFailure::UnknownUnit(String::new())][..]),
+                (
+                    "pid",
+                    &[FieldFailure::NoMatch(String::from("[0-9]{9}"))][..]
+                ),
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "eyr (\"1972\"): not between 2020 and 2030; hgt (\"170\"): unknown unit \"\"; \
+             pid (\"186cm\"): doesn't match /[0-9]{9}/"
+        );
+    }
+
+    #[test]
+    fn missing_fields() {
+        let passport = PassportData::from_str("hcl:#cfa07d eyr:2025 pid:166559648 byr:").unwrap();
+        let report = passport.validate(&PRESENCE);
+
+        let missing: Vec<&str> = report
+            .failures()
+            .filter(|field| field.failures == [FieldFailure::Missing])
+            .map(|field| field.field.as_str())
+            .collect();
+        assert_eq!(missing, vec!["byr", "iyr", "hgt", "ecl"]);
+    }
+
+    #[test]
+    fn custom_schema() {
+        let schema = Schema::from_str(
+            r##"
+            # Comments can go anywhere, but a # inside a string is just a #
+            [hcl]
+            required = true
+            regex = "#[0-9a-f]{3}"   # short colors only
+
+            [hgt]
+            units = { "m" = [1, 2], mm = [1000, 2000], }
+            length = 4
+
+            [xyz]
+            one_of = ['a', 'b']
+            "##,
+        )
+        .unwrap();
+
+        let check = |passport: &str| {
+            let report = PassportData::from_str(passport).unwrap().validate(&schema);
+            report
+                .fields
+                .iter()
+                .map(|field| field.failures.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(check("hcl:#abc"), vec![vec![], vec![], vec![]]);
+        assert_eq!(
+            check("hcl:#abcdef hgt:2500mm xyz:c"),
+            vec![
+                vec![FieldFailure::NoMatch(String::from("#[0-9a-f]{3}"))],
+                vec![
+                    FieldFailure::OutOfRange {
+                        min: 1000,
+                        max: 2000
+                    },
+                    FieldFailure::WrongLength(4)
+                ],
+                vec![FieldFailure::NotOneOf(vec![
+                    String::from("a"),
+                    String::from("b")
+                ])],
+            ]
+        );
+        assert_eq!(
+            check("hgt:1.5m xyz:a"),
+            vec![
+                vec![FieldFailure::Missing],
+                vec![FieldFailure::UnknownUnit(String::from(".5m"))],
+                vec![]
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_errors() {
+        let err = Schema::from_str("required = true").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::Missing("`[<field>]` before the first key")
+        );
+
+        let err = Schema::from_str("[byr]\nrequired = true\nrnage = [1, 2]").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (3, 1, ParseErrorKind::UnknownKeyword)
+        );
+
+        let err = Schema::from_str("[byr]\nrange = [1, 2").unwrap_err();
+        assert_eq!(
+            (err.line, err.kind),
+            (2, ParseErrorKind::Malformed("`,` or `]`"))
+        );
+
+        let err = Schema::from_str("[byr]\nrange = [1, \"2\"]").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 9));
+
+        let err = Schema::from_str("[hcl]\nregex = '#[0-9'").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::Malformed("a valid regular expression")
+        );
+
+        let err = Schema::from_str("[hcl]\nregex = \"abc").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::Missing("closing quote"));
+
+        let err = Schema::from_str("[byr]\n[byr]").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
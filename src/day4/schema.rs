@@ -0,0 +1,513 @@
+use std::fmt;
+
+use regex::Regex;
+
+use super::PassportData;
+use crate::cursor::Cursor;
+use crate::{ParseError, ParseErrorKind};
+
+/// A value on the right hand side of a `key = value` line in a schema
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<Value>),
+    Table(Vec<(String, Value)>),
+}
+
+/// A single check applied to a field's value
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    /// The value is a number between `min` and `max` (inclusive)
+    Range { min: i64, max: i64 },
+    /// The value is a number followed by one of the units, and the number is within that unit's range
+    Units(Vec<(String, i64, i64)>),
+    /// The whole value matches `pattern`, `regex` is the anchored version of it
+    Regex { pattern: String, regex: Regex },
+    /// The value is one of the listed values
+    OneOf(Vec<String>),
+    /// The value is exactly this many characters long
+    Length(usize),
+}
+
+impl Constraint {
+    fn check(&self, value: &str) -> Result<(), FieldFailure> {
+        match self {
+            Self::Range { min, max } => check_range(value, *min, *max),
+            Self::Units(units) => {
+                let split = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(split);
+                if number.is_empty() {
+                    return Err(FieldFailure::NotANumber);
+                }
+
+                let (_, min, max) = units
+                    .iter()
+                    .find(|(name, _, _)| name == unit)
+                    .ok_or_else(|| FieldFailure::UnknownUnit(unit.to_string()))?;
+                check_range(number, *min, *max)
+            }
+            Self::Regex { pattern, regex } => {
+                if regex.is_match(value) {
+                    Ok(())
+                } else {
+                    Err(FieldFailure::NoMatch(pattern.clone()))
+                }
+            }
+            Self::OneOf(values) => {
+                if values.iter().any(|allowed| allowed == value) {
+                    Ok(())
+                } else {
+                    Err(FieldFailure::NotOneOf(values.clone()))
+                }
+            }
+            Self::Length(length) => {
+                if value.chars().count() == *length {
+                    Ok(())
+                } else {
+                    Err(FieldFailure::WrongLength(*length))
+                }
+            }
+        }
+    }
+}
+
+fn check_range(value: &str, min: i64, max: i64) -> Result<(), FieldFailure> {
+    let number = value.parse::<i64>().map_err(|_| FieldFailure::NotANumber)?;
+    if min <= number && number <= max {
+        Ok(())
+    } else {
+        Err(FieldFailure::OutOfRange { min, max })
+    }
+}
+
+/// Everything the schema declares about one passport field
+#[derive(Debug, Clone)]
+pub struct FieldRule {
+    pub name: String,
+    /// A missing (or empty) field is only a failure if it is required, optional fields are only checked when present
+    pub required: bool,
+    pub constraints: Vec<Constraint>,
+}
+
+/// Why a field failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldFailure {
+    Missing,
+    NotANumber,
+    OutOfRange { min: i64, max: i64 },
+    UnknownUnit(String),
+    NoMatch(String),
+    NotOneOf(Vec<String>),
+    WrongLength(usize),
+}
+
+impl fmt::Display for FieldFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing"),
+            Self::NotANumber => write!(f, "not a number"),
+            Self::OutOfRange { min, max } => write!(f, "not between {} and {}", min, max),
+            Self::UnknownUnit(unit) => write!(f, "unknown unit {:?}", unit),
+            Self::NoMatch(pattern) => write!(f, "doesn't match /{}/", pattern),
+            Self::NotOneOf(values) => write!(f, "not one of {}", values.join(", ")),
+            Self::WrongLength(length) => write!(f, "not {} characters long", length),
+        }
+    }
+}
+
+/// The outcome of checking one field of a passport
+#[derive(Debug, Clone)]
+pub struct FieldReport {
+    pub field: String,
+    pub value: Option<String>,
+    /// Every constraint the field broke, empty if the field is valid
+    pub failures: Vec<FieldFailure>,
+}
+
+impl FieldReport {
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The outcome of checking a passport against a `Schema`, with one `FieldReport` per field in the schema
+#[derive(Debug, Clone)]
+pub struct PassportReport {
+    pub fields: Vec<FieldReport>,
+}
+
+impl PassportReport {
+    pub fn is_valid(&self) -> bool {
+        self.fields.iter().all(FieldReport::is_valid)
+    }
+
+    /// Only the fields that failed
+    pub fn failures(&self) -> impl Iterator<Item = &FieldReport> {
+        self.fields.iter().filter(|field| !field.is_valid())
+    }
+}
+
+impl fmt::Display for PassportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "valid");
+        }
+
+        for (idx, field) in self.failures().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", field.field)?;
+            if let Some(value) = &field.value {
+                write!(f, " ({:?})", value)?;
+            }
+            for (idx, failure) in field.failures.iter().enumerate() {
+                write!(f, "{} {}", if idx == 0 { ":" } else { "," }, failure)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The passport policy, declared in a small TOML-like format so it can change without recompiling. Each field gets a
+/// `[name]` section holding `key = value` lines, `#` starts a comment:
+///
+/// ```text
+/// [hgt]
+/// required = true
+/// units = { cm = [150, 193], in = [59, 76] }
+///
+/// [ecl]
+/// required = true
+/// one_of = ["amb", "blu", "brn"]
+/// ```
+///
+/// The keys are `required` (bool, defaults to false), `range` (`[min, max]`), `units` (a table of unit to
+/// `[min, max]`), `regex` (a string the whole value has to match), `one_of` (an array of strings) and `length` (a
+/// number of characters). Strings can use double quotes, where `\"` and `\\` are escapes, or single quotes, where
+/// nothing is.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub fields: Vec<FieldRule>,
+}
+
+impl Schema {
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let error = |fragment: &str, kind| ParseError::new(4, input, fragment, kind);
+        let mut fields: Vec<FieldRule> = Vec::new();
+
+        for raw_line in input.lines() {
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                let name = line
+                    .strip_prefix('[')
+                    .and_then(|line| line.strip_suffix(']'))
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| error(line, ParseErrorKind::Malformed("`[<field>]`")))?;
+                if fields.iter().any(|field| field.name == name) {
+                    return Err(error(
+                        name,
+                        ParseErrorKind::Malformed("each field to be declared once"),
+                    ));
+                }
+
+                fields.push(FieldRule {
+                    name: name.to_string(),
+                    required: false,
+                    constraints: Vec::new(),
+                });
+                continue;
+            }
+
+            let field = fields.last_mut().ok_or_else(|| {
+                error(
+                    line,
+                    ParseErrorKind::Missing("`[<field>]` before the first key"),
+                )
+            })?;
+
+            let equals = line
+                .find('=')
+                .ok_or_else(|| error(line, ParseErrorKind::Malformed("`<key> = <value>`")))?;
+            let key = line[..equals].trim();
+            let raw_value = line[equals + 1..].trim();
+            let mut reader = ValueReader {
+                cursor: Cursor::new(4, input, raw_value),
+            };
+            let value = reader.value()?;
+            reader.cursor.end()?;
+
+            let kind = |expected| ParseErrorKind::Malformed(expected);
+            match key {
+                "required" => match value {
+                    Value::Bool(required) => field.required = required,
+                    _ => return Err(error(raw_value, kind("`true` or `false`"))),
+                },
+                "range" => {
+                    let (min, max) = as_range(&value)
+                        .ok_or_else(|| error(raw_value, kind("`[<min>, <max>]`")))?;
+                    field.constraints.push(Constraint::Range { min, max });
+                }
+                "units" => {
+                    let units = match &value {
+                        Value::Table(entries) => entries
+                            .iter()
+                            .map(|(unit, range)| {
+                                as_range(range).map(|(min, max)| (unit.clone(), min, max))
+                            })
+                            .collect::<Option<Vec<_>>>(),
+                        _ => None,
+                    }
+                    .ok_or_else(|| error(raw_value, kind("`{ <unit> = [<min>, <max>], ... }`")))?;
+                    field.constraints.push(Constraint::Units(units));
+                }
+                "regex" => match value {
+                    Value::String(pattern) => {
+                        let regex = Regex::new(&format!("^(?:{})$", pattern))
+                            .map_err(|_| error(raw_value, kind("a valid regular expression")))?;
+                        field.constraints.push(Constraint::Regex { pattern, regex });
+                    }
+                    _ => return Err(error(raw_value, kind("a quoted regular expression"))),
+                },
+                "one_of" => {
+                    let values = match &value {
+                        Value::Array(values) => values
+                            .iter()
+                            .map(|value| match value {
+                                Value::String(value) => Some(value.clone()),
+                                _ => None,
+                            })
+                            .collect::<Option<Vec<String>>>(),
+                        _ => None,
+                    }
+                    .ok_or_else(|| error(raw_value, kind("an array of strings")))?;
+                    field.constraints.push(Constraint::OneOf(values));
+                }
+                "length" => match value {
+                    Value::Integer(length) if length >= 0 => {
+                        field.constraints.push(Constraint::Length(length as usize))
+                    }
+                    _ => return Err(error(raw_value, kind("a number of characters"))),
+                },
+                _ => return Err(error(key, ParseErrorKind::UnknownKeyword)),
+            }
+        }
+
+        Ok(Schema { fields })
+    }
+
+    /// Check every field the schema declares against `passport`, fields the schema doesn't mention are ignored
+    pub fn validate(&self, passport: &PassportData) -> PassportReport {
+        let fields = self
+            .fields
+            .iter()
+            .map(|rule| {
+                let value = passport.get(&rule.name).filter(|value| !value.is_empty());
+                let failures = match value {
+                    None if rule.required => vec![FieldFailure::Missing],
+                    None => Vec::new(),
+                    Some(value) => rule
+                        .constraints
+                        .iter()
+                        .filter_map(|constraint| constraint.check(value).err())
+                        .collect(),
+                };
+
+                FieldReport {
+                    field: rule.name.clone(),
+                    value: value.map(String::from),
+                    failures,
+                }
+            })
+            .collect();
+
+        PassportReport { fields }
+    }
+
+    pub fn is_valid(&self, passport: &PassportData) -> bool {
+        self.validate(passport).is_valid()
+    }
+}
+
+/// `[min, max]` as a pair of numbers
+fn as_range(value: &Value) -> Option<(i64, i64)> {
+    match value {
+        Value::Array(bounds) => match bounds.as_slice() {
+            [Value::Integer(min), Value::Integer(max)] => Some((*min, *max)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Cut a line off at the first `#` that isn't inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), _) if c == open && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..idx],
+            _ => {}
+        }
+        escaped = false;
+    }
+
+    line
+}
+
+/// Reads a single value from the rest of a `key = value` line
+struct ValueReader<'a> {
+    cursor: Cursor<'a>,
+}
+
+impl<'a> ValueReader<'a> {
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.cursor.skip_whitespace();
+        match self.cursor.peek() {
+            Some('"') | Some('\'') => self.string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.table(),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.integer(),
+            Some(_) => {
+                let word = self.word();
+                match word {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => {
+                        self.cursor.rewind(self.cursor.pos() - word.len());
+                        Err(self.cursor.error(ParseErrorKind::Malformed(
+                            "a string, number, boolean, array or table",
+                        )))
+                    }
+                }
+            }
+            None => Err(self.cursor.error(ParseErrorKind::Missing("value"))),
+        }
+    }
+
+    /// A bare word, used for keys and booleans
+    fn word(&mut self) -> &'a str {
+        let rest = self.cursor.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        self.cursor.advance(len);
+        &rest[..len]
+    }
+
+    fn integer(&mut self) -> Result<Value, ParseError> {
+        let start = self.cursor.pos();
+        if self.cursor.peek() == Some('-') {
+            self.cursor.advance(1);
+        }
+        let rest = self.cursor.rest();
+        self.cursor.advance(
+            rest.find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len()),
+        );
+
+        let text = self.cursor.since(start);
+        text.parse().map(Value::Integer).map_err(|_| {
+            ParseError::new(4, self.cursor.input(), text, ParseErrorKind::InvalidNumber)
+        })
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let start = self.cursor.pos();
+        let quote = self.cursor.peek().unwrap();
+        self.cursor.advance(1);
+
+        let mut value = String::new();
+        let mut chars = self.cursor.rest().char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                _ if c == quote => {
+                    self.cursor.advance(idx + 1);
+                    return Ok(value);
+                }
+                '\\' if quote == '"' => match chars.next() {
+                    Some((_, escaped)) if escaped == '"' || escaped == '\\' => value.push(escaped),
+                    // Anything else is kept as is, so regexes don't need every backslash doubled
+                    Some((_, other)) => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                    None => break,
+                },
+                _ => value.push(c),
+            }
+        }
+
+        Err(ParseError::new(
+            4,
+            self.cursor.input(),
+            &self.cursor.text()[start..],
+            ParseErrorKind::Missing("closing quote"),
+        ))
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.cursor.advance(1);
+        let mut values = Vec::new();
+        loop {
+            if self.cursor.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            if self.cursor.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            if !self.cursor.eat(',') {
+                return Err(self.cursor.error(ParseErrorKind::Malformed("`,` or `]`")));
+            }
+        }
+    }
+
+    fn table(&mut self) -> Result<Value, ParseError> {
+        self.cursor.advance(1);
+        let mut entries = Vec::new();
+        loop {
+            if self.cursor.eat('}') {
+                return Ok(Value::Table(entries));
+            }
+
+            self.cursor.skip_whitespace();
+            let key = match self.cursor.peek() {
+                Some('"') | Some('\'') => self.string()?,
+                _ => self.word().to_string(),
+            };
+            if key.is_empty() {
+                return Err(self.cursor.error(ParseErrorKind::Missing("key")));
+            }
+            if !self.cursor.eat('=') {
+                return Err(self
+                    .cursor
+                    .error(ParseErrorKind::Malformed("`<key> = <value>`")));
+            }
+            entries.push((key, self.value()?));
+
+            if self.cursor.eat('}') {
+                return Ok(Value::Table(entries));
+            }
+            if !self.cursor.eat(',') {
+                return Err(self.cursor.error(ParseErrorKind::Malformed("`,` or `}`")));
+            }
+        }
+    }
+}
@@ -1,71 +1,114 @@
-use fnv::FnvHashMap;
-use regex::Regex;
-// use std::collections::HashMap;
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
 
-lazy_static! {
-    static ref BOARDING_PASS: Regex = Regex::new(r"([FB]{7})([LR]{3})").unwrap();
-}
+pub mod seating;
+
+pub use seating::{Geometry, SeatMap};
 
 /// Holds all Boarding pass fields
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct BoardingPass {
     /// Seat Row
     row: usize,
     /// Seat Column
     column: usize,
+    /// Plane the pass was issued for
+    geometry: Geometry,
 }
 
 impl BoardingPass {
-    pub fn from_str(input: &str) -> Self {
-        let boarding_pass = BOARDING_PASS
-            .captures(input)
-            .expect("Error parsing boarding pass data");
-
-        let row = boarding_pass
-            .get(1)
-            .expect("Error getting row capture value")
-            .as_str()
-            .chars()
-            .enumerate()
-            .map(|(idx, val)| match val {
-                'B' => 1 << (6 - (idx)),
-                'F' => 0,
-                _ => unreachable!(),
-            })
-            .sum();
-
-        let column = boarding_pass
-            .get(2)
-            .expect("Error getting column capture value")
-            .as_str()
-            .chars()
-            .enumerate()
-            .map(|(idx, val)| match val {
-                'R' => 1 << (2 - (idx)),
-                'L' => 0,
-                _ => unreachable!(),
-            })
-            .sum();
-
-        BoardingPass { row, column }
+    /// Decode a pass for the standard plane, 7 `F`/`B` characters followed by 3 `L`/`R` characters
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        Self::from_str_with(input, Geometry::STANDARD)
+    }
+
+    /// Decode a pass for a plane of any size, one `F`/`B` character per row bit followed by one `L`/`R` character per
+    /// column bit
+    pub fn from_str_with(input: &str, geometry: Geometry) -> Result<Self, ParseError> {
+        // Point at the first character that can't be part of a boarding pass before falling back to a general error
+        if let Some((idx, c)) = input
+            .char_indices()
+            .find(|(_, c)| !matches!(c, 'F' | 'B' | 'L' | 'R'))
+        {
+            return Err(ParseError::new(
+                5,
+                input,
+                &input[idx..idx + c.len_utf8()],
+                ParseErrorKind::UnexpectedChar(c),
+            ));
+        }
+
+        // Only ASCII is left at this point, so bytes and characters line up
+        let row_bits = geometry.row_bits() as usize;
+        let well_formed = input.len() == geometry.code_len()
+            && input[..row_bits].bytes().all(|b| matches!(b, b'F' | b'B'))
+            && input[row_bits..].bytes().all(|b| matches!(b, b'L' | b'R'));
+        if !well_formed {
+            return Err(ParseError::new(
+                5,
+                input,
+                input,
+                ParseErrorKind::Malformed(
+                    "one `F`/`B` character per row bit followed by one `L`/`R` character per column bit",
+                ),
+            ));
+        }
+
+        // Each character is a bit, most significant first, which makes the row and column plain binary numbers
+        let binary = |code: &str, one: u8| {
+            code.bytes()
+                .fold(0, |acc, b| acc << 1 | if b == one { 1 } else { 0 })
+        };
+
+        Ok(BoardingPass {
+            row: binary(&input[..row_bits], b'B'),
+            column: binary(&input[row_bits..], b'R'),
+            geometry,
+        })
+    }
+
+    /// Pass for a seat ID on the given plane, `None` if the plane doesn't have that seat
+    pub fn from_seat_id(seat_id: usize, geometry: Geometry) -> Option<Self> {
+        geometry.seat(seat_id).map(|(row, column)| BoardingPass {
+            row,
+            column,
+            geometry,
+        })
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// The boarding pass code this pass decodes from
+    pub fn encode(&self) -> String {
+        self.geometry
+            .encode(self.get_seat_id())
+            .expect("Boarding pass for a seat outside its own plane")
     }
 }
 
 impl BoardingPass {
     /// Simple Unique seat ID generation based on binary conversion
     pub fn get_seat_id(&self) -> usize {
-        self.row * 8 + self.column
+        self.row * self.geometry.columns() + self.column
     }
 }
 
 #[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Vec<BoardingPass> {
-    let vec: Vec<BoardingPass> = input
+pub fn input_generator(input: &str) -> Result<Vec<BoardingPass>, ParseError> {
+    input
         .lines()
-        .map(|bpdata| BoardingPass::from_str(bpdata))
-        .collect();
-
-    vec
+        .map(|bpdata| BoardingPass::from_str(bpdata).map_err(|err| err.within(input, bpdata)))
+        .collect()
 }
 
 #[aoc(day5, part1)]
@@ -79,23 +122,170 @@ pub fn solve_part1(input: &[BoardingPass]) -> usize {
 
 #[aoc(day5, part2)]
 pub fn solve_part2(input: &[BoardingPass]) -> usize {
-    let mut seat_map = FnvHashMap::default();
-    for bp in input {
-        seat_map.insert(bp.get_seat_id(), bp);
+    // The seats at the very front and back don't exist on this plane, so ours is the only empty seat with a taken
+    // seat on either side of it
+    match SeatMap::from_passes(Geometry::STANDARD, input)
+        .candidate_seats()
+        .as_slice()
+    {
+        [seat_id] => *seat_id,
+        [] => panic!("Could not find a seat matching the requirements"),
+        candidates => panic!("Several seats match the requirements: {:?}", candidates),
     }
+}
 
-    seat_map
-        .iter()
-        // We only have a list of everyone else's boarding passes, so we don't know where the hole is
-        // we need to modify the requirements to a - 2/-1 instead of a +1/-1 that way we know we can find the seat
-        // with the data we actually have
-        .find(|(key, _)| {
-            seat_map.get(&(*key - 2)).is_some() && seat_map.get(&(*key - 1)).is_none()
-        })
-        // At this point we should have the key-value pair that we need
+#[aoc(day5, part2, sorted)]
+pub fn solve_part2_sorted(input: &[BoardingPass]) -> usize {
+    let mut seat_ids: Vec<usize> = input.iter().map(BoardingPass::get_seat_id).collect();
+    seat_ids.sort_unstable();
+
+    seat_ids
+        .windows(2)
+        // Everyone else is sat next to someone, so the first pair of passes two seats apart surrounds our seat
+        .find(|pair| pair[1] == pair[0] + 2)
+        .map(|pair| pair[0] + 1)
         .expect("Could not find a seat matching the requirements")
-        // We get the key, which is the seat id
-        .0
-        // Then we subtract one, because of how we did the search, we actually searched off of the seat +1 to ours
-        - 1
+}
+
+/// Registers day 5 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<BoardingPass>;
+
+    const DAY: u8 = 5;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+            Variant::new(Part::Two, Some("sorted"), |input| {
+                solve_part2_sorted(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    #[test]
+    fn seat_ids() {
+        for (code, id) in [
+            ("FBFBBFFRLR", 357),
+            ("BFFFBBFRRR", 567),
+            ("FFFBBBFRRR", 119),
+            ("BBFFBBFRLL", 820),
+        ]
+        .iter()
+        {
+            assert_eq!(BoardingPass::from_str(code).unwrap().get_seat_id(), *id);
+        }
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(
+            "FBFBBFFRLR\nBFFFBBFRRR\nFFFBBBFRRR\nBBFFBBFRLL",
+            Part::One,
+            "820",
+        );
+    }
+
+    #[test]
+    fn part2_finds_gap() {
+        // Seats 8 through 19 with seat 14 missing
+        let input = "FFFFFFBLLL
+FFFFFFBLLR
+FFFFFFBLRL
+FFFFFFBLRR
+FFFFFFBRLL
+FFFFFFBRLR
+FFFFFFBRRR
+FFFFFBFLLL
+FFFFFBFLLR
+FFFFFBFLRL
+FFFFFBFLRR
+FFFFFBFRLL";
+        assert_variants::<Solution>(input, Part::Two, "14");
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        assert_eq!(Geometry::STANDARD.encode(357).unwrap(), "FBFBBFFRLR");
+        assert_eq!(Geometry::STANDARD.encode(1024), None);
+
+        for seat_id in 0..Geometry::STANDARD.seat_count() {
+            let pass = BoardingPass::from_seat_id(seat_id, Geometry::STANDARD).unwrap();
+            let decoded = BoardingPass::from_str(&pass.encode()).unwrap();
+            assert_eq!(decoded, pass);
+            assert_eq!(decoded.get_seat_id(), seat_id);
+        }
+    }
+
+    #[test]
+    fn custom_geometry() {
+        let small = Geometry::new(3, 2).unwrap();
+        assert_eq!((small.rows(), small.columns()), (8, 4));
+
+        let pass = BoardingPass::from_str_with("BFBRL", small).unwrap();
+        assert_eq!((pass.row(), pass.column(), pass.get_seat_id()), (5, 2, 22));
+        assert_eq!(pass.encode(), "BFBRL");
+
+        // The standard layout doesn't fit this plane
+        let err = BoardingPass::from_str_with("FBFBBFFRLR", small).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Malformed(_)));
+        let err = BoardingPass::from_str_with("BFRBL", small).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Malformed(_)));
+
+        assert_eq!(Geometry::new(20, 5), None);
+    }
+
+    #[test]
+    fn bad_characters() {
+        let err = input_generator("FBFBBFFRLR\nFBFBXFFRLR").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('X'));
+        assert_eq!((err.line, err.column), (2, 5));
+    }
+
+    #[test]
+    fn seat_map() {
+        let small = Geometry::new(2, 2).unwrap();
+        let passes: Vec<BoardingPass> = [2, 3, 4, 6, 8, 9, 10]
+            .iter()
+            .map(|seat_id| BoardingPass::from_seat_id(*seat_id, small).unwrap())
+            .chain(BoardingPass::from_str("FFFFFFBRRR"))
+            .collect();
+        let seat_map = SeatMap::from_passes(small, &passes);
+
+        assert_eq!(seat_map.occupied_count(), 7);
+        assert_eq!(seat_map.empty_seats(), vec![0, 1, 5, 7, 11, 12, 13, 14, 15]);
+        assert_eq!(seat_map.gaps(), vec![0..2, 5..6, 7..8, 11..16]);
+        assert_eq!(seat_map.candidate_seats(), vec![5, 7]);
+        assert_eq!(seat_map.out_of_range(), &passes[7..]);
+        assert_eq!(
+            seat_map.chart(),
+            "0 .. ##
+1 #O #O
+2 ## #.
+3 .. ..
+out of range: FFFFFFBRRR (row 1, column 7)
+"
+        );
+    }
+
+    #[test]
+    fn part2_variants_agree() {
+        let input: Vec<String> = (40..=60)
+            .filter(|seat_id| *seat_id != 51)
+            .map(|seat_id| Geometry::STANDARD.encode(seat_id).unwrap())
+            .collect();
+        assert_variants::<Solution>(&input.join("\n"), Part::Two, "51");
+    }
 }
@@ -0,0 +1,254 @@
+use std::fmt;
+use std::ops::Range;
+
+use super::BoardingPass;
+
+/// The layout of a plane, boarding pass codes use one `F`/`B` character per row bit and one `L`/`R` character per
+/// column bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    row_bits: u32,
+    column_bits: u32,
+}
+
+impl Geometry {
+    /// The plane from the puzzle, 128 rows of 8 seats
+    pub const STANDARD: Geometry = Geometry {
+        row_bits: 7,
+        column_bits: 3,
+    };
+
+    /// Largest number of bits a seat ID can have, which keeps a `SeatMap` of the whole plane at a sane size
+    pub const MAX_BITS: u32 = 24;
+
+    /// Plane with `2^row_bits` rows of `2^column_bits` seats, `None` if it would have more than `2^MAX_BITS` seats
+    pub fn new(row_bits: u32, column_bits: u32) -> Option<Self> {
+        if row_bits + column_bits > Self::MAX_BITS {
+            return None;
+        }
+
+        Some(Geometry {
+            row_bits,
+            column_bits,
+        })
+    }
+
+    pub fn row_bits(&self) -> u32 {
+        self.row_bits
+    }
+
+    pub fn column_bits(&self) -> u32 {
+        self.column_bits
+    }
+
+    pub fn rows(&self) -> usize {
+        1 << self.row_bits
+    }
+
+    pub fn columns(&self) -> usize {
+        1 << self.column_bits
+    }
+
+    /// Number of seats in the plane, every seat ID is below this
+    pub fn seat_count(&self) -> usize {
+        1 << (self.row_bits + self.column_bits)
+    }
+
+    /// Length of a boarding pass code for this plane
+    pub fn code_len(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+
+    /// Seat ID of a seat, `None` if the plane doesn't have that seat
+    pub fn seat_id(&self, row: usize, column: usize) -> Option<usize> {
+        if row < self.rows() && column < self.columns() {
+            Some(row << self.column_bits | column)
+        } else {
+            None
+        }
+    }
+
+    /// Row and column of a seat ID, `None` if the plane doesn't have that seat
+    pub fn seat(&self, seat_id: usize) -> Option<(usize, usize)> {
+        if seat_id < self.seat_count() {
+            Some((seat_id >> self.column_bits, seat_id & (self.columns() - 1)))
+        } else {
+            None
+        }
+    }
+
+    /// Boarding pass code of a seat ID, `None` if the plane doesn't have that seat
+    pub fn encode(&self, seat_id: usize) -> Option<String> {
+        if seat_id >= self.seat_count() {
+            return None;
+        }
+
+        let code = (0..self.code_len())
+            .rev()
+            .enumerate()
+            .map(|(idx, bit)| {
+                let set = seat_id >> bit & 1 == 1;
+                match (idx < self.row_bits as usize, set) {
+                    (true, false) => 'F',
+                    (true, true) => 'B',
+                    (false, false) => 'L',
+                    (false, true) => 'R',
+                }
+            })
+            .collect();
+
+        Some(code)
+    }
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Which seats of a plane are taken, built from the boarding passes that were scanned
+#[derive(Debug, Clone)]
+pub struct SeatMap {
+    geometry: Geometry,
+    occupied: Vec<bool>,
+    /// Passes for seats this plane doesn't have
+    out_of_range: Vec<BoardingPass>,
+}
+
+impl SeatMap {
+    /// Map of an empty plane
+    pub fn new(geometry: Geometry) -> Self {
+        SeatMap {
+            geometry,
+            occupied: vec![false; geometry.seat_count()],
+            out_of_range: Vec::new(),
+        }
+    }
+
+    pub fn from_passes(geometry: Geometry, passes: &[BoardingPass]) -> Self {
+        let mut seat_map = Self::new(geometry);
+        for pass in passes {
+            seat_map.insert(pass);
+        }
+
+        seat_map
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Mark the seat of `pass` as taken. Passes are placed by row and column, so a pass decoded for a different plane
+    /// still lands on the right seat if this plane has it. Returns `false`, and keeps the pass aside, if it doesn't.
+    pub fn insert(&mut self, pass: &BoardingPass) -> bool {
+        match self.geometry.seat_id(pass.row(), pass.column()) {
+            Some(seat_id) => {
+                self.occupied[seat_id] = true;
+                true
+            }
+            None => {
+                self.out_of_range.push(pass.clone());
+                false
+            }
+        }
+    }
+
+    pub fn is_occupied(&self, seat_id: usize) -> bool {
+        self.occupied.get(seat_id).copied().unwrap_or(false)
+    }
+
+    pub fn occupied_count(&self) -> usize {
+        self.occupied.iter().filter(|taken| **taken).count()
+    }
+
+    /// Every seat ID nobody has a pass for, in order
+    pub fn empty_seats(&self) -> Vec<usize> {
+        (0..self.occupied.len())
+            .filter(|seat_id| !self.occupied[*seat_id])
+            .collect()
+    }
+
+    /// Runs of consecutive empty seat IDs, including the missing seats at the very front and back of the plane
+    pub fn gaps(&self) -> Vec<Range<usize>> {
+        let mut gaps = Vec::new();
+        let mut start = None;
+
+        for (seat_id, taken) in self.occupied.iter().enumerate() {
+            match (start, taken) {
+                (None, false) => start = Some(seat_id),
+                (Some(first), true) => {
+                    gaps.push(first..seat_id);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(first) = start {
+            gaps.push(first..self.occupied.len());
+        }
+
+        gaps
+    }
+
+    /// Empty seats whose neighbouring seat IDs are both taken, which is where the missing passenger should be
+    pub fn candidate_seats(&self) -> Vec<usize> {
+        self.gaps()
+            .into_iter()
+            .filter(|gap| gap.len() == 1 && gap.start > 0 && gap.end < self.occupied.len())
+            .map(|gap| gap.start)
+            .collect()
+    }
+
+    /// Passes for seats this plane doesn't have, in the order they were inserted
+    pub fn out_of_range(&self) -> &[BoardingPass] {
+        &self.out_of_range
+    }
+
+    /// ASCII seat chart, one line per row with the aisle down the middle. Taken seats are `#`, empty ones `.` and
+    /// candidate seats `O`, followed by a line for every out of range pass.
+    pub fn chart(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for SeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let candidates = self.candidate_seats();
+        let columns = self.geometry.columns();
+        let label_width = (self.geometry.rows() - 1).to_string().len();
+
+        for row in 0..self.geometry.rows() {
+            write!(f, "{:>width$} ", row, width = label_width)?;
+            for column in 0..columns {
+                if column == columns / 2 && columns > 1 {
+                    write!(f, " ")?;
+                }
+
+                let seat_id = row * columns + column;
+                let seat = if self.occupied[seat_id] {
+                    '#'
+                } else if candidates.contains(&seat_id) {
+                    'O'
+                } else {
+                    '.'
+                };
+                write!(f, "{}", seat)?;
+            }
+            writeln!(f)?;
+        }
+
+        for pass in &self.out_of_range {
+            writeln!(
+                f,
+                "out of range: {} (row {}, column {})",
+                pass.encode(),
+                pass.row(),
+                pass.column()
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,68 +1,249 @@
-use fnv::FnvHashMap;
-// use std::collections::HashSet;
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod query;
+
+pub use query::{question_stats, unanswered, Answers, Query, QuestionStats, QUESTIONS};
 
 /// Holds customs information for a single group of people
 #[derive(Debug, Clone, Default)]
 pub struct CustomsGroup {
-    /// How many people are in each customs group
-    size: usize,
-    /// Questions answered yes to
-    yes_questions: FnvHashMap<char, usize>,
+    /// Questions each person in the group answered yes to
+    people: Vec<Answers>,
 }
 
 impl CustomsGroup {
-    pub fn from_str(input: &str) -> Self {
-        let mut size = 0;
-        let mut yes_questions = FnvHashMap::default();
-        input.lines().for_each(|line| {
-            size += 1;
-            line.chars().for_each(|c| {
-                *yes_questions.entry(c).or_insert(0) += 1;
-            });
-        });
-
-        CustomsGroup {
-            size,
-            yes_questions,
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let mut people = Vec::new();
+        for line in input.lines() {
+            let mut answers = Answers::default();
+            for (idx, c) in line.char_indices() {
+                // Questions are only ever labelled `a` through `z`
+                if !answers.insert(c) {
+                    return Err(ParseError::new(
+                        6,
+                        input,
+                        &line[idx..idx + c.len_utf8()],
+                        ParseErrorKind::UnexpectedChar(c),
+                    ));
+                }
+            }
+            people.push(answers);
+        }
+
+        Ok(CustomsGroup { people })
+    }
+
+    /// How many people are in the group
+    pub fn size(&self) -> usize {
+        self.people.len()
+    }
+
+    /// Each person's answers, in the order they were written down
+    pub fn people(&self) -> &[Answers] {
+        &self.people
+    }
+
+    /// Questions anyone in the group answered yes to
+    pub fn union(&self) -> Answers {
+        self.people
+            .iter()
+            .fold(Answers::NONE, |acc, answers| acc.union(*answers))
+    }
+
+    /// Questions everyone in the group answered yes to, nothing for a group without people
+    pub fn intersection(&self) -> Answers {
+        if self.people.is_empty() {
+            return Answers::NONE;
         }
+
+        self.people
+            .iter()
+            .fold(Answers::ALL, |acc, answers| acc.intersection(*answers))
+    }
+
+    /// How many people in the group answered yes to `question`
+    pub fn count(&self, question: char) -> usize {
+        self.people
+            .iter()
+            .filter(|answers| answers.contains(question))
+            .count()
+    }
+
+    /// Questions whose number of yes answers satisfies `matches`
+    pub fn answered_by<F: Fn(usize) -> bool>(&self, matches: F) -> Answers {
+        let mut answers = Answers::NONE;
+        for question in Answers::ALL.iter() {
+            if matches(self.count(question)) {
+                answers.insert(question);
+            }
+        }
+
+        answers
+    }
+
+    /// Questions answered yes to by at least `n` people
+    pub fn at_least(&self, n: usize) -> Answers {
+        self.answered_by(|count| count >= n)
+    }
+
+    /// Questions answered yes to by exactly `n` people
+    pub fn exactly(&self, n: usize) -> Answers {
+        self.answered_by(|count| count == n)
+    }
+
+    /// Questions nobody in the group answered yes to
+    pub fn unanswered(&self) -> Answers {
+        self.union().complement()
     }
 }
 
 #[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<CustomsGroup> {
-    let vec: Vec<CustomsGroup> = input
+pub fn input_generator(input: &str) -> Result<Vec<CustomsGroup>, ParseError> {
+    input
         // Split each `\n\n` which seperates each group
         .split("\n\n")
-        // Parse these into `CustomsGroup`s
-        .map(|data| CustomsGroup::from_str(data))
-        // Collect it back into a vector
-        .collect();
-
-    vec
+        // Parse these into `CustomsGroup`s, moving any error position to be relative to the whole input
+        .map(|data| CustomsGroup::from_str(data).map_err(|err| err.within(input, data)))
+        // Collect it back into a vector, stopping at the first error
+        .collect()
 }
 
 #[aoc(day6, part1)]
 pub fn solve_part1(input: &[CustomsGroup]) -> usize {
-    // Here we only care when *anyone* answered yes, so instead of caring about the contents of the map
-    // We just use the size of the map, alternatively we could have used a Hashset as well, but then we
-    // would need to construct 2 data structures for this problem
-    input.iter().map(|cg| cg.yes_questions.len()).sum()
+    // Here we only care when *anyone* answered yes, which is the union of everyone's answers
+    Query::Anyone.count(input)
+}
+
+#[aoc(day6, part1, at_least)]
+pub fn solve_part1_at_least(input: &[CustomsGroup]) -> usize {
+    Query::AtLeast(1).count(input)
 }
 
 #[aoc(day6, part2)]
 pub fn solve_part2(input: &[CustomsGroup]) -> usize {
+    // Questions everyone in the group answered yes to are the intersection of everyone's answers
+    Query::Everyone.count(input)
+}
+
+#[aoc(day6, part2, exactly)]
+pub fn solve_part2_exactly(input: &[CustomsGroup]) -> usize {
+    // Group sizes differ, so this one can't go through a single `Query`
     input
         .iter()
-        // Map our CustomsGroup into its `yes_questions` HashMap
-        .map(|cg| {
-            cg.yes_questions
-                // Go through our values only, we don't care about keys
-                .values()
-                // Find values that show everyone in the group answered yes
-                .filter(|val| **val == cg.size)
-                // Count the number of those values
-                .count()
-        })
-        // Then we sum the counts to find the total number of questions answered yes to (not uniquely) for all groups
+        .map(|group| group.exactly(group.size()).len())
         .sum()
 }
+
+/// Registers day 6 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = Vec<CustomsGroup>;
+
+    const DAY: u8 = 6;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::One, Some("at_least"), |input| {
+                solve_part1_at_least(input).to_string()
+            }),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+            Variant::new(Part::Two, Some("exactly"), |input| {
+                solve_part2_exactly(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "abc
+
+a
+b
+c
+
+ab
+ac
+
+a
+a
+a
+a
+
+b";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "11");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "6");
+    }
+
+    #[test]
+    fn answer_sets() {
+        let group = CustomsGroup::from_str("ab\nac").unwrap();
+        assert_eq!(group.size(), 2);
+        assert_eq!(group.union().to_string(), "abc");
+        assert_eq!(group.intersection().to_string(), "a");
+        assert_eq!(group.exactly(1).to_string(), "bc");
+        assert_eq!(group.at_least(2).to_string(), "a");
+        assert_eq!(group.unanswered().len(), QUESTIONS - 3);
+
+        // Writing a question down twice doesn't make it count twice
+        let group = CustomsGroup::from_str("aa\na").unwrap();
+        assert_eq!(group.count('a'), 2);
+        assert_eq!(group.exactly(2).to_string(), "a");
+    }
+
+    #[test]
+    fn queries() {
+        let groups = input_generator(EXAMPLE).unwrap();
+        assert_eq!(Query::Anyone.count(&groups), 11);
+        assert_eq!(Query::Everyone.count(&groups), 6);
+        assert_eq!(Query::AtLeast(2).count(&groups), 2);
+        assert_eq!(Query::Exactly(1).count(&groups), 9);
+        assert_eq!(Query::Nobody.apply(&groups[0]).len(), QUESTIONS - 3);
+
+        assert_eq!(unanswered(&groups).to_string(), "defghijklmnopqrstuvwxyz");
+    }
+
+    #[test]
+    fn stats() {
+        let groups = input_generator(EXAMPLE).unwrap();
+        let stats = question_stats(&groups);
+        assert_eq!(stats.len(), QUESTIONS);
+        assert_eq!(
+            stats[0],
+            QuestionStats {
+                question: 'a',
+                people: 8,
+                groups: 4,
+                unanimous: 3,
+            }
+        );
+        assert_eq!(stats[3].people, 0);
+
+        let total_people = groups.iter().map(CustomsGroup::size).sum();
+        assert_eq!(stats[0].frequency(total_people), 8.0 / 11.0);
+    }
+
+    #[test]
+    fn bad_question() {
+        let err = input_generator("abc\n\naBc").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('B'));
+        assert_eq!((err.line, err.column), (3, 2));
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use super::CustomsGroup;
+
+/// Number of questions on the customs declaration form, labelled `a` through `z`
+pub const QUESTIONS: usize = 26;
+
+/// Set of questions answered yes to, stored one bit per question with `a` as the lowest bit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Answers(u32);
+
+impl Answers {
+    /// No questions at all
+    pub const NONE: Answers = Answers(0);
+    /// Every question on the form
+    pub const ALL: Answers = Answers((1 << QUESTIONS) - 1);
+
+    /// Bit for `question`, `None` if it isn't on the form
+    fn bit(question: char) -> Option<u32> {
+        if question.is_ascii_lowercase() {
+            Some(1 << (question as u32 - 'a' as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Add `question` to the set, returning `false` if it isn't on the form
+    pub fn insert(&mut self, question: char) -> bool {
+        match Self::bit(question) {
+            Some(bit) => {
+                self.0 |= bit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, question: char) -> bool {
+        matches!(Self::bit(question), Some(bit) if self.0 & bit != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: Answers) -> Answers {
+        Answers(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Answers) -> Answers {
+        Answers(self.0 & other.0)
+    }
+
+    /// Questions on the form that aren't in this set
+    pub fn complement(self) -> Answers {
+        Answers(!self.0 & Self::ALL.0)
+    }
+
+    /// The questions in the set, in alphabetical order
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        (b'a'..=b'z')
+            .map(char::from)
+            .filter(move |c| self.contains(*c))
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for question in self.iter() {
+            write!(f, "{}", question)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A question that can be asked of every group, each one picks the questions that some number of people in the
+/// group answered yes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Query {
+    /// Answered by at least one person, the union of everyone's answers
+    Anyone,
+    /// Answered by every person, the intersection of everyone's answers
+    Everyone,
+    /// Answered by nobody in the group
+    Nobody,
+    /// Answered by `n` or more people
+    AtLeast(usize),
+    /// Answered by exactly `n` people
+    Exactly(usize),
+}
+
+impl Query {
+    /// The questions in `group` that match this query
+    pub fn apply(&self, group: &CustomsGroup) -> Answers {
+        match *self {
+            Self::Anyone => group.union(),
+            Self::Everyone => group.intersection(),
+            Self::Nobody => group.union().complement(),
+            Self::AtLeast(n) => group.answered_by(|count| count >= n),
+            Self::Exactly(n) => group.answered_by(|count| count == n),
+        }
+    }
+
+    /// Sum of the number of matching questions in each group
+    pub fn count(&self, groups: &[CustomsGroup]) -> usize {
+        groups.iter().map(|group| self.apply(group).len()).sum()
+    }
+}
+
+/// How one question was answered across every group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuestionStats {
+    pub question: char,
+    /// People who answered yes
+    pub people: usize,
+    /// Groups where at least one person answered yes
+    pub groups: usize,
+    /// Groups where every person answered yes
+    pub unanimous: usize,
+}
+
+impl QuestionStats {
+    /// Share of all `total_people` that answered yes
+    pub fn frequency(&self, total_people: usize) -> f64 {
+        if total_people == 0 {
+            0.0
+        } else {
+            self.people as f64 / total_people as f64
+        }
+    }
+}
+
+/// Statistics for every question on the form, in alphabetical order
+pub fn question_stats(groups: &[CustomsGroup]) -> Vec<QuestionStats> {
+    let mut stats: Vec<QuestionStats> = (b'a'..=b'z')
+        .map(|question| QuestionStats {
+            question: question.into(),
+            ..QuestionStats::default()
+        })
+        .collect();
+
+    for group in groups {
+        let everyone = group.intersection();
+        for stat in &mut stats {
+            let people = group.count(stat.question);
+            stat.people += people;
+            if people > 0 {
+                stat.groups += 1;
+            }
+            if everyone.contains(stat.question) {
+                stat.unanimous += 1;
+            }
+        }
+    }
+
+    stats
+}
+
+/// Questions that nobody in any group answered yes to
+pub fn unanswered(groups: &[CustomsGroup]) -> Answers {
+    groups
+        .iter()
+        .fold(Answers::NONE, |acc, group| acc.union(group.union()))
+        .complement()
+}
@@ -0,0 +1,372 @@
+use std::fmt::Write;
+
+use super::graph::{BagGraph, BagId, GraphError};
+use super::LuggageRules;
+use crate::cursor::Cursor;
+use crate::{ParseError, ParseErrorKind};
+
+/// Which part of the bag graph to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus<'a> {
+    /// Every bag and rule
+    All,
+    /// The chosen bag and everything that can end up inside it
+    From(&'a str),
+    /// The chosen bag and everything that can eventually hold it
+    To(&'a str),
+}
+
+/// Quote `text` as a string that both DOT and JSON understand
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl LuggageRules {
+    /// Graphviz DOT rendering of the rules, each edge points from a bag to a bag it holds and is labelled with how
+    /// many it holds. With a `Focus` on a color only the bags inside it, or holding it, are drawn and the color
+    /// itself is highlighted.
+    pub fn to_dot(&self, focus: Focus<'_>) -> Result<String, GraphError> {
+        let graph = self.graph();
+        let (highlight, included) = match focus {
+            Focus::All => (None, vec![true; graph.len()]),
+            Focus::From(color) => {
+                let bag = graph.lookup(color)?;
+                (Some(bag), mark(graph, bag, graph.descendants(bag)))
+            }
+            Focus::To(color) => {
+                let bag = graph.lookup(color)?;
+                (Some(bag), mark(graph, bag, graph.ancestors(bag)))
+            }
+        };
+
+        let mut dot = String::from("digraph luggage {\n");
+        for bag in graph.ids().filter(|bag| included[*bag]) {
+            if Some(bag) == highlight {
+                writeln!(
+                    dot,
+                    "    {} [style=filled, fillcolor=gold];",
+                    quote(graph.name(bag))
+                )
+                .unwrap();
+            } else {
+                writeln!(dot, "    {};", quote(graph.name(bag))).unwrap();
+            }
+        }
+        for bag in graph.ids().filter(|bag| included[*bag]) {
+            for (inner, count) in graph.contents(bag) {
+                if included[*inner] {
+                    writeln!(
+                        dot,
+                        "    {} -> {} [label={}, weight={}];",
+                        quote(graph.name(bag)),
+                        quote(graph.name(*inner)),
+                        quote(&count.to_string()),
+                        count
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        dot.push_str("}\n");
+
+        Ok(dot)
+    }
+
+    /// JSON rendering of the rules, one object per bag color in the order they were first seen:
+    ///
+    /// `{"bags": [{"color": "light red", "contents": [{"color": "bright white", "count": 1}]}]}`
+    pub fn to_json(&self) -> String {
+        let graph = self.graph();
+        let mut json = String::from("{\n  \"bags\": [");
+        for bag in graph.ids() {
+            if bag > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "\n    {{\"color\": {}, \"contents\": [",
+                quote(graph.name(bag))
+            )
+            .unwrap();
+            for (idx, (inner, count)) in graph.contents(bag).iter().enumerate() {
+                if idx > 0 {
+                    json.push_str(", ");
+                }
+                write!(
+                    json,
+                    "{{\"color\": {}, \"count\": {}}}",
+                    quote(graph.name(*inner)),
+                    count
+                )
+                .unwrap();
+            }
+            json.push_str("]}");
+        }
+        json.push_str("\n  ]\n}\n");
+
+        json
+    }
+
+    /// Read rules back from the JSON written by `to_json`. Keys may come in any order and unknown keys are ignored,
+    /// so other tools are free to add their own annotations.
+    pub fn from_json(input: &str) -> Result<Self, ParseError> {
+        let mut reader = JsonReader {
+            cursor: Cursor::new(7, input, input),
+        };
+        let root = reader.value()?;
+        reader.cursor.end()?;
+
+        let bags = root
+            .get(input, "bags")?
+            .as_array(input, "`bags` to be an array of bags")?;
+        // Intern every listed bag before any contents so the ids, and so the order of every export, survive the trip
+        let mut graph = BagGraph::new();
+        let mut outers = Vec::with_capacity(bags.len());
+        for bag in bags {
+            let color = bag
+                .get(input, "color")?
+                .as_str(input, "`color` to be a string")?;
+            outers.push(graph.intern(color));
+        }
+
+        for (bag, outer) in bags.iter().zip(outers) {
+            let contents = bag
+                .get(input, "contents")?
+                .as_array(input, "`contents` to be an array")?;
+            for content in contents {
+                let color = content
+                    .get(input, "color")?
+                    .as_str(input, "`color` to be a string")?;
+                let count = content.get(input, "count")?.as_count(input)?;
+                let inner = graph.intern(color);
+                graph.add_edge(outer, inner, count);
+            }
+        }
+
+        Ok(LuggageRules::from_graph(graph))
+    }
+}
+
+/// `bag` and `others` as a lookup table by `BagId`
+fn mark(graph: &BagGraph, bag: BagId, others: Vec<BagId>) -> Vec<bool> {
+    let mut included = vec![false; graph.len()];
+    included[bag] = true;
+    for other in others {
+        included[other] = true;
+    }
+    included
+}
+
+/// A parsed JSON value, along with the text it came from so errors can point at it
+#[derive(Debug)]
+struct Json<'a> {
+    text: &'a str,
+    value: Value<'a>,
+}
+
+#[derive(Debug)]
+enum Value<'a> {
+    /// `true`, `false` or `null`, none of which the rules use
+    Literal,
+    Number,
+    String(String),
+    Array(Vec<Json<'a>>),
+    Object(Vec<(String, Json<'a>)>),
+}
+
+impl<'a> Json<'a> {
+    fn error(&self, input: &str, expected: &'static str) -> ParseError {
+        ParseError::new(7, input, self.text, ParseErrorKind::Malformed(expected))
+    }
+
+    fn get(&self, input: &str, key: &'static str) -> Result<&Json<'a>, ParseError> {
+        match &self.value {
+            Value::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)
+                .ok_or_else(|| ParseError::new(7, input, self.text, ParseErrorKind::Missing(key))),
+            _ => Err(self.error(input, "an object")),
+        }
+    }
+
+    fn as_array(&self, input: &str, expected: &'static str) -> Result<&[Json<'a>], ParseError> {
+        match &self.value {
+            Value::Array(values) => Ok(values),
+            _ => Err(self.error(input, expected)),
+        }
+    }
+
+    fn as_str(&self, input: &str, expected: &'static str) -> Result<&str, ParseError> {
+        match &self.value {
+            Value::String(text) => Ok(text),
+            _ => Err(self.error(input, expected)),
+        }
+    }
+
+    fn as_count(&self, input: &str) -> Result<usize, ParseError> {
+        match &self.value {
+            Value::Number => self
+                .text
+                .parse()
+                .map_err(|_| ParseError::new(7, input, self.text, ParseErrorKind::InvalidNumber)),
+            _ => Err(self.error(input, "`count` to be a number")),
+        }
+    }
+}
+
+/// Just enough of a JSON reader for `from_json`
+struct JsonReader<'a> {
+    cursor: Cursor<'a>,
+}
+
+impl<'a> JsonReader<'a> {
+    fn value(&mut self) -> Result<Json<'a>, ParseError> {
+        let cursor = &mut self.cursor;
+        cursor.skip_whitespace();
+        let start = cursor.pos();
+        let value = match cursor.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => Value::String(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let rest = cursor.rest();
+                let len = rest
+                    .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+                    .unwrap_or(rest.len());
+                cursor.advance(len);
+                Value::Number
+            }
+            Some(_) => {
+                let rest = cursor.rest();
+                match ["true", "false", "null"]
+                    .iter()
+                    .find(|literal| rest.starts_with(*literal))
+                {
+                    Some(literal) => cursor.advance(literal.len()),
+                    None => return Err(cursor.error(ParseErrorKind::Malformed("a JSON value"))),
+                }
+                Value::Literal
+            }
+            None => return Err(cursor.error(ParseErrorKind::Missing("a JSON value"))),
+        };
+
+        Ok(Json {
+            text: self.cursor.since(start),
+            value,
+        })
+    }
+
+    fn object(&mut self) -> Result<Value<'a>, ParseError> {
+        self.cursor.eat('{');
+        let mut fields = Vec::new();
+        if self.cursor.eat('}') {
+            return Ok(Value::Object(fields));
+        }
+
+        loop {
+            self.cursor.skip_whitespace();
+            if self.cursor.peek() != Some('"') {
+                return Err(self.cursor.error(ParseErrorKind::Malformed("a quoted key")));
+            }
+            let key = self.string()?;
+            if !self.cursor.eat(':') {
+                return Err(self
+                    .cursor
+                    .error(ParseErrorKind::Malformed("`:` after the key")));
+            }
+            fields.push((key, self.value()?));
+
+            if self.cursor.eat('}') {
+                return Ok(Value::Object(fields));
+            }
+            if !self.cursor.eat(',') {
+                return Err(self.cursor.error(ParseErrorKind::Malformed("`,` or `}`")));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value<'a>, ParseError> {
+        self.cursor.eat('[');
+        let mut values = Vec::new();
+        if self.cursor.eat(']') {
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            if self.cursor.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            if !self.cursor.eat(',') {
+                return Err(self.cursor.error(ParseErrorKind::Malformed("`,` or `]`")));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let cursor = &mut self.cursor;
+        // Skip the opening quote
+        cursor.advance(1);
+        let mut text = String::new();
+        loop {
+            let c = match cursor.peek() {
+                Some(c) => c,
+                None => return Err(cursor.error(ParseErrorKind::Missing("closing `\"`"))),
+            };
+            cursor.advance(c.len_utf8());
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escaped = match cursor.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex = cursor.rest().get(1..5);
+                            match hex
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(std::char::from_u32)
+                            {
+                                Some(c) => {
+                                    cursor.advance(4);
+                                    c
+                                }
+                                None => {
+                                    return Err(cursor.error(ParseErrorKind::Malformed(
+                                        "4 hex digits naming a character",
+                                    )))
+                                }
+                            }
+                        }
+                        Some(c) => return Err(cursor.error(ParseErrorKind::UnexpectedChar(c))),
+                        None => return Err(cursor.error(ParseErrorKind::Missing("closing `\"`"))),
+                    };
+                    cursor.advance(1);
+                    text.push(escaped);
+                }
+                c => text.push(c),
+            }
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use fnv::FnvHashMap;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Index of a bag color within a `BagGraph`, colors are numbered in the order they were first seen
+pub type BagId = usize;
+
+/// Why a question about the bag graph couldn't be answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// The color doesn't appear anywhere in the rules
+    UnknownBag(String),
+    /// The rules make these bags contain each other, in order, so their size is infinite
+    Cycle(Vec<String>),
+    /// The number of bags doesn't fit in a `usize`
+    Overflow,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownBag(bag) => write!(f, "no rule mentions {:?} bags", bag),
+            Self::Cycle(bags) => write!(
+                f,
+                "bags contain each other: {} -> {}",
+                bags.join(" -> "),
+                bags[0]
+            ),
+            Self::Overflow => write!(f, "too many bags to count"),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// Directed graph of which bags go directly inside which, with every color interned to a `BagId`. Edges are kept in
+/// both directions so searches can walk outwards (what can hold this bag) as cheaply as inwards (what this bag holds).
+#[derive(Debug, Clone, Default)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: FnvHashMap<String, BagId>,
+    /// Bags directly inside each bag, and how many of them
+    contents: Vec<Vec<(BagId, usize)>>,
+    /// Bags that directly hold each bag, and how many of it they hold
+    containers: Vec<Vec<(BagId, usize)>>,
+}
+
+impl BagGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id for `name`, adding it to the graph as a bag with no contents if it hasn't been seen yet
+    pub fn intern(&mut self, name: &str) -> BagId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.contents.push(Vec::new());
+        self.containers.push(Vec::new());
+        id
+    }
+
+    /// Record that every `outer` bag directly holds `count` `inner` bags
+    pub fn add_edge(&mut self, outer: BagId, inner: BagId, count: usize) {
+        self.contents[outer].push((inner, count));
+        self.containers[inner].push((outer, count));
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn id(&self, name: &str) -> Option<BagId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Like `id`, but with an error naming the color when it isn't there
+    pub fn lookup(&self, name: &str) -> Result<BagId, GraphError> {
+        self.id(name)
+            .ok_or_else(|| GraphError::UnknownBag(name.to_string()))
+    }
+
+    pub fn name(&self, id: BagId) -> &str {
+        &self.names[id]
+    }
+
+    /// Every bag in the graph, in the order they were first seen
+    pub fn ids(&self) -> std::ops::Range<BagId> {
+        0..self.len()
+    }
+
+    /// Bags directly inside `id`, and how many of each
+    pub fn contents(&self, id: BagId) -> &[(BagId, usize)] {
+        &self.contents[id]
+    }
+
+    /// Bags that directly hold `id`, and how many of it each holds
+    pub fn containers(&self, id: BagId) -> &[(BagId, usize)] {
+        &self.containers[id]
+    }
+
+    /// Breadth first search from `start` along `edges`, returning every bag reached in the order they were found.
+    /// `start` itself is only included if it can reach itself.
+    fn reachable(&self, start: BagId, edges: &[Vec<(BagId, usize)>]) -> Vec<BagId> {
+        let mut seen = vec![false; self.len()];
+        let mut found = Vec::new();
+        let mut queue: VecDeque<BagId> = edges[start].iter().map(|(bag, _)| *bag).collect();
+
+        while let Some(bag) = queue.pop_front() {
+            if seen[bag] {
+                continue;
+            }
+            seen[bag] = true;
+            found.push(bag);
+            queue.extend(edges[bag].iter().map(|(next, _)| *next));
+        }
+
+        found
+    }
+
+    /// Every bag that can eventually hold `id`
+    pub fn ancestors(&self, id: BagId) -> Vec<BagId> {
+        self.reachable(id, &self.containers)
+    }
+
+    /// Every bag that can eventually end up inside `id`
+    pub fn descendants(&self, id: BagId) -> Vec<BagId> {
+        self.reachable(id, &self.contents)
+    }
+
+    /// A set of bags that contain each other, in containment order, if the rules have any
+    pub fn find_cycle(&self) -> Option<Vec<BagId>> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Mark {
+            Unvisited,
+            OnPath,
+            Done,
+        }
+
+        let mut marks = vec![Mark::Unvisited; self.len()];
+        for root in self.ids() {
+            if marks[root] != Mark::Unvisited {
+                continue;
+            }
+
+            // Depth first search keeping the current path and how far through each bag's contents we are, so that
+            // deeply nested rules can't overflow the stack
+            let mut path = vec![(root, 0)];
+            marks[root] = Mark::OnPath;
+            while let Some((bag, next)) = path.last_mut() {
+                let bag = *bag;
+                match self.contents[bag].get(*next) {
+                    Some((inner, _)) => {
+                        *next += 1;
+                        match marks[*inner] {
+                            Mark::Unvisited => {
+                                marks[*inner] = Mark::OnPath;
+                                path.push((*inner, 0));
+                            }
+                            Mark::OnPath => {
+                                let start = path.iter().position(|(b, _)| b == inner).unwrap();
+                                return Some(path[start..].iter().map(|(b, _)| *b).collect());
+                            }
+                            Mark::Done => {}
+                        }
+                    }
+                    None => {
+                        marks[bag] = Mark::Done;
+                        path.pop();
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Every bag ordered so that each bag comes before all of the bags it can hold, failing if the rules have a cycle
+    pub fn topological_order(&self) -> Result<Vec<BagId>, GraphError> {
+        // Kahn's algorithm, repeatedly take a bag that nothing left over can hold
+        let mut holders: Vec<usize> = self.containers.iter().map(Vec::len).collect();
+        let mut ready: VecDeque<BagId> = self.ids().filter(|bag| holders[*bag] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(bag) = ready.pop_front() {
+            order.push(bag);
+            for (inner, _) in &self.contents[bag] {
+                holders[*inner] -= 1;
+                if holders[*inner] == 0 {
+                    ready.push_back(*inner);
+                }
+            }
+        }
+
+        if order.len() == self.len() {
+            Ok(order)
+        } else {
+            Err(self.cycle_error())
+        }
+    }
+
+    /// Number of bags **INCLUDING THE TOP BAG** that one `id` bag adds up to. Use a `BagQuery` to keep the sizes
+    /// counted along the way for later calls.
+    pub fn size(&self, id: BagId) -> Result<usize, GraphError> {
+        self.size_with(id, &mut vec![None; self.len()])
+    }
+
+    /// Like `size`, picking up bag sizes already in `sizes` (indexed by `BagId`) and filling in every size it counts
+    pub(crate) fn size_with(
+        &self,
+        id: BagId,
+        sizes: &mut [Option<usize>],
+    ) -> Result<usize, GraphError> {
+        if let Some(size) = sizes[id] {
+            return Ok(size);
+        }
+
+        // Depth first search keeping the current path, how far through each bag's contents we are and the size so
+        // far, so that deeply nested rules can't overflow the stack. Every bag counts itself (size of 1), and a bag's
+        // size is filled in as soon as all of its contents are counted.
+        let mut on_path = vec![false; self.len()];
+        let mut path = vec![(id, 0, 1)];
+        on_path[id] = true;
+        while let Some((bag, next, size)) = path.last_mut() {
+            let bag = *bag;
+            match self.contents(bag).get(*next) {
+                Some((inner, count)) => match sizes[*inner] {
+                    Some(inner_size) => {
+                        *next += 1;
+                        *size = count
+                            .checked_mul(inner_size)
+                            .and_then(|bags| bags.checked_add(*size))
+                            .ok_or(GraphError::Overflow)?;
+                    }
+                    None if on_path[*inner] => return Err(self.cycle_error()),
+                    // Count the inner bag first, then come back to this one and pick up its size
+                    None => {
+                        on_path[*inner] = true;
+                        path.push((*inner, 0, 1));
+                    }
+                },
+                None => {
+                    sizes[bag] = Some(*size);
+                    on_path[bag] = false;
+                    path.pop();
+                }
+            }
+        }
+
+        Ok(sizes[id].expect("The bag was counted when it left the path"))
+    }
+
+    fn cycle_error(&self) -> GraphError {
+        let cycle = self
+            .find_cycle()
+            .expect("Only called once a cycle is known to exist");
+        GraphError::Cycle(
+            cycle
+                .iter()
+                .map(|bag| self.name(*bag).to_string())
+                .collect(),
+        )
+    }
+}
@@ -1,188 +1,108 @@
-use fnv::FnvHashMap;
+use rayon::prelude::*;
 use regex::Regex;
-// use std::collections::HashMap;
-// use std::fmt;
-use std::sync::Mutex;
+use std::fmt;
+
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod export;
+pub mod graph;
+pub mod query;
+
+pub use export::Focus;
+pub use graph::{BagGraph, BagId, GraphError};
+pub use query::BagQuery;
 
 lazy_static! {
     static ref LUGGAGE_RULES: Regex = Regex::new(r"(\d+) ([a-z ]+) (bags|bag)").unwrap();
-    static ref CONTENTS_CACHE: Mutex<FnvHashMap<String, bool>> = Mutex::new(FnvHashMap::default());
-    static ref SIZE_CACHE: Mutex<FnvHashMap<String, usize>> = Mutex::new(FnvHashMap::default());
 }
 
 /// Contains all of the Luggage Rules
 #[derive(Debug, Clone, Default)]
 pub struct LuggageRules {
-    /// These rules are the bag color and a map of content bag color, and count
-    rules: FnvHashMap<String, FnvHashMap<String, usize>>,
+    /// Which bag colors go directly inside which, and how many of them
+    graph: BagGraph,
 }
 
 impl LuggageRules {
-    pub fn from_str(input: &str) -> Self {
-        // Build our default hashmap
-        let mut rules = FnvHashMap::default();
+    pub fn from_str(input: &str) -> Result<Self, ParseError> {
+        let mut graph = BagGraph::new();
         for line in input.lines() {
             // Split the parent bag color from the children bag color
             let split_input: Vec<&str> = line.split(" bags contain ").collect();
-            // Make this a `String` because cargo-aoc doesn't let us use lifetimes
-            let bag = split_input[0].to_string();
+            if split_input.len() != 2 {
+                return Err(ParseError::new(
+                    7,
+                    input,
+                    line,
+                    ParseErrorKind::Malformed("`<color> bags contain <contents>.`"),
+                ));
+            }
+            let bag = graph.intern(split_input[0]);
 
-            let mut children = FnvHashMap::default();
             for cap in LUGGAGE_RULES.captures_iter(split_input[1]) {
-                // Build our second hashmap which is the bag color and the number of that color
-                // One hashmap is made for all children, assuming that a single color can occur once
-                // in the contents list
-                children.insert(
-                    cap[2].to_string(),
-                    cap[1]
-                        .parse::<usize>()
-                        .expect("Error parsing usize from input"),
-                );
+                let count = cap.get(1).unwrap().as_str();
+                let count = count
+                    .parse::<usize>()
+                    .map_err(|_| ParseError::new(7, input, count, ParseErrorKind::InvalidNumber))?;
+                // Bags that are only ever mentioned as contents still get a node, they just hold nothing
+                let inner = graph.intern(&cap[2]);
+                graph.add_edge(bag, inner, count);
             }
-
-            // Put our rule into our overarching ruleset
-            rules.insert(bag, children);
         }
 
-        LuggageRules { rules }
+        Ok(LuggageRules { graph })
     }
 
-    /// Search the specified `haystack` (one specific bag rule) for the specified `needle` (one specific bag color)
-    /// returning `true` if `haystack` contains `needle`. This uses a recursive search of all of `needle`'s contents
-    /// as well. This *should* benefit from cacheing if cargo-aoc would give mutable references.
-    fn contains(&self, needle: &str, haystack: &str) -> bool {
-        // Get the bag rule associated with haystack, this should never fail unless you search
-        // for a bag color that doesn't exist in the ruleset
-        if let Some(bag_contents) = self.rules.get(haystack) {
-            // If our bag directly contains our needle, return true
-            if let Some(_) = bag_contents.get(needle) {
-                true
-            } else {
-                // If our bag doesn't directly contain our needle, we need to recursively search
-                // our haystack's contents until we find it, or run out of contents to search.
-                let mut contents_found = false;
-                for key in bag_contents.keys() {
-                    // If we find *any* bag that contains our needle, then we know everything above it contains
-                    // the needle, so we can exit early to speed up our search. We should be able to cache this
-                    // query to speed it up.
-                    contents_found |= self.contains(needle, key);
-                    if contents_found {
-                        // Early return if we find *any* haystack that contains our needle
-                        return contents_found;
-                    }
-                }
-                // This is our long branch, if we don't find any needle in haystacks
-                contents_found
-            }
-        } else {
-            unreachable!("Hashmap should contain all possible searches")
-        }
+    /// Rules made from an already built graph
+    pub fn from_graph(graph: BagGraph) -> Self {
+        LuggageRules { graph }
     }
 
-    /// Search the specified `haystack` (one specific bag rule) for the specified `needle` (one specific bag color)
-    /// returning `true` if `haystack` contains `needle`. This uses a recursive search of all of `needle`'s contents
-    /// as well. This method uses caching of a global FnvHashMap behind a Mutex.
-    fn contains_cached(&self, needle: &str, haystack: &str) -> bool {
-        // Check our cache before we bother walking to find the answer ourselves
-        if let Some(val) = CONTENTS_CACHE.lock().unwrap().get(haystack) {
-            return *val;
-        }
+    pub fn graph(&self) -> &BagGraph {
+        &self.graph
+    }
 
-        // Get the bag rule associated with haystack, this should never fail unless you search
-        // for a bag color that doesn't exist in the ruleset
-        if let Some(bag_contents) = self.rules.get(haystack) {
-            // If our bag directly contains our needle, return true
-            if let Some(_) = bag_contents.get(needle) {
-                true
-            } else {
-                // If our bag doesn't directly contain our needle, we need to recursively search
-                // our haystack's contents until we find it, or run out of contents to search.
-                let mut contents_found = false;
-                for key in bag_contents.keys() {
-                    // If we find *any* bag that contains our needle, then we know everything above it contains
-                    // the needle, so we can exit early to speed up our search. We should be able to cache this
-                    // query to speed it up.
-                    contents_found |= self.contains_cached(needle, key);
-                    if contents_found {
-                        // Early return if we find *any* haystack that contains our needle
-                        // Throw the solution into our cache before we exit
-                        CONTENTS_CACHE
-                            .lock()
-                            .unwrap()
-                            .insert(key.into(), contents_found);
-                        return contents_found;
-                    }
-                }
-                // This is our long branch, if we don't find any needle in haystacks
-                // Throw the solution into our cache before we exit
-                CONTENTS_CACHE
-                    .lock()
-                    .unwrap()
-                    .insert(haystack.into(), contents_found);
-                contents_found
-            }
-        } else {
-            unreachable!("Hashmap should contain all possible searches")
-        }
+    /// Every bag color that can eventually hold a `needle` bag, found with one search outwards from `needle`
+    pub fn containers_of(&self, needle: &str) -> Result<Vec<&str>, GraphError> {
+        let needle = self.graph.lookup(needle)?;
+        Ok(self
+            .graph
+            .ancestors(needle)
+            .into_iter()
+            .map(|bag| self.graph.name(bag))
+            .collect())
     }
 
-    /// Calculates the number of bags **INCLUDING THE TOP BAG** that the provided needle contains (including itself)
-    /// It does this by walking the list of contents of each bag, and recursively calculating the size of each bag.
-    fn size(&self, needle: &str) -> usize {
-        // Get our bag, this should only ever fial if you query for a bag that doesn't exist in the ruleset
-        if let Some(bag_rule) = self.rules.get(needle) {
-            // All bags count themselves (size of 1)
-            let mut size = 1;
-            // Iterate through all of the contents of the bag and add up all of their sizes recusively
-            for (bag, num) in bag_rule {
-                // Multiply the number of bags we have of each type, by their size, and that gives us the size of our
-                // current bag
-                size += num * self.size(bag);
-            }
-            // We will see a size of 1 for bags that have no contents (end nodes)
-            size
-        } else {
-            unreachable!("Hashmap should contain all possible searches")
+    /// Search the specified `haystack` (one specific bag rule) for the specified `needle` (one specific bag color)
+    /// returning `true` if `haystack` contains `needle`. This walks everything inside `haystack`, so finding every
+    /// bag that holds `needle` this way repeats a lot of work compared to `containers_of`. Unknown colors are never
+    /// contained and contain nothing.
+    fn contains(&self, needle: &str, haystack: &str) -> bool {
+        match (self.graph.id(needle), self.graph.id(haystack)) {
+            (Some(needle), Some(haystack)) => self.graph.descendants(haystack).contains(&needle),
+            _ => false,
         }
     }
 
-    /// Calculates the number of bags **INCLUDING THE TOP BAG** that the provided needle contains (including itself)
-    /// It does this by walking the list of contents of each bag, and recursively calculating the size of each bag.
-    /// This is cached in a global FnvHashMap behind a Mutex. Between the Mutex interactions and additional cache lookups
-    /// this caching doesn't seem effective for smaller bag sizes, if you had more recursive bags, caching would quickly
-    /// pull ahead in performance
-    fn size_cached(&self, needle: &str) -> usize {
-        // Check our cache before we bother walking to find the answer ourselves
-        if let Some(size) = SIZE_CACHE.lock().unwrap().get(needle) {
-            return *size;
-        }
+    /// Calculates the number of bags **INCLUDING THE TOP BAG** that the provided needle contains (including itself).
+    /// Fails for unknown colors, for bags that (eventually) contain themselves, and when the count doesn't fit.
+    pub fn size(&self, needle: &str) -> Result<usize, GraphError> {
+        self.graph.size(self.graph.lookup(needle)?)
+    }
 
-        // Get our bag, this should only ever fial if you query for a bag that doesn't exist in the ruleset
-        if let Some(bag_rule) = self.rules.get(needle) {
-            // All bags count themselves (size of 1)
-            let mut size = 1;
-            // Iterate through all of the contents of the bag and add up all of their sizes recusively
-            for (bag, num) in bag_rule {
-                // Multiply the number of bags we have of each type, by their size, and that gives us the size of our
-                // current bag.
-                size += num * self.size(bag);
-            }
-            // We will see a size of 1 for bags that have no contents (end nodes)
-            // Whatever we find, we insert it into our cache for later cache hits
-            SIZE_CACHE.lock().unwrap().insert(needle.into(), size);
-            size
-        } else {
-            unreachable!("Hashmap should contain all possible searches")
-        }
+    /// Memoizing query over these rules, which is worth it when asking about many bags
+    pub fn query(&self) -> BagQuery<'_> {
+        BagQuery::new(&self.graph)
     }
 }
 
-impl std::fmt::Display for LuggageRules {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (bag, contents) in &self.rules {
-            write!(f, "{} contains :\n", bag)?;
-            for (bag, num) in contents {
-                write!(f, "\t{} {}\n", num, bag)?;
+impl fmt::Display for LuggageRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bag in self.graph.ids() {
+            writeln!(f, "{} contains :", self.graph.name(bag))?;
+            for (inner, num) in self.graph.contents(bag) {
+                writeln!(f, "\t{} {}", num, self.graph.name(*inner))?;
             }
         }
 
@@ -191,7 +111,7 @@ impl std::fmt::Display for LuggageRules {
 }
 
 #[aoc_generator(day7)]
-pub fn input_generator(input: &str) -> LuggageRules {
+pub fn input_generator(input: &str) -> Result<LuggageRules, ParseError> {
     LuggageRules::from_str(input)
 }
 
@@ -199,19 +119,42 @@ pub fn input_generator(input: &str) -> LuggageRules {
 pub fn solve_part1(input: &LuggageRules) -> usize {
     let search_bag = "shiny gold";
     input
-        .rules
-        .iter()
-        .filter(|(key, _)| input.contains(search_bag, key))
+        .containers_of(search_bag)
+        .expect("Error searching for bags that hold our bag")
+        .len()
+}
+
+#[aoc(day7, part1, per_bag)]
+pub fn solve_part1_per_bag(input: &LuggageRules) -> usize {
+    let search_bag = "shiny gold";
+    input
+        .graph
+        .ids()
+        .filter(|bag| input.contains(search_bag, input.graph.name(*bag)))
         .count()
 }
 
 #[aoc(day7, part1, contents_cached)]
 pub fn solve_part1_cached(input: &LuggageRules) -> usize {
     let search_bag = "shiny gold";
+    let query = input.query();
+    input
+        .graph
+        .ids()
+        .filter(|bag| query.contains(search_bag, input.graph.name(*bag)))
+        .count()
+}
+
+#[aoc(day7, part1, contents_cached_par)]
+pub fn solve_part1_cached_par(input: &LuggageRules) -> usize {
+    let search_bag = "shiny gold";
+    // One query shared between every rayon task, the first task to ask searches and the rest reuse its answer
+    let query = input.query();
     input
-        .rules
-        .iter()
-        .filter(|(key, _)| input.contains_cached(search_bag, key))
+        .graph
+        .ids()
+        .into_par_iter()
+        .filter(|bag| query.contains(search_bag, input.graph.name(*bag)))
         .count()
 }
 
@@ -219,12 +162,314 @@ pub fn solve_part1_cached(input: &LuggageRules) -> usize {
 pub fn solve_part2(input: &LuggageRules) -> usize {
     let search_bag = "shiny gold";
     // We subtract 1 here because we don't want to count our outer-most bag
-    input.size(search_bag) - 1
+    input.size(search_bag).expect("Error counting bags") - 1
 }
 
 #[aoc(day7, part2, size_cached)]
 pub fn solve_part2_cached(input: &LuggageRules) -> usize {
     let search_bag = "shiny gold";
     // We subtract 1 here because we don't want to count our outer-most bag
-    input.size_cached(search_bag) - 1
+    input.query().size(search_bag).expect("Error counting bags") - 1
+}
+
+/// Registers day 7 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = LuggageRules;
+
+    const DAY: u8 = 7;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::One, Some("per_bag"), |input| {
+                solve_part1_per_bag(input).to_string()
+            }),
+            Variant::new(Part::One, Some("contents_cached"), |input| {
+                solve_part1_cached(input).to_string()
+            }),
+            Variant::new(Part::One, Some("contents_cached_par"), |input| {
+                solve_part1_cached_par(input).to_string()
+            }),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+            Variant::new(Part::Two, Some("size_cached"), |input| {
+                solve_part2_cached(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.";
+
+    const DEEP_EXAMPLE: &str = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 2 dark orange bags.
+dark orange bags contain 2 dark yellow bags.
+dark yellow bags contain 2 dark green bags.
+dark green bags contain 2 dark blue bags.
+dark blue bags contain 2 dark violet bags.
+dark violet bags contain no other bags.";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "4");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "32");
+    }
+
+    #[test]
+    fn part2_deep_example() {
+        assert_variants::<Solution>(DEEP_EXAMPLE, Part::Two, "126");
+    }
+
+    #[test]
+    fn query_memo() {
+        let rules = input_generator(EXAMPLE).unwrap();
+        let query = rules.query();
+
+        // Answers for one needle mustn't leak into another
+        assert!(query.contains("shiny gold", "light red"));
+        assert!(!query.contains("light red", "shiny gold"));
+        assert!(query.contains("faded blue", "shiny gold"));
+        assert!(!query.contains("shiny gold", "shiny gold"));
+        assert!(!query.contains("plaid magenta", "light red"));
+
+        assert_eq!(query.size("shiny gold"), Ok(33));
+        assert_eq!(query.size("dark olive"), Ok(8));
+        assert_eq!(query.size("light red"), Ok(187));
+
+        // A second rule set gets its own cache
+        let deep = input_generator(DEEP_EXAMPLE).unwrap();
+        assert_eq!(deep.query().size("shiny gold"), Ok(127));
+        assert!(!deep.query().contains("shiny gold", "light red"));
+    }
+
+    #[test]
+    fn query_across_threads() {
+        let rules = input_generator(EXAMPLE).unwrap();
+        let query = rules.query();
+        let graph = rules.graph();
+
+        let holders: Vec<(&str, usize)> = graph
+            .ids()
+            .into_par_iter()
+            .map(|needle| {
+                let needle = graph.name(needle);
+                let count = graph
+                    .ids()
+                    .filter(|bag| query.contains(needle, graph.name(*bag)))
+                    .count();
+                (needle, count)
+            })
+            .collect();
+        for (needle, count) in holders {
+            assert_eq!(count, rules.containers_of(needle).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn containers() {
+        let rules = input_generator(EXAMPLE).unwrap();
+        let mut containers = rules.containers_of("shiny gold").unwrap();
+        containers.sort_unstable();
+        assert_eq!(
+            containers,
+            vec!["bright white", "dark orange", "light red", "muted yellow"]
+        );
+        assert_eq!(
+            rules.containers_of("light red").unwrap(),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            rules.containers_of("plaid magenta"),
+            Err(GraphError::UnknownBag("plaid magenta".to_string()))
+        );
+    }
+
+    #[test]
+    fn topological_order() {
+        let rules = input_generator(EXAMPLE).unwrap();
+        let graph = rules.graph();
+        let order = graph.topological_order().unwrap();
+        assert_eq!(order.len(), graph.len());
+
+        let position = |bag| order.iter().position(|b| *b == bag).unwrap();
+        for bag in graph.ids() {
+            for (inner, _) in graph.contents(bag) {
+                assert!(position(bag) < position(*inner));
+            }
+        }
+        assert_eq!(graph.find_cycle(), None);
+    }
+
+    #[test]
+    fn cycles() {
+        let rules = input_generator(
+            "light red bags contain 1 dark orange bag, 2 shiny gold bags.
+dark orange bags contain 3 light red bags.
+shiny gold bags contain 1 faded blue bag.
+faded blue bags contain no other bags.",
+        )
+        .unwrap();
+
+        let cycle = GraphError::Cycle(vec!["light red".to_string(), "dark orange".to_string()]);
+        assert_eq!(rules.size("light red"), Err(cycle.clone()));
+        assert_eq!(rules.graph().topological_order(), Err(cycle.clone()));
+        assert_eq!(
+            cycle.to_string(),
+            "bags contain each other: light red -> dark orange -> light red"
+        );
+
+        // Bags outside the cycle can still be counted and searched
+        assert_eq!(rules.size("shiny gold"), Ok(2));
+        assert_eq!(solve_part1(&rules), 2);
+    }
+
+    #[test]
+    fn size_overflow() {
+        let rules = input_generator(
+            "shiny gold bags contain 1000000 dark red bags.
+dark red bags contain 1000000 dark orange bags.
+dark orange bags contain 1000000 dark yellow bags.
+dark yellow bags contain 1000000 dark green bags.
+dark green bags contain no other bags.",
+        )
+        .unwrap();
+        assert_eq!(rules.size("dark orange"), Ok(1_000_001_000_001));
+        assert_eq!(rules.size("shiny gold"), Err(GraphError::Overflow));
+    }
+
+    #[test]
+    fn deeply_nested_size() {
+        // Each bag holds one of the next, far deeper than a recursive count could go
+        let depth = 100_000;
+        // Colors are only letters, so spell the number out in base 26
+        let name = |mut bag: usize| {
+            let mut name = String::from("deep ");
+            loop {
+                name.push((b'a' + (bag % 26) as u8) as char);
+                bag /= 26;
+                if bag == 0 {
+                    return name;
+                }
+            }
+        };
+        let mut input: Vec<String> = (0..depth)
+            .map(|bag| format!("{} bags contain 1 {} bag.", name(bag), name(bag + 1)))
+            .collect();
+        input.push(format!("{} bags contain no other bags.", name(depth)));
+        let rules = input_generator(&input.join("\n")).unwrap();
+
+        let query = rules.query();
+        assert_eq!(query.size(&name(0)), Ok(depth + 1));
+        assert_eq!(query.size(&name(1)), Ok(depth));
+    }
+
+    #[test]
+    fn dot_export() {
+        let rules = input_generator(
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain no other bags.
+shiny gold bags contain 3 faded blue bags.
+faded blue bags contain no other bags.",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules.to_dot(Focus::From("bright white")).unwrap(),
+            r#"digraph luggage {
+    "bright white" [style=filled, fillcolor=gold];
+    "shiny gold";
+    "faded blue";
+    "bright white" -> "shiny gold" [label="1", weight=1];
+    "shiny gold" -> "faded blue" [label="3", weight=3];
+}
+"#
+        );
+        assert_eq!(
+            rules.to_dot(Focus::To("shiny gold")).unwrap(),
+            r#"digraph luggage {
+    "light red";
+    "bright white";
+    "shiny gold" [style=filled, fillcolor=gold];
+    "light red" -> "bright white" [label="1", weight=1];
+    "bright white" -> "shiny gold" [label="1", weight=1];
+}
+"#
+        );
+
+        let everything = rules.to_dot(Focus::All).unwrap();
+        assert_eq!(everything.matches(" -> ").count(), 4);
+        assert!(!everything.contains("fillcolor"));
+        assert_eq!(
+            rules.to_dot(Focus::To("plaid magenta")),
+            Err(GraphError::UnknownBag("plaid magenta".to_string()))
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let rules = input_generator(EXAMPLE).unwrap();
+        let json = rules.to_json();
+        assert!(json.contains(
+            r#"{"color": "light red", "contents": [{"color": "bright white", "count": 1}, {"color": "muted yellow", "count": 2}]}"#
+        ));
+
+        let imported = LuggageRules::from_json(&json).unwrap();
+        assert_eq!(imported.to_string(), rules.to_string());
+        assert_eq!(imported.to_json(), json);
+        assert_eq!(solve_part1(&imported), 4);
+        assert_eq!(solve_part2(&imported), 32);
+
+        // Key order, escapes and extra keys are all fine
+        let imported = LuggageRules::from_json(
+            r#"{"version": 1, "bags": [
+                {"contents": [{"count": 2, "color": "dark \"red\""}], "color": "shiny gold", "note": null},
+                {"color": "dark \"red\"", "contents": []}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(imported.size("shiny gold"), Ok(3));
+        assert!(imported.to_json().contains(r#""dark \"red\"""#));
+    }
+
+    #[test]
+    fn json_errors() {
+        let err = LuggageRules::from_json("{\"bags\": [{\"color\": \"shiny gold\"}]}").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::Missing("contents"));
+        assert_eq!(err.column, 11);
+
+        let err = LuggageRules::from_json(
+            "{\"bags\": [\n  {\"color\": \"shiny gold\", \"contents\": [{\"color\": \"red\", \"count\": -1}]}\n]}",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidNumber);
+        assert_eq!((err.line, err.text.as_str()), (2, "-1"));
+
+        let err = LuggageRules::from_json("{\"bags\": [}").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::Malformed("a JSON value"));
+        let err = LuggageRules::from_json("{\"bags\": []} x").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('x'));
+    }
 }
@@ -0,0 +1,76 @@
+use fnv::FnvHashMap;
+use std::sync::{Arc, Mutex};
+
+use super::graph::{BagGraph, BagId, GraphError};
+
+/// Memoizing queries over one set of luggage rules. Every answer is cached inside the query itself, so a new set of
+/// rules always starts from an empty cache, and the caches sit behind `Mutex`es so one query can be shared between
+/// rayon threads.
+#[derive(Debug)]
+pub struct BagQuery<'a> {
+    graph: &'a BagGraph,
+    /// For each needle searched so far, which bags can eventually hold it
+    holders: Mutex<FnvHashMap<BagId, Arc<Vec<bool>>>>,
+    /// Size of each bag counted so far
+    sizes: Mutex<Vec<Option<usize>>>,
+}
+
+impl<'a> BagQuery<'a> {
+    pub fn new(graph: &'a BagGraph) -> Self {
+        BagQuery {
+            graph,
+            holders: Mutex::new(FnvHashMap::default()),
+            sizes: Mutex::new(vec![None; graph.len()]),
+        }
+    }
+
+    pub fn graph(&self) -> &'a BagGraph {
+        self.graph
+    }
+
+    /// Which bags can eventually hold `needle`, indexed by `BagId`. The first query for a needle searches outwards from
+    /// it once, every later query for the same needle is a lookup.
+    fn holders(&self, needle: BagId) -> Arc<Vec<bool>> {
+        if let Some(holders) = self.holders.lock().unwrap().get(&needle) {
+            return Arc::clone(holders);
+        }
+
+        // Search without holding the lock, another thread may do the same search but they'll agree on the answer
+        let mut holders = vec![false; self.graph.len()];
+        for bag in self.graph.ancestors(needle) {
+            holders[bag] = true;
+        }
+
+        Arc::clone(
+            self.holders
+                .lock()
+                .unwrap()
+                .entry(needle)
+                .or_insert_with(|| Arc::new(holders)),
+        )
+    }
+
+    /// Returns `true` if a `haystack` bag eventually contains a `needle` bag. Unknown colors are never contained and
+    /// contain nothing.
+    pub fn contains(&self, needle: &str, haystack: &str) -> bool {
+        match (self.graph.id(needle), self.graph.id(haystack)) {
+            (Some(needle), Some(haystack)) => self.contains_id(needle, haystack),
+            _ => false,
+        }
+    }
+
+    pub fn contains_id(&self, needle: BagId, haystack: BagId) -> bool {
+        self.holders(needle)[haystack]
+    }
+
+    /// Calculates the number of bags **INCLUDING THE TOP BAG** that the provided needle contains (including itself).
+    /// Every bag counted along the way is cached for later queries.
+    pub fn size(&self, needle: &str) -> Result<usize, GraphError> {
+        let id = self.graph.lookup(needle)?;
+        self.size_id(id)
+    }
+
+    pub fn size_id(&self, id: BagId) -> Result<usize, GraphError> {
+        self.graph.size_with(id, &mut self.sizes.lock().unwrap())
+    }
+}
@@ -0,0 +1,307 @@
+use fnv::FnvHashMap;
+use std::fmt;
+
+use super::{Instruction, Opcode, Register};
+use crate::{ParseError, ParseErrorKind};
+
+/// Assemble a boot program. On top of the puzzle's one-instruction-per-line format this understands:
+///
+/// - comments, from `#` or `;` to the end of the line
+/// - blank lines, which are skipped
+/// - labels, `name:` on a line of their own or in front of an instruction, naming the address of the next instruction
+/// - label names in place of a jump offset, `jmp loop` or `jnz x done`, which are turned into the right offset
+pub fn assemble(source: &str) -> Result<FnvHashMap<usize, Instruction>, ParseError> {
+    // First pass: strip comments and labels, and work out which address each label stands for
+    let mut labels: FnvHashMap<&str, usize> = FnvHashMap::default();
+    let mut lines = Vec::new();
+    for line in source.lines() {
+        let mut code = line[..line.find(['#', ';']).unwrap_or(line.len())].trim();
+        while let Some(colon) = code.find(':') {
+            let label = code[..colon].trim();
+            if !is_label(label) {
+                return Err(ParseError::new(
+                    8,
+                    source,
+                    if label.is_empty() {
+                        &code[colon..]
+                    } else {
+                        label
+                    },
+                    ParseErrorKind::Malformed("a label made of letters, digits and `_`"),
+                ));
+            }
+            if labels.insert(label, lines.len()).is_some() {
+                return Err(ParseError::new(
+                    8,
+                    source,
+                    label,
+                    ParseErrorKind::Malformed("each label to be defined once"),
+                ));
+            }
+            code = code[colon + 1..].trim();
+        }
+
+        if !code.is_empty() {
+            lines.push(code);
+        }
+    }
+
+    // Second pass: parse each instruction, resolving labels relative to its own address
+    let mut mem = FnvHashMap::default();
+    for (addr, code) in lines.into_iter().enumerate() {
+        let instr = Instruction::parse_with(code, |opcode, label| {
+            if !opcode.is_jump() || !is_label(label) {
+                return None;
+            }
+            Some(
+                labels
+                    .get(label)
+                    .map(|target| *target as isize - addr as isize)
+                    .ok_or_else(|| ParseError::new(8, code, label, ParseErrorKind::UnknownKeyword)),
+            )
+        })
+        .map_err(|err| err.within(source, code))?;
+        mem.insert(addr, instr);
+    }
+
+    Ok(mem)
+}
+
+fn is_label(label: &str) -> bool {
+    label.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Where a jump at `addr` with `offset` goes in a program of `len` instructions
+fn jump_target(addr: usize, offset: isize, len: usize) -> Option<usize> {
+    let target = (addr as isize).checked_add(offset)?;
+    if target >= 0 && target as usize <= len {
+        Some(target as usize)
+    } else {
+        None
+    }
+}
+
+/// Human readable listing of a program, one instruction per line with its address, and where each jump goes
+pub fn disassemble(program: &FnvHashMap<usize, Instruction>) -> String {
+    let len = program.len();
+    let width = len.to_string().len();
+    let mut listing = String::new();
+
+    for addr in 0..len {
+        let instr = program[&addr];
+        let line = format!("{:>width$}: {}", addr, instr, width = width);
+        if instr.opcode().is_jump() {
+            let target = match jump_target(addr, instr.value(), len) {
+                Some(target) if target == len => String::from("end"),
+                Some(target) => target.to_string(),
+                None => String::from("out of bounds"),
+            };
+            listing.push_str(&format!("{:<24}; -> {}\n", line, target));
+        } else {
+            listing.push_str(&line);
+            listing.push('\n');
+        }
+    }
+
+    listing
+}
+
+/// Source for a program that `assemble` turns back into the same program, with every jump target given a label
+pub fn to_assembly(program: &FnvHashMap<usize, Instruction>) -> String {
+    let len = program.len();
+    let mut targets = vec![false; len + 1];
+    for addr in 0..len {
+        let instr = program[&addr];
+        if let (true, Some(target)) = (
+            instr.opcode().is_jump(),
+            jump_target(addr, instr.value(), len),
+        ) {
+            targets[target] = true;
+        }
+    }
+
+    let mut source = String::new();
+    for (addr, target) in targets.into_iter().enumerate() {
+        if target {
+            source.push_str(&format!("l{}:\n", addr));
+        }
+        let instr = match program.get(&addr) {
+            Some(instr) => *instr,
+            None => break,
+        };
+
+        match jump_target(addr, instr.value(), len) {
+            Some(target) if instr.opcode().is_jump() => {
+                source.push_str(&format!("    {}", instr.opcode()));
+                if instr.opcode().takes_register() {
+                    source.push_str(&format!(" {}", instr.register().unwrap()));
+                }
+                source.push_str(&format!(" l{}\n", target));
+            }
+            _ => source.push_str(&format!("    {}\n", instr)),
+        }
+    }
+
+    source
+}
+
+/// Identifies the binary format
+const MAGIC: &[u8; 4] = b"HHC8";
+/// Version of the binary format `encode` writes
+const VERSION: u8 = 1;
+
+/// Compact binary encoding of a program. After a 4 byte magic number and a version byte comes the number of
+/// instructions, then each instruction as one byte holding the opcode in the low 4 bits and the register in the next
+/// 2, followed by its value if it has one. Numbers are LEB128 varints, with values zigzag encoded first so small
+/// negative offsets stay small. `decode` always gives back exactly the program that was encoded.
+pub fn encode(program: &FnvHashMap<usize, Instruction>) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    write_varint(&mut bytes, program.len() as u64);
+
+    for addr in 0..program.len() {
+        let instr = program[&addr];
+        let opcode = instr.opcode();
+        let opcode_idx = Opcode::ALL.iter().position(|op| *op == opcode).unwrap() as u8;
+        let register_idx = instr.register().unwrap_or(Register::Acc) as u8;
+        bytes.push(opcode_idx | register_idx << 4);
+        if opcode.takes_value() {
+            let value = instr.value() as i64;
+            write_varint(&mut bytes, ((value << 1) ^ (value >> 63)) as u64);
+        }
+    }
+
+    bytes
+}
+
+/// Why bytes couldn't be decoded into a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes don't start with the magic number
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// The byte at `offset` doesn't hold a known opcode and register
+    BadInstruction {
+        offset: usize,
+        byte: u8,
+    },
+    /// A number starting at `offset` doesn't fit
+    Overflow {
+        offset: usize,
+    },
+    /// The bytes stop part way through
+    UnexpectedEnd,
+    /// There are bytes left over after the last instruction, starting at `offset`
+    TrailingBytes {
+        offset: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a boot program"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {}", version),
+            Self::BadInstruction { offset, byte } => {
+                write!(f, "bad instruction byte {:#04x} at offset {}", byte, offset)
+            }
+            Self::Overflow { offset } => write!(f, "number at offset {} is too large", offset),
+            Self::UnexpectedEnd => write!(f, "unexpected end of program"),
+            Self::TrailingBytes { offset } => write!(f, "unexpected bytes from offset {}", offset),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Read a program written by `encode`
+pub fn decode(bytes: &[u8]) -> Result<FnvHashMap<usize, Instruction>, DecodeError> {
+    if bytes.len() < MAGIC.len() + 1 {
+        return Err(if MAGIC.starts_with(bytes) {
+            DecodeError::UnexpectedEnd
+        } else {
+            DecodeError::BadMagic
+        });
+    }
+    if &bytes[..MAGIC.len()] != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    if bytes[MAGIC.len()] != VERSION {
+        return Err(DecodeError::UnsupportedVersion(bytes[MAGIC.len()]));
+    }
+
+    let mut pos = MAGIC.len() + 1;
+    let len = read_varint(bytes, &mut pos)?;
+    let mut mem = FnvHashMap::default();
+    for addr in 0..len as usize {
+        let offset = pos;
+        let byte = *bytes.get(pos).ok_or(DecodeError::UnexpectedEnd)?;
+        pos += 1;
+
+        let opcode = Opcode::ALL.get((byte & 0x0f) as usize);
+        let register = Register::ALL.get((byte >> 4) as usize);
+        let (opcode, register) = match (opcode, register) {
+            // Only opcodes that take a register may name one other than the default, so every program has exactly
+            // one encoding
+            (Some(opcode), Some(register))
+                if opcode.takes_register() || *register == Register::Acc =>
+            {
+                (*opcode, *register)
+            }
+            _ => return Err(DecodeError::BadInstruction { offset, byte }),
+        };
+
+        let value = if opcode.takes_value() {
+            let offset = pos;
+            let zigzag = read_varint(bytes, &mut pos)?;
+            let value = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+            if value < isize::MIN as i64 || value > isize::MAX as i64 {
+                return Err(DecodeError::Overflow { offset });
+            }
+            value as isize
+        } else {
+            0
+        };
+
+        mem.insert(addr, opcode.with_operands(register, value));
+    }
+
+    if pos != bytes.len() {
+        return Err(DecodeError::TrailingBytes { offset: pos });
+    }
+
+    Ok(mem)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, DecodeError> {
+    let offset = *pos;
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos).ok_or(DecodeError::UnexpectedEnd)?;
+        *pos += 1;
+
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            return Err(DecodeError::Overflow { offset });
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(DecodeError::Overflow { offset })
+}
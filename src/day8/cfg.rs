@@ -0,0 +1,223 @@
+use fnv::FnvHashMap;
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::Instruction;
+
+/// Control-flow graph of a boot program. Every instruction is a node, plus one extra node for the address just past
+/// the last instruction, which is where a program that terminates normally ends up. Conditional jumps have an edge to
+/// both places they can go, and jumps that leave the program have no edge at all.
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(program: &FnvHashMap<usize, Instruction>) -> Self {
+        let len = program.len();
+        let mut successors = vec![Vec::new(); len + 1];
+        let mut predecessors = vec![Vec::new(); len + 1];
+
+        for addr in 0..len {
+            for next in next_addresses(addr, &program[&addr], len) {
+                successors[addr].push(next);
+                predecessors[next].push(addr);
+            }
+        }
+
+        ControlFlowGraph {
+            successors,
+            predecessors,
+        }
+    }
+
+    /// The node for normal termination, one past the last instruction
+    pub fn end(&self) -> usize {
+        self.successors.len() - 1
+    }
+
+    /// Where execution can go after the instruction at `addr`
+    pub fn successors(&self, addr: usize) -> &[usize] {
+        &self.successors[addr]
+    }
+
+    /// Instructions that can be followed by the one at `addr`
+    pub fn predecessors(&self, addr: usize) -> &[usize] {
+        &self.predecessors[addr]
+    }
+
+    /// Shortest number of instructions from each address to the end of the program, `None` for addresses that can
+    /// never get there. This is a single search backwards from the end, so every address is visited once.
+    pub fn distances_to_end(&self) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.successors.len()];
+        let mut queue = VecDeque::new();
+        distances[self.end()] = Some(0);
+        queue.push_back(self.end());
+
+        while let Some(addr) = queue.pop_front() {
+            let distance = distances[addr].unwrap();
+            for prev in &self.predecessors[addr] {
+                if distances[*prev].is_none() {
+                    distances[*prev] = Some(distance + 1);
+                    queue.push_back(*prev);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Which addresses can reach the end of the program
+    pub fn terminating(&self) -> Vec<bool> {
+        self.distances_to_end()
+            .iter()
+            .map(Option::is_some)
+            .collect()
+    }
+}
+
+/// Addresses execution can move to from `addr`, leaving out jumps outside the program
+fn next_addresses(addr: usize, instr: &Instruction, len: usize) -> Vec<usize> {
+    let jump = |offset: isize| {
+        let target = (addr as isize).checked_add(offset)?;
+        if target >= 0 && target as usize <= len {
+            Some(target as usize)
+        } else {
+            None
+        }
+    };
+
+    match instr {
+        Instruction::NOP(_) | Instruction::ACC(_) | Instruction::ADD(..) | Instruction::SET(..) => {
+            vec![addr + 1]
+        }
+        Instruction::JMP(offset) => jump(*offset).into_iter().collect(),
+        Instruction::JZ(_, offset) | Instruction::JNZ(_, offset) => {
+            let mut next = vec![addr + 1];
+            next.extend(jump(*offset).filter(|target| *target != addr + 1));
+            next
+        }
+        Instruction::HLT => Vec::new(),
+    }
+}
+
+/// The single `jmp`/`nop` flip that makes a program terminate, and why it works
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub addr: usize,
+    pub original: Instruction,
+    pub replacement: Instruction,
+    /// How many instructions the unrepaired program runs before reaching `addr`
+    pub reached_after: usize,
+    /// Where execution goes after the repaired instruction
+    pub target: usize,
+    /// How many instructions it takes to get from `target` to the end of the program
+    pub steps_to_end: usize,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "change `{}` at {} to `{}`: the program reaches {} after {} instruction(s), and from there the change \
+             sends it to {}, which gets to the end of the program in {} instruction(s)",
+            self.original,
+            self.addr,
+            self.replacement,
+            self.addr,
+            self.reached_after,
+            self.target,
+            self.steps_to_end
+        )
+    }
+}
+
+/// Why `repair` couldn't find a flip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairError {
+    /// The program terminates as it is
+    AlreadyTerminates,
+    /// The program has a conditional jump at this address, so where it goes depends on register values, which this
+    /// analysis doesn't track
+    ConditionalJump(usize),
+    /// No single flip along the path the program takes leads to the end
+    NoRepair,
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyTerminates => write!(f, "the program already terminates"),
+            Self::ConditionalJump(addr) => write!(
+                f,
+                "the conditional jump at {} makes the program depend on registers",
+                addr
+            ),
+            Self::NoRepair => write!(f, "no single jmp/nop flip makes the program terminate"),
+        }
+    }
+}
+
+impl std::error::Error for RepairError {}
+
+/// Find the one `jmp`/`nop` flip that makes `program` terminate, in time linear in the length of the program.
+///
+/// The unrepaired program follows a single path from address 0 until it loops or gets stuck. The flip has to be
+/// somewhere on that path, since nothing else ever runs, and it has to send execution somewhere that can reach the
+/// end. Any such address works: if the way from there to the end went back through the flipped instruction, that
+/// instruction could already reach the end unflipped, and so could the unrepaired program. So one search backwards
+/// from the end, and one walk along the path, are all it takes.
+pub fn repair(program: &FnvHashMap<usize, Instruction>) -> Result<Repair, RepairError> {
+    let cfg = ControlFlowGraph::new(program);
+    let distances = cfg.distances_to_end();
+    let len = program.len();
+
+    // The distances treat conditional jumps as going both ways, so with one anywhere in the program a flip could lead
+    // to an end the registers never let it reach
+    if let Some(addr) =
+        (0..len).find(|addr| matches!(program[addr], Instruction::JZ(..) | Instruction::JNZ(..)))
+    {
+        return Err(RepairError::ConditionalJump(addr));
+    }
+
+    // Walk the path the unrepaired program takes
+    let mut path = Vec::new();
+    let mut visited = vec![false; len];
+    let mut addr = 0;
+    while addr < len && !visited[addr] {
+        visited[addr] = true;
+        path.push(addr);
+        match cfg.successors(addr) {
+            [next] => addr = *next,
+            _ => break,
+        }
+    }
+    if addr == len {
+        return Err(RepairError::AlreadyTerminates);
+    }
+
+    for (reached_after, addr) in path.into_iter().enumerate() {
+        let original = program[&addr];
+        let replacement = match original {
+            Instruction::JMP(val) => Instruction::NOP(val),
+            Instruction::NOP(val) => Instruction::JMP(val),
+            _ => continue,
+        };
+
+        for target in next_addresses(addr, &replacement, len) {
+            if let Some(steps_to_end) = distances[target] {
+                return Ok(Repair {
+                    addr,
+                    original,
+                    replacement,
+                    reached_after,
+                    target,
+                    steps_to_end,
+                });
+            }
+        }
+    }
+
+    Err(RepairError::NoRepair)
+}
@@ -0,0 +1,514 @@
+use fnv::FnvHashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use super::{ExitReason, Instruction, LoopDetector, Opcode, Register, CPU};
+
+/// Where execution should pause before running an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Before the instruction at this address
+    Address(usize),
+    /// Before any instruction with this opcode
+    Opcode(Opcode),
+}
+
+impl Breakpoint {
+    fn hits(&self, ip: usize, instr: &Instruction) -> bool {
+        match self {
+            Self::Address(addr) => *addr == ip,
+            Self::Opcode(opcode) => *opcode == instr.opcode(),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(addr) => write!(f, "address {}", addr),
+            Self::Opcode(opcode) => write!(f, "opcode {}", opcode),
+        }
+    }
+}
+
+/// Pause execution after an instruction changes a register, or sets it to a particular value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub register: Register,
+    /// Only pause when the register ends up holding this value
+    pub value: Option<isize>,
+}
+
+impl Watchpoint {
+    /// Watch the accumulator for any change
+    pub fn acc() -> Self {
+        Watchpoint {
+            register: Register::Acc,
+            value: None,
+        }
+    }
+
+    fn hits(&self, old: isize, new: isize) -> bool {
+        old != new && (self.value.is_none() || self.value == Some(new))
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "{} == {}", self.register, value),
+            None => write!(f, "{} changes", self.register),
+        }
+    }
+}
+
+/// Why the debugger handed control back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    /// A single step finished without anything else happening
+    Stepped,
+    /// The next instruction has a breakpoint on it
+    Breakpoint(Breakpoint),
+    /// The last instruction changed a watched register from `old` to `new`
+    Watchpoint {
+        watchpoint: Watchpoint,
+        old: isize,
+        new: isize,
+    },
+    /// The program can't go on
+    Exited(ExitReason),
+    /// `resume` ran out of steps
+    StepLimit,
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stepped => write!(f, "stepped"),
+            Self::Breakpoint(breakpoint) => write!(f, "breakpoint on {}", breakpoint),
+            Self::Watchpoint {
+                watchpoint,
+                old,
+                new,
+            } => write!(
+                f,
+                "watchpoint on {}: {} -> {}",
+                watchpoint.register, old, new
+            ),
+            Self::Exited(reason) => write!(f, "program {}", reason),
+            Self::StepLimit => write!(f, "step limit reached"),
+        }
+    }
+}
+
+/// One executed instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub ip: usize,
+    pub instr: Instruction,
+    /// Registers after the instruction ran, indexed like `Register::ALL`
+    pub registers: [isize; 4],
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>5}: {:<12}", self.ip, self.instr.to_string())?;
+        for reg in Register::ALL.iter() {
+            write!(f, " {}={}", reg, self.registers[*reg as usize])?;
+        }
+        Ok(())
+    }
+}
+
+/// Something the debugger can undo
+#[derive(Debug, Clone)]
+enum Event {
+    /// An instruction ran, starting from this `ip` and these registers
+    Step { ip: usize, registers: [isize; 4] },
+    /// The instruction at `addr` was patched over `previous`
+    Patch { addr: usize, previous: Instruction },
+}
+
+/// Debugging layer over a `CPU`, with breakpoints, watchpoints, a trace of every instruction run, coverage, and the
+/// ability to step backwards through both execution and patches
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    cpu: CPU,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    history: Vec<Event>,
+    trace: Vec<TraceEntry>,
+    /// How many times each address has been executed
+    coverage: Vec<usize>,
+    /// States `resume` has passed through, kept between calls so a loop is caught the first time it comes around
+    detector: LoopDetector,
+}
+
+impl Debugger {
+    pub fn new(mem: &FnvHashMap<usize, Instruction>) -> Self {
+        let cpu = CPU::new(mem);
+        let coverage = vec![0; cpu.len()];
+        let detector = LoopDetector::new(&cpu);
+        Debugger {
+            cpu,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            history: Vec::new(),
+            trace: Vec::new(),
+            coverage,
+            detector,
+        }
+    }
+
+    pub fn cpu(&self) -> &CPU {
+        &self.cpu
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    /// Remove every breakpoint and watchpoint
+    pub fn clear_points(&mut self) {
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+    }
+
+    /// Every instruction executed so far, oldest first. Stepping backwards removes entries.
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    /// How many times each address has been executed
+    pub fn coverage(&self) -> &[usize] {
+        &self.coverage
+    }
+
+    /// Addresses that have never been executed
+    pub fn uncovered(&self) -> Vec<usize> {
+        (0..self.coverage.len())
+            .filter(|addr| self.coverage[*addr] == 0)
+            .collect()
+    }
+
+    /// Execute one instruction, unless the CPU is in a state it has been in before and would loop forever
+    pub fn step(&mut self) -> Stop {
+        if let Some(reason) = self.detector.check(&self.cpu) {
+            return Stop::Exited(reason);
+        }
+
+        let ip = self.cpu.ip;
+        let before = self.cpu.registers;
+        if let Err(reason) = self.cpu.step() {
+            return Stop::Exited(reason);
+        }
+
+        let instr = self.cpu.mem[&ip];
+        self.history.push(Event::Step {
+            ip,
+            registers: before,
+        });
+        self.trace.push(TraceEntry {
+            ip,
+            instr,
+            registers: self.cpu.registers,
+        });
+        self.coverage[ip] += 1;
+
+        for watchpoint in &self.watchpoints {
+            let reg = watchpoint.register as usize;
+            if watchpoint.hits(before[reg], self.cpu.registers[reg]) {
+                return Stop::Watchpoint {
+                    watchpoint: *watchpoint,
+                    old: before[reg],
+                    new: self.cpu.registers[reg],
+                };
+            }
+        }
+
+        Stop::Stepped
+    }
+
+    /// Keep stepping until a breakpoint or watchpoint is hit, the program stops or loops, or `max_steps` instructions
+    /// have run. A breakpoint on the instruction execution resumes from doesn't stop it straight away.
+    pub fn resume(&mut self, max_steps: usize) -> Stop {
+        for count in 0..max_steps {
+            if count > 0 {
+                if let Some(breakpoint) = self.breakpoint_at_ip() {
+                    return Stop::Breakpoint(breakpoint);
+                }
+            }
+            match self.step() {
+                Stop::Stepped => {}
+                stop => return stop,
+            }
+        }
+
+        Stop::StepLimit
+    }
+
+    fn breakpoint_at_ip(&self) -> Option<Breakpoint> {
+        let instr = self.cpu.instruction(self.cpu.ip)?;
+        self.breakpoints
+            .iter()
+            .copied()
+            .find(|breakpoint| breakpoint.hits(self.cpu.ip, instr))
+    }
+
+    /// Undo the last instruction or patch, returning `false` if there is nothing left to undo
+    pub fn reverse_step(&mut self) -> bool {
+        let undone = match self.history.pop() {
+            Some(Event::Step { ip, registers }) => {
+                self.cpu.ip = ip;
+                self.cpu.registers = registers;
+                self.trace.pop();
+                self.coverage[ip] -= 1;
+                true
+            }
+            Some(Event::Patch { addr, previous }) => {
+                self.cpu.mem.insert(addr, previous);
+                // Once the original is back in place the CPU has nothing to restore on reset
+                if self.cpu.patch_list.get(&addr) == Some(&previous) {
+                    self.cpu.patch_list.remove(&addr);
+                }
+                true
+            }
+            None => false,
+        };
+
+        // The states seen so far may be ahead of where execution now is, or belong to a different program
+        self.detector = LoopDetector::new(&self.cpu);
+        undone
+    }
+
+    /// Replace the instruction at `addr`, returning the instruction that was there. The CPU's `patch_list` keeps the
+    /// original so `reset` restores it, and `reverse_step` can undo the patch.
+    pub fn patch(&mut self, addr: usize, instr: Instruction) -> Option<Instruction> {
+        let previous = *self.cpu.mem.get(&addr)?;
+        self.cpu.patch_list.entry(addr).or_insert(previous);
+        self.cpu.mem.insert(addr, instr);
+        self.history.push(Event::Patch { addr, previous });
+        self.detector = LoopDetector::new(&self.cpu);
+        Some(previous)
+    }
+
+    /// Put the CPU back to its starting state, undoing every patch and forgetting the trace and coverage
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+        self.detector = LoopDetector::new(&self.cpu);
+        self.history.clear();
+        self.trace.clear();
+        self.coverage.iter_mut().for_each(|count| *count = 0);
+    }
+
+    /// Listing of the program with the coverage of each instruction and the instruction pointer marked
+    pub fn listing(&self) -> String {
+        let mut listing = String::new();
+        for addr in 0..self.cpu.len() {
+            let marker = if addr == self.cpu.ip { "=>" } else { "  " };
+            let breakpoint = if self
+                .breakpoints
+                .iter()
+                .any(|breakpoint| breakpoint.hits(addr, &self.cpu.mem[&addr]))
+            {
+                "*"
+            } else {
+                " "
+            };
+            listing.push_str(&format!(
+                "{}{}{:>5}: {:<12} x{}\n",
+                marker,
+                breakpoint,
+                addr,
+                self.cpu.mem[&addr].to_string(),
+                self.coverage[addr]
+            ));
+        }
+        if self.cpu.ip == self.cpu.len() {
+            listing.push_str(&format!("=> {:>5}: <end>\n", self.cpu.ip));
+        }
+
+        listing
+    }
+}
+
+const HELP: &str = "commands:
+  s, step [n]          run n instructions (default 1)
+  c, continue [n]      run until a breakpoint, watchpoint, loop or exit (at most n instructions)
+  r, back [n]          undo n instructions or patches (default 1)
+  b, break <addr|op>   break before an address or any instruction with an opcode
+  w, watch <reg> [v]   stop when a register changes, or changes to v
+  d, delete            remove every breakpoint and watchpoint
+  p, regs              show the instruction pointer and registers
+  l, list              show the program with coverage
+  t, trace [n]         show the last n executed instructions (default 10)
+  cov, coverage        summarize coverage
+  patch <addr> <instr> replace an instruction, e.g. `patch 7 nop -4`
+  reset                start over, undoing patches
+  h, help              show this help
+  q, quit              leave the debugger";
+
+/// Most instructions `continue` runs when it isn't given a limit
+const DEFAULT_CONTINUE_STEPS: usize = 1_000_000;
+
+/// Drive `debugger` with commands read from `input`, one per line, writing responses to `output`. Stops at `quit` or
+/// the end of the input.
+pub fn repl<R: BufRead, W: Write>(
+    debugger: &mut Debugger,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    write!(output, "(dbg) ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let count = |default: usize| -> Result<usize, String> {
+            words.get(1).map_or(Ok(default), |n| {
+                n.parse().map_err(|_| format!("`{}` is not a count", n))
+            })
+        };
+
+        let response = match words.first().copied() {
+            None => Ok(String::new()),
+            Some("q") | Some("quit") => break,
+            Some("h") | Some("help") => Ok(HELP.to_string()),
+            Some("s") | Some("step") => count(1).map(|n| {
+                let mut stop = Stop::Stepped;
+                for _ in 0..n {
+                    stop = debugger.step();
+                    if stop != Stop::Stepped {
+                        break;
+                    }
+                }
+                describe(debugger, &stop)
+            }),
+            Some("c") | Some("continue") => count(DEFAULT_CONTINUE_STEPS).map(|n| {
+                let stop = debugger.resume(n);
+                describe(debugger, &stop)
+            }),
+            Some("r") | Some("back") => count(1).map(|n| {
+                let undone = (0..n).take_while(|_| debugger.reverse_step()).count();
+                format!("undid {}\n{}", undone, position(debugger))
+            }),
+            Some("b") | Some("break") => match words.get(1) {
+                Some(target) => {
+                    let breakpoint = match (target.parse(), Opcode::from_mnemonic(target)) {
+                        (Ok(addr), _) => Ok(Breakpoint::Address(addr)),
+                        (_, Some(opcode)) => Ok(Breakpoint::Opcode(opcode)),
+                        _ => Err(format!("`{}` is not an address or opcode", target)),
+                    };
+                    breakpoint.map(|breakpoint| {
+                        debugger.add_breakpoint(breakpoint);
+                        format!("breakpoint on {}", breakpoint)
+                    })
+                }
+                None => Err(String::from("break needs an address or opcode")),
+            },
+            Some("w") | Some("watch") => {
+                let register = words
+                    .get(1)
+                    .map_or(Some(Register::Acc), |name| Register::from_name(name));
+                let value = words.get(2).map(|value| value.parse::<isize>()).transpose();
+                match (register, value) {
+                    (None, _) => Err(format!("`{}` is not a register", words[1])),
+                    (_, Err(_)) => Err(format!("`{}` is not a value", words[2])),
+                    (Some(register), Ok(value)) => {
+                        let watchpoint = Watchpoint { register, value };
+                        debugger.add_watchpoint(watchpoint);
+                        Ok(format!("watching {}", watchpoint))
+                    }
+                }
+            }
+            Some("d") | Some("delete") => {
+                debugger.clear_points();
+                Ok(String::from("removed every breakpoint and watchpoint"))
+            }
+            Some("p") | Some("regs") => Ok(position(debugger)),
+            Some("l") | Some("list") => Ok(debugger.listing().trim_end().to_string()),
+            Some("t") | Some("trace") => count(10).map(|n| {
+                let trace = debugger.trace();
+                trace[trace.len().saturating_sub(n)..]
+                    .iter()
+                    .map(TraceEntry::to_string)
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            }),
+            Some("cov") | Some("coverage") => {
+                let uncovered = debugger.uncovered();
+                let total = debugger.coverage().len();
+                Ok(format!(
+                    "{}/{} instructions executed, never executed: {:?}",
+                    total - uncovered.len(),
+                    total,
+                    uncovered
+                ))
+            }
+            Some("patch") => {
+                let addr = words.get(1).and_then(|addr| addr.parse::<usize>().ok());
+                // Rejoin the instruction's own words, however they were spaced out
+                let instr = words
+                    .get(2..)
+                    .filter(|rest| !rest.is_empty())
+                    .map(|rest| rest.join(" "));
+                match (addr, instr.as_deref().map(Instruction::from_str)) {
+                    (Some(addr), Some(Ok(instr))) => match debugger.patch(addr, instr) {
+                        Some(previous) => Ok(format!("{}: {} -> {}", addr, previous, instr)),
+                        None => Err(format!("there is no instruction at {}", addr)),
+                    },
+                    (_, Some(Err(err))) => Err(err.kind.to_string()),
+                    _ => Err(String::from("patch needs an address and an instruction")),
+                }
+            }
+            Some("reset") => {
+                debugger.reset();
+                Ok(position(debugger))
+            }
+            Some(command) => Err(format!("unknown command `{}`, try `help`", command)),
+        };
+
+        match response {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => writeln!(output, "{}", text)?,
+            Err(err) => writeln!(output, "error: {}", err)?,
+        }
+        write!(output, "(dbg) ")?;
+        output.flush()?;
+    }
+
+    writeln!(output)
+}
+
+/// The instruction pointer, next instruction and registers
+fn position(debugger: &Debugger) -> String {
+    let cpu = debugger.cpu();
+    let next = cpu
+        .instruction(cpu.ip())
+        .map_or_else(|| String::from("<end>"), Instruction::to_string);
+    let registers: Vec<String> = Register::ALL
+        .iter()
+        .map(|reg| format!("{}={}", reg, cpu.register(*reg)))
+        .collect();
+    format!("ip={} ({}) {}", cpu.ip(), next, registers.join(" "))
+}
+
+fn describe(debugger: &Debugger, stop: &Stop) -> String {
+    format!("{}\n{}", stop, position(debugger))
+}
@@ -1,21 +1,173 @@
 use fnv::{FnvHashMap, FnvHashSet};
-use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
 
-lazy_static! {
-    static ref INSTRUCTIONS: Regex = Regex::new(r"(\w{3}) ([+-]\d+)").unwrap();
-}
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
+
+pub mod asm;
+pub mod cfg;
+pub mod debugger;
+
+pub use asm::{assemble, decode, disassemble, encode, to_assembly, DecodeError};
+pub use cfg::{repair, ControlFlowGraph, Repair, RepairError};
+pub use debugger::{Breakpoint, Debugger, Stop, TraceEntry, Watchpoint};
 
 /// Holds the various reasons that the program execution exits
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExitReason {
-    /// If a loop is encountered `ExitReason::Loop` is created with the current value of the accumulator
-    Loop(isize),
-    /// If the instruction pointer does not point to an instruction `ExitReason::NoInstruction` is created
-    NoInstruction,
+    /// The instruction pointer is exactly one past the last instruction, the program finished normally
+    Terminated { acc: isize },
+    /// A `hlt` instruction at `ip` stopped the program
+    Halted { ip: usize, acc: isize },
+    /// The jump at `ip` tried to go to `target`, which is neither an instruction nor the end of the program
+    OutOfBounds { ip: usize, target: isize },
+    /// `run` executed `steps` instructions without the program stopping, `ip` is the next instruction
+    StepLimit { steps: usize, ip: usize },
+    /// The program is about to run the instruction at `ip` in a state it has been in before, so it would run forever.
+    /// `acc` is the accumulator at that point.
+    Loop { ip: usize, acc: isize },
+    /// The instruction at `ip` would take a register or the jump target past what an `isize` can hold
+    Overflow { ip: usize },
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Terminated { acc } => write!(f, "terminated normally with acc {}", acc),
+            Self::Halted { ip, acc } => write!(f, "halted at {} with acc {}", ip, acc),
+            Self::OutOfBounds { ip, target } => {
+                write!(f, "jump at {} to {} is out of bounds", ip, target)
+            }
+            Self::StepLimit { steps, ip } => {
+                write!(f, "still running at {} after {} steps", ip, steps)
+            }
+            Self::Loop { ip, acc } => write!(f, "loops at {} with acc {}", ip, acc),
+            Self::Overflow { ip } => write!(f, "overflows at {}", ip),
+        }
+    }
+}
+
+/// Registers of the handheld, the accumulator plus three general purpose registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+    Acc,
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    pub const ALL: [Register; 4] = [Self::Acc, Self::X, Self::Y, Self::Z];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Acc => "acc",
+            Self::X => "x",
+            Self::Y => "y",
+            Self::Z => "z",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|reg| reg.name() == name)
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The operation of an `Instruction` without its operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Nop,
+    Acc,
+    Jmp,
+    Add,
+    Set,
+    Jz,
+    Jnz,
+    Hlt,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 8] = [
+        Self::Nop,
+        Self::Acc,
+        Self::Jmp,
+        Self::Add,
+        Self::Set,
+        Self::Jz,
+        Self::Jnz,
+        Self::Hlt,
+    ];
+
+    /// Name of the opcode as written in a program
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Nop => "nop",
+            Self::Acc => "acc",
+            Self::Jmp => "jmp",
+            Self::Add => "add",
+            Self::Set => "set",
+            Self::Jz => "jz",
+            Self::Jnz => "jnz",
+            Self::Hlt => "hlt",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|op| op.mnemonic() == mnemonic)
+    }
+
+    /// Whether the opcode takes a register before its value
+    pub fn takes_register(&self) -> bool {
+        matches!(self, Self::Add | Self::Set | Self::Jz | Self::Jnz)
+    }
+
+    /// Whether the opcode takes a value
+    pub fn takes_value(&self) -> bool {
+        !matches!(self, Self::Hlt)
+    }
+
+    /// Whether the value of the opcode is a jump offset
+    pub fn is_jump(&self) -> bool {
+        matches!(self, Self::Jmp | Self::Jz | Self::Jnz)
+    }
+
+    /// Build the instruction for this opcode from its operands, `reg` is ignored by opcodes that don't take one
+    pub fn with_operands(&self, reg: Register, val: isize) -> Instruction {
+        match self {
+            Self::Nop => Instruction::NOP(val),
+            Self::Acc => Instruction::ACC(val),
+            Self::Jmp => Instruction::JMP(val),
+            Self::Add => Instruction::ADD(reg, val),
+            Self::Set => Instruction::SET(reg, val),
+            Self::Jz => Instruction::JZ(reg, val),
+            Self::Jnz => Instruction::JNZ(reg, val),
+            Self::Hlt => Instruction::HLT,
+        }
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
 }
 
 /// Represents all possible instructions of the handheld device
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Instruction {
     /// No Operation
     NOP(isize),
@@ -23,25 +175,125 @@ pub enum Instruction {
     ACC(isize),
     /// Jump
     JMP(isize),
+    /// Add a value to a register
+    ADD(Register, isize),
+    /// Set a register to a value
+    SET(Register, isize),
+    /// Jump if the register is zero
+    JZ(Register, isize),
+    /// Jump if the register isn't zero
+    JNZ(Register, isize),
+    /// Halt
+    HLT,
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    /// Parse an `Instruction` variant from a single line of the program, such as `jmp -4` or `jnz x +2`
+    fn from_str(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with(input, |_, _| None)
+    }
 }
 
 impl Instruction {
-    /// Parse an `Instruction` variant from a &str
-    pub fn from_str(input: &str, val: &str) -> Self {
-        match input {
-            "nop" => Self::NOP(val.parse::<isize>().expect("Error parsing NOP value")),
-            "acc" => Self::ACC(val.parse::<isize>().expect("Error parsing ACC value")),
-            "jmp" => Self::JMP(val.parse::<isize>().expect("Error parsing JMP value")),
-            _ => unreachable!(),
+    /// Like `from_str`, but a value that isn't a `+`/`-` number is handed to `symbol` along with the opcode, which can
+    /// resolve it (or fail with an error relative to `input`). Returning `None` rejects the value as malformed.
+    pub(crate) fn parse_with<'a, F>(input: &'a str, symbol: F) -> Result<Self, ParseError>
+    where
+        F: FnOnce(Opcode, &'a str) -> Option<Result<isize, ParseError>>,
+    {
+        let mut operands = input.split_whitespace();
+        let mnemonic = operands.next().unwrap_or(input);
+        let opcode = Opcode::from_mnemonic(mnemonic)
+            .ok_or_else(|| ParseError::new(8, input, mnemonic, ParseErrorKind::UnknownKeyword))?;
+
+        let malformed = || {
+            ParseError::new(
+                8,
+                input,
+                input,
+                ParseErrorKind::Malformed("`<opcode> [<register>] <+/-value>`"),
+            )
+        };
+
+        let reg = if opcode.takes_register() {
+            let name = operands.next().ok_or_else(malformed)?;
+            Register::from_name(name)
+                .ok_or_else(|| ParseError::new(8, input, name, ParseErrorKind::UnknownKeyword))?
+        } else {
+            Register::Acc
+        };
+
+        let val = if opcode.takes_value() {
+            let val = operands.next().ok_or_else(malformed)?;
+            if val.starts_with(['+', '-']) {
+                val.parse::<isize>()
+                    .map_err(|_| ParseError::new(8, input, val, ParseErrorKind::InvalidNumber))?
+            } else {
+                symbol(opcode, val).ok_or_else(malformed)??
+            }
+        } else {
+            0
+        };
+
+        if operands.next().is_some() {
+            return Err(malformed());
+        }
+
+        Ok(opcode.with_operands(reg, val))
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Self::NOP(_) => Opcode::Nop,
+            Self::ACC(_) => Opcode::Acc,
+            Self::JMP(_) => Opcode::Jmp,
+            Self::ADD(..) => Opcode::Add,
+            Self::SET(..) => Opcode::Set,
+            Self::JZ(..) => Opcode::Jz,
+            Self::JNZ(..) => Opcode::Jnz,
+            Self::HLT => Opcode::Hlt,
+        }
+    }
+
+    /// The register the instruction uses, `acc` for the instructions that only ever touch the accumulator
+    pub fn register(&self) -> Option<Register> {
+        match self {
+            Self::ACC(_) => Some(Register::Acc),
+            Self::ADD(reg, _) | Self::SET(reg, _) | Self::JZ(reg, _) | Self::JNZ(reg, _) => {
+                Some(*reg)
+            }
+            Self::NOP(_) | Self::JMP(_) | Self::HLT => None,
+        }
+    }
+
+    /// The value operand of the instruction, `hlt` has none so it is 0
+    pub fn value(&self) -> isize {
+        match self {
+            Self::NOP(val) | Self::ACC(val) | Self::JMP(val) => *val,
+            Self::ADD(_, val) | Self::SET(_, val) | Self::JZ(_, val) | Self::JNZ(_, val) => *val,
+            Self::HLT => 0,
         }
     }
 
     /// Boolean check if Instruction is `Instruction:ACC(_)`, useful for filtering
     pub fn is_acc(&self) -> bool {
-        match self {
-            Self::ACC(_) => true,
-            _ => false,
+        matches!(self, Self::ACC(_))
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opcode = self.opcode();
+        write!(f, "{}", opcode)?;
+        if opcode.takes_register() {
+            write!(f, " {}", self.register().unwrap())?;
+        }
+        if opcode.takes_value() {
+            write!(f, " {:+}", self.value())?;
         }
+        Ok(())
     }
 }
 
@@ -50,8 +302,8 @@ impl Instruction {
 pub struct CPU {
     /// Instruction pointer - Points at what instruction will be executed
     ip: usize,
-    /// Accumulator - Register for accumulating values during execution
-    acc: isize,
+    /// Registers - The accumulator and general purpose registers, indexed by `Register`
+    registers: [isize; 4],
     /// Memory - Holds the address and `Instruction` for execution
     mem: FnvHashMap<usize, Instruction>,
     /// Patch list - Holds any patches that are made to the CPU memory for faster resets, holds the `self.ip` and
@@ -65,8 +317,8 @@ impl CPU {
         CPU {
             // Always start the CPU at address 0
             ip: 0,
-            // Accumulator always starts at 0
-            acc: 0,
+            // Registers always start at 0
+            registers: [0; 4],
             // This clone is a byproduct of restrictitons from cargo-aoc, we can't use a reference
             // since we can't pass lifetimes around, we can't parse in `CPU::new` because we can use
             // mutable references to part1 and part2. This should change when moving to any other runner
@@ -79,39 +331,126 @@ impl CPU {
     /// Reset the CPU to starting state
     pub fn reset(&mut self) {
         self.ip = 0;
-        self.acc = 0;
+        self.registers = [0; 4];
 
         for (ip, instr) in &self.patch_list {
-            *self.mem.get_mut(ip).unwrap() = instr.clone();
+            *self.mem.get_mut(ip).unwrap() = *instr;
         }
 
         self.patch_list = FnvHashMap::default();
     }
 
-    /// Execute a single instruction within the CPU
-    fn execute(&mut self) -> Result<(), ExitReason> {
-        // Try to get an instruction at `self.ip`
-        if let Some(instr) = self.mem.get(&self.ip) {
-            // Match the instruction for execution
-            match instr {
-                // NOP we do nothing except move on
-                Instruction::NOP(_) => {
-                    self.ip += 1;
-                }
-                // ACC we add to `self.acc` the value associated with the ACC instruction
-                Instruction::ACC(val) => {
-                    self.acc += val;
-                    self.ip += 1;
-                }
-                // JMP we add to `self.ip` the value associated with the JMP instruction
-                Instruction::JMP(val) => self.ip = self.ip.wrapping_add(*val as usize),
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn acc(&self) -> isize {
+        self.register(Register::Acc)
+    }
+
+    pub fn register(&self, reg: Register) -> isize {
+        self.registers[reg.index()]
+    }
+
+    /// Number of instructions in the program, the program terminates when `ip` gets here
+    pub fn len(&self) -> usize {
+        self.mem.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mem.is_empty()
+    }
+
+    /// The instruction at `addr`, `None` past the end of the program
+    pub fn instruction(&self, addr: usize) -> Option<&Instruction> {
+        self.mem.get(&addr)
+    }
+
+    /// Move the instruction pointer by `offset` from the instruction at `self.ip`
+    fn jump(&mut self, offset: isize) -> Result<(), ExitReason> {
+        let target = (self.ip as isize)
+            .checked_add(offset)
+            .ok_or(ExitReason::Overflow { ip: self.ip })?;
+        if target < 0 || target as usize > self.len() {
+            return Err(ExitReason::OutOfBounds {
+                ip: self.ip,
+                target,
+            });
+        }
+
+        self.ip = target as usize;
+        Ok(())
+    }
+
+    /// Add `val` to `reg` and move on to the next instruction
+    fn add(&mut self, reg: Register, val: isize) -> Result<(), ExitReason> {
+        let sum = self.registers[reg.index()]
+            .checked_add(val)
+            .ok_or(ExitReason::Overflow { ip: self.ip })?;
+        self.registers[reg.index()] = sum;
+        self.ip += 1;
+        Ok(())
+    }
+
+    /// Execute a single instruction within the CPU, returning why the program stopped if it can't go on. A stopped
+    /// program stays stopped, stepping again gives the same reason.
+    pub fn step(&mut self) -> Result<(), ExitReason> {
+        // Try to get an instruction at `self.ip`, the only address past the end the CPU can reach is the one right
+        // after the last instruction, so running out of instructions means the program finished
+        let instr = match self.mem.get(&self.ip) {
+            Some(instr) => *instr,
+            None => return Err(ExitReason::Terminated { acc: self.acc() }),
+        };
+
+        // Match the instruction for execution
+        match instr {
+            // NOP we do nothing except move on
+            Instruction::NOP(_) => self.ip += 1,
+            // ACC we add to the accumulator the value associated with the ACC instruction
+            Instruction::ACC(val) => self.add(Register::Acc, val)?,
+            // JMP we add to `self.ip` the value associated with the JMP instruction
+            Instruction::JMP(val) => self.jump(val)?,
+            Instruction::ADD(reg, val) => self.add(reg, val)?,
+            Instruction::SET(reg, val) => {
+                self.registers[reg.index()] = val;
+                self.ip += 1;
+            }
+            Instruction::JZ(reg, val) if self.register(reg) == 0 => self.jump(val)?,
+            Instruction::JNZ(reg, val) if self.register(reg) != 0 => self.jump(val)?,
+            Instruction::JZ(..) | Instruction::JNZ(..) => self.ip += 1,
+            // HLT leaves `self.ip` where it is, so the CPU stays halted
+            Instruction::HLT => {
+                return Err(ExitReason::Halted {
+                    ip: self.ip,
+                    acc: self.acc(),
+                })
             }
-            // Instruction was executed successfully
-            Ok(())
         }
-        // If an instruction doesn't exist, something happened, so we just exit
-        else {
-            Err(ExitReason::NoInstruction)
+
+        // Instruction was executed successfully
+        Ok(())
+    }
+
+    /// Run the program until it stops, or until `max_steps` instructions have been executed.
+    ///
+    /// Registers only ever change by constant amounts, so which way the program goes depends only on `self.ip` and
+    /// the registers that conditional jumps test. Once the CPU is back at an instruction with all of those registers
+    /// the same as before it is going to do the same thing forever, which is reported as a loop.
+    pub fn run(&mut self, max_steps: usize) -> ExitReason {
+        let mut detector = LoopDetector::new(self);
+        for _ in 0..max_steps {
+            if let Some(reason) = detector.check(self) {
+                return reason;
+            }
+
+            if let Err(reason) = self.step() {
+                return reason;
+            }
+        }
+
+        ExitReason::StepLimit {
+            steps: max_steps,
+            ip: self.ip,
         }
     }
 
@@ -122,18 +461,14 @@ impl CPU {
     where
         T: FnOnce(isize) -> Instruction,
     {
-        // Add our patch to our patch_list so we can reset
-        self.patch_list
-            .insert(instr_addr, self.mem.get(&instr_addr).unwrap().clone());
         // Get a mutable reference to our current instruction in the CPU
         let current_instruction = self.mem.get_mut(&instr_addr).unwrap();
-        // Unwrap the instruction to get its value, this feels clunky and there should be a better way to do this I think
-        match current_instruction {
-            // Destructure our current instruction to get its value, then give it to the new_instr closure
-            Instruction::JMP(val) => *current_instruction = new_instr(*val),
-            Instruction::ACC(val) => *current_instruction = new_instr(*val),
-            Instruction::NOP(val) => *current_instruction = new_instr(*val),
-        }
+        // Add our patch to our patch_list so we can reset, only the first patch holds the original
+        self.patch_list
+            .entry(instr_addr)
+            .or_insert(*current_instruction);
+        // Give the value of the current instruction to the new_instr closure
+        *current_instruction = new_instr(current_instruction.value());
     }
 
     /// Specific swap for Part2, this is a less generalized version of `patch_opcode` just taking an `instr_addr` and
@@ -147,33 +482,83 @@ impl CPU {
         }
     }
 
-    /// Executes instructions within the CPU until a loop is detected, or no more instructions are found to execute
-    pub fn run_until_loop(&mut self) -> Result<(), ExitReason> {
+    /// Executes instructions within the CPU until any instruction is about to run a second time, or the program stops
+    /// some other way. Unlike `run` this doesn't look at the registers at all, which is the puzzle's idea of a loop.
+    pub fn run_until_loop(&mut self) -> ExitReason {
         // Build a hashset to track instructions we have executed. This is essentially code coverage tracking.
         let mut instruction_tracker = FnvHashSet::default();
 
         loop {
-            // If we try to insert a `self.ip` that is already in the HashSet, then we are looping, so exit with the `self.acc`
-            // value for Part 1
+            // If we try to insert a `self.ip` that is already in the HashSet, then we are looping, so exit with the
+            // accumulator value for Part 1
             if !instruction_tracker.insert(self.ip) {
-                return Err(ExitReason::Loop(self.acc));
+                return ExitReason::Loop {
+                    ip: self.ip,
+                    acc: self.acc(),
+                };
             }
 
             // Continue execution of the program
-            self.execute()?
+            if let Err(reason) = self.step() {
+                return reason;
+            }
+        }
+    }
+}
+
+/// Spots a `CPU` that is going to run forever, see `CPU::run` for how
+#[derive(Debug, Clone)]
+pub(crate) struct LoopDetector {
+    /// Registers that conditional jumps test, by `Register` index
+    tested: [bool; 4],
+    seen: FnvHashSet<(usize, [isize; 4])>,
+}
+
+impl LoopDetector {
+    pub(crate) fn new(cpu: &CPU) -> Self {
+        let mut tested = [false; 4];
+        for instr in cpu.mem.values() {
+            if let Instruction::JZ(reg, _) | Instruction::JNZ(reg, _) = instr {
+                tested[reg.index()] = true;
+            }
+        }
+
+        LoopDetector {
+            tested,
+            seen: FnvHashSet::default(),
+        }
+    }
+
+    /// Record the state `cpu` is in, returning `ExitReason::Loop` if it has been in it before
+    pub(crate) fn check(&mut self, cpu: &CPU) -> Option<ExitReason> {
+        let mut state = cpu.registers;
+        for (idx, value) in state.iter_mut().enumerate() {
+            if !self.tested[idx] {
+                *value = 0;
+            }
+        }
+
+        if self.seen.insert((cpu.ip, state)) {
+            None
+        } else {
+            Some(ExitReason::Loop {
+                ip: cpu.ip,
+                acc: cpu.acc(),
+            })
         }
     }
 }
 
 #[aoc_generator(day8)]
-pub fn input_generator(input: &str) -> FnvHashMap<usize, Instruction> {
+pub fn input_generator(input: &str) -> Result<FnvHashMap<usize, Instruction>, ParseError> {
     let mut mem = FnvHashMap::default();
 
-    for (idx, cap) in INSTRUCTIONS.captures_iter(input).enumerate() {
-        mem.insert(idx, Instruction::from_str(&cap[1], &cap[2]));
+    for (idx, line) in input.lines().enumerate() {
+        let instr = Instruction::from_str(line).map_err(|err| err.within(input, line))?;
+        mem.insert(idx, instr);
     }
 
-    mem
+    Ok(mem)
 }
 
 #[aoc(day8, part1)]
@@ -181,8 +566,8 @@ pub fn solve_part1(input: &FnvHashMap<usize, Instruction>) -> isize {
     let mut cpu = CPU::new(input);
 
     match cpu.run_until_loop() {
-        Err(ExitReason::Loop(val)) => val,
-        _ => unreachable!(),
+        ExitReason::Loop { acc, .. } => acc,
+        reason => panic!("Expected the boot code to loop, but it {}", reason),
     }
 }
 
@@ -206,9 +591,8 @@ pub fn solve_part2(input: &FnvHashMap<usize, Instruction>) -> isize {
             cpu.patch_opcode(*ip, patch_op);
             // Run the CPU
             match cpu.run_until_loop() {
-                Ok(_) => None,
-                Err(ExitReason::NoInstruction) => Some(cpu.acc),
-                Err(ExitReason::Loop(_)) => None,
+                ExitReason::Terminated { acc } => Some(acc),
+                _ => None,
             }
         })
         // Collect our single solution into a vec, we could probably `.sum()` instead, but you wouldn't know if
@@ -240,9 +624,8 @@ pub fn solve_part2_reset(input: &FnvHashMap<usize, Instruction>) -> isize {
             cpu.patch_opcode(*ip, patch_op);
             // Run the CPU
             match cpu.run_until_loop() {
-                Ok(_) => None,
-                Err(ExitReason::NoInstruction) => Some(cpu.acc),
-                Err(ExitReason::Loop(_)) => None,
+                ExitReason::Terminated { acc } => Some(acc),
+                _ => None,
             }
         })
         // Collect our single solution into a vec, we could probably `.sum()` instead, but you wouldn't know if
@@ -261,12 +644,516 @@ pub fn solve_part2_swap(input: &FnvHashMap<usize, Instruction>) -> isize {
             let mut cpu = CPU::new(input);
             cpu.swap_jmp_nop(*ip);
             match cpu.run_until_loop() {
-                Ok(_) => None,
-                Err(ExitReason::NoInstruction) => Some(cpu.acc),
-                Err(ExitReason::Loop(_)) => None,
+                ExitReason::Terminated { acc } => Some(acc),
+                _ => None,
             }
         })
         .collect();
 
     solutions[0]
 }
+
+#[aoc(day8, part2, cfg)]
+pub fn solve_part2_cfg(input: &FnvHashMap<usize, Instruction>) -> isize {
+    // Work out the fix from the control-flow graph instead of trying every flip, then run the program once
+    let fix = repair(input).expect("Error finding the instruction to repair");
+    let mut cpu = CPU::new(input);
+    cpu.patch_opcode(fix.addr, |val| {
+        fix.replacement.opcode().with_operands(Register::Acc, val)
+    });
+
+    match cpu.run_until_loop() {
+        ExitReason::Terminated { acc } => acc,
+        reason => panic!("The repaired program {}", reason),
+    }
+}
+
+/// Registers day 8 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = FnvHashMap<usize, Instruction>;
+
+    const DAY: u8 = 8;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, None, |input| solve_part1(input).to_string()),
+            Variant::new(Part::Two, None, |input| solve_part2(input).to_string()),
+            Variant::new(Part::Two, Some("reset"), |input| {
+                solve_part2_reset(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("swapcode"), |input| {
+                solve_part2_swap(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("cfg"), |input| {
+                solve_part2_cfg(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn part1_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::One, "5");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_variants::<Solution>(EXAMPLE, Part::Two, "8");
+    }
+
+    fn cpu(program: &str) -> CPU {
+        CPU::new(&input_generator(program).unwrap())
+    }
+
+    #[test]
+    fn parse_instructions() {
+        let program = "nop +0\nacc -3\njmp +2\nadd x +5\nset y -1\njz z +2\njnz acc -6\nhlt";
+        let mem = input_generator(program).unwrap();
+        assert_eq!(mem[&3], Instruction::ADD(Register::X, 5));
+        assert_eq!(mem[&6], Instruction::JNZ(Register::Acc, -6));
+        assert_eq!(mem[&7], Instruction::HLT);
+
+        // Instructions display the way they are written
+        let shown: Vec<String> = (0..mem.len()).map(|addr| mem[&addr].to_string()).collect();
+        assert_eq!(shown.join("\n"), program);
+
+        let err = input_generator("nop +0\nmul x +2").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnknownKeyword);
+        assert_eq!((err.line, err.text.as_str()), (2, "mul"));
+        let err = input_generator("add w +2").unwrap_err();
+        assert_eq!(
+            (err.kind, err.text.as_str()),
+            (ParseErrorKind::UnknownKeyword, "w")
+        );
+        for bad in &["jmp", "jmp 4", "add x", "hlt +1", "acc +1 +2"] {
+            let err = Instruction::from_str(bad).unwrap_err();
+            assert!(matches!(err.kind, ParseErrorKind::Malformed(_)), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn step_by_step() {
+        let mut cpu = cpu("add x +2\nacc +1\nadd x -1\njnz x -2\nhlt");
+        cpu.step().unwrap();
+        assert_eq!((cpu.ip(), cpu.register(Register::X)), (1, 2));
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        // x was 1 after the first pass, so the jump was taken
+        assert_eq!((cpu.ip(), cpu.acc()), (1, 1));
+
+        assert_eq!(cpu.run(100), ExitReason::Halted { ip: 4, acc: 2 });
+        assert_eq!(cpu.step(), Err(ExitReason::Halted { ip: 4, acc: 2 }));
+
+        cpu.reset();
+        assert_eq!((cpu.ip(), cpu.acc(), cpu.register(Register::X)), (0, 0, 0));
+        assert_eq!(cpu.run(3), ExitReason::StepLimit { steps: 3, ip: 3 });
+    }
+
+    #[test]
+    fn exit_reasons() {
+        assert_eq!(
+            cpu("acc +1\nnop +0").run(10),
+            ExitReason::Terminated { acc: 1 }
+        );
+        assert_eq!(
+            cpu("acc +1\njmp +1").run(10),
+            ExitReason::Terminated { acc: 1 }
+        );
+        assert_eq!(
+            cpu("acc +1\njmp +2").run(10),
+            ExitReason::OutOfBounds { ip: 1, target: 3 }
+        );
+        assert_eq!(
+            cpu("jz x -1").run(10),
+            ExitReason::OutOfBounds { ip: 0, target: -1 }
+        );
+        assert_eq!(cpu(EXAMPLE).run(1000), ExitReason::Loop { ip: 1, acc: 5 });
+
+        // Counting down revisits instructions without looping forever, only the puzzle's loop check stops early
+        let countdown = "set x +3\nadd x -1\njnz x -1\nhlt";
+        assert_eq!(
+            cpu(countdown).run(100),
+            ExitReason::Halted { ip: 3, acc: 0 }
+        );
+        assert_eq!(
+            cpu(countdown).run_until_loop(),
+            ExitReason::Loop { ip: 1, acc: 0 }
+        );
+        assert_eq!(
+            cpu("set x +1\nacc +1\njnz x -1").run(100),
+            ExitReason::Loop { ip: 1, acc: 1 }
+        );
+
+        // Overflowing a register or a jump target stops the program instead of panicking, and it stays stopped
+        let mut overflow = cpu("add x +9223372036854775807\nadd x +1");
+        assert_eq!(overflow.run(10), ExitReason::Overflow { ip: 1 });
+        assert_eq!(overflow.step(), Err(ExitReason::Overflow { ip: 1 }));
+        assert_eq!(overflow.register(Register::X), isize::MAX);
+        assert_eq!(
+            cpu("acc -9223372036854775807\nacc -2").run(10),
+            ExitReason::Overflow { ip: 1 }
+        );
+        assert_eq!(
+            cpu("nop +0\njmp +9223372036854775807").run(10),
+            ExitReason::Overflow { ip: 1 }
+        );
+    }
+
+    #[test]
+    fn debugger_points() {
+        let mut debugger = Debugger::new(&input_generator(EXAMPLE).unwrap());
+        debugger.add_breakpoint(Breakpoint::Opcode(Opcode::Jmp));
+        assert_eq!(
+            debugger.resume(100),
+            Stop::Breakpoint(Breakpoint::Opcode(Opcode::Jmp))
+        );
+        assert_eq!(debugger.cpu().ip(), 2);
+        // Resuming from a breakpoint runs the instruction under it before stopping again
+        debugger.resume(100);
+        assert_eq!(debugger.cpu().ip(), 7);
+
+        debugger.clear_points();
+        debugger.add_watchpoint(Watchpoint {
+            register: Register::Acc,
+            value: Some(5),
+        });
+        assert_eq!(
+            debugger.resume(100),
+            Stop::Watchpoint {
+                watchpoint: Watchpoint {
+                    register: Register::Acc,
+                    value: Some(5),
+                },
+                old: 2,
+                new: 5,
+            }
+        );
+        assert_eq!(
+            debugger.resume(100),
+            Stop::Exited(ExitReason::Loop { ip: 1, acc: 5 })
+        );
+
+        let executed: Vec<usize> = debugger.trace().iter().map(|entry| entry.ip).collect();
+        assert_eq!(executed, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(debugger.uncovered(), vec![5, 8]);
+        assert_eq!(
+            debugger.trace()[1].to_string(),
+            "    1: acc +1       acc=1 x=0 y=0 z=0"
+        );
+    }
+
+    #[test]
+    fn debugger_step_loop() {
+        // Stepping by hand spots the loop as soon as the program gets back to an instruction it has run
+        let mut debugger = Debugger::new(&input_generator(EXAMPLE).unwrap());
+        for _ in 0..7 {
+            assert_eq!(debugger.step(), Stop::Stepped);
+        }
+        assert_eq!(
+            debugger.step(),
+            Stop::Exited(ExitReason::Loop { ip: 1, acc: 5 })
+        );
+        assert_eq!(
+            debugger.resume(100),
+            Stop::Exited(ExitReason::Loop { ip: 1, acc: 5 })
+        );
+        assert_eq!(debugger.trace().len(), 7);
+    }
+
+    #[test]
+    fn debugger_reverse_step() {
+        let mut debugger = Debugger::new(&input_generator(EXAMPLE).unwrap());
+        for _ in 0..4 {
+            assert_eq!(debugger.step(), Stop::Stepped);
+        }
+        assert_eq!((debugger.cpu().ip(), debugger.cpu().acc()), (7, 2));
+
+        assert!(debugger.reverse_step());
+        assert!(debugger.reverse_step());
+        assert_eq!((debugger.cpu().ip(), debugger.cpu().acc()), (2, 1));
+        assert_eq!(debugger.trace().len(), 2);
+        assert_eq!(debugger.coverage()[6], 0);
+
+        // Fix the program the way part 2 does, then undo the fix
+        assert_eq!(
+            debugger.patch(7, Instruction::NOP(-4)),
+            Some(Instruction::JMP(-4))
+        );
+        assert_eq!(
+            debugger.resume(100),
+            Stop::Exited(ExitReason::Terminated { acc: 8 })
+        );
+        while debugger.cpu().ip() != 2 {
+            assert!(debugger.reverse_step());
+        }
+        assert!(debugger.reverse_step());
+        assert_eq!(debugger.cpu().instruction(7), Some(&Instruction::JMP(-4)));
+        assert!(debugger.cpu().patch_list.is_empty());
+
+        while debugger.reverse_step() {}
+        assert_eq!((debugger.cpu().ip(), debugger.cpu().acc()), (0, 0));
+        assert!(debugger.trace().is_empty());
+    }
+
+    #[test]
+    fn debugger_repl() {
+        let mut debugger = Debugger::new(&input_generator(EXAMPLE).unwrap());
+        let commands = "b 4\nc\nstep 2\nback\nwatch acc 99\npatch 7 nop -4\npatch  7   nop  -4\nc\ncov\nbogus\nq\nstep";
+        let mut output = Vec::new();
+        debugger::repl(&mut debugger, commands.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(
+            output,
+            "(dbg) breakpoint on address 4
+(dbg) breakpoint on address 4
+ip=4 (jmp -3) acc=5 x=0 y=0 z=0
+(dbg) program loops at 1 with acc 5
+ip=1 (acc +1) acc=5 x=0 y=0 z=0
+(dbg) undid 1
+ip=4 (jmp -3) acc=5 x=0 y=0 z=0
+(dbg) watching acc == 99
+(dbg) 7: jmp -4 -> nop -4
+(dbg) 7: nop -4 -> nop -4
+(dbg) program terminated normally with acc 13
+ip=9 (<end>) acc=13 x=0 y=0 z=0
+(dbg) 8/9 instructions executed, never executed: [5]
+(dbg) error: unknown command `bogus`, try `help`
+(dbg) 
+"
+        );
+    }
+
+    #[test]
+    fn control_flow_graph() {
+        let program = input_generator("nop +2\njnz x +2\njmp -2\nhlt\njmp +9").unwrap();
+        let cfg = ControlFlowGraph::new(&program);
+        assert_eq!(cfg.end(), 5);
+        assert_eq!(cfg.successors(0), &[1]);
+        assert_eq!(cfg.successors(1), &[2, 3]);
+        assert_eq!(cfg.successors(3), &[] as &[usize]);
+        assert_eq!(cfg.successors(4), &[] as &[usize]);
+        assert_eq!(cfg.predecessors(0), &[2]);
+        assert_eq!(cfg.predecessors(1), &[0]);
+        assert_eq!(
+            cfg.terminating(),
+            vec![false; 5]
+                .into_iter()
+                .chain(Some(true))
+                .collect::<Vec<_>>()
+        );
+
+        let cfg = ControlFlowGraph::new(&input_generator(EXAMPLE).unwrap());
+        assert_eq!(
+            cfg.distances_to_end(),
+            vec![
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(1),
+                Some(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn repair_example() {
+        let fix = repair(&input_generator(EXAMPLE).unwrap()).unwrap();
+        assert_eq!(
+            fix,
+            Repair {
+                addr: 7,
+                original: Instruction::JMP(-4),
+                replacement: Instruction::NOP(-4),
+                reached_after: 4,
+                target: 8,
+                steps_to_end: 1,
+            }
+        );
+        assert_eq!(
+            fix.to_string(),
+            "change `jmp -4` at 7 to `nop -4`: the program reaches 7 after 4 instruction(s), and from there the \
+             change sends it to 8, which gets to the end of the program in 1 instruction(s)"
+        );
+
+        // A nop turned into a jump over the loop
+        let fix = repair(&input_generator("nop +3\nacc +1\njmp -1\nacc +2").unwrap()).unwrap();
+        assert_eq!((fix.addr, fix.target, fix.steps_to_end), (0, 3, 1));
+    }
+
+    #[test]
+    fn repair_errors() {
+        assert_eq!(
+            repair(&input_generator("acc +1\nnop +0").unwrap()),
+            Err(RepairError::AlreadyTerminates)
+        );
+        assert_eq!(
+            repair(&input_generator("nop +0\njmp -1\nhlt").unwrap()),
+            Err(RepairError::NoRepair)
+        );
+        assert_eq!(
+            repair(&input_generator("jz x +2\njmp -1").unwrap()),
+            Err(RepairError::ConditionalJump(0))
+        );
+        // The flip at 0 only looks like it reaches the end through the `jnz`, which x never lets it take
+        assert_eq!(
+            repair(&input_generator("nop +2\njmp +0\njnz x +2\njmp -3").unwrap()),
+            Err(RepairError::ConditionalJump(2))
+        );
+    }
+
+    #[test]
+    fn assembler() {
+        let source = "# count x down from 3, adding 2 to acc each time
+    set x +3
+loop:   acc +2      ; body
+        add x -1
+        jnz x loop
+done:
+        hlt";
+        let program = assemble(source).unwrap();
+        assert_eq!(program.len(), 5);
+        assert_eq!(program[&3], Instruction::JNZ(Register::X, -2));
+        assert_eq!(
+            CPU::new(&program).run(100),
+            ExitReason::Halted { ip: 4, acc: 6 }
+        );
+
+        // Plain puzzle input assembles to the same program as the generator gives
+        let example = input_generator(EXAMPLE).unwrap();
+        assert_eq!(assemble(EXAMPLE).unwrap(), example);
+        assert_eq!(assemble(&to_assembly(&example)).unwrap(), example);
+        assert_eq!(assemble(&to_assembly(&program)).unwrap(), program);
+    }
+
+    #[test]
+    fn assembler_errors() {
+        let err = assemble("start:\n  jmp nowhere").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnknownKeyword);
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 7, "nowhere"));
+
+        let err = assemble("a: nop +0\na: nop +0").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::Malformed("each label to be defined once")
+        );
+        assert_eq!(err.line, 2);
+
+        // Labels only stand in for jump offsets
+        let err = assemble("a: acc a").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Malformed(_)));
+        let err = assemble("2x: nop +0").unwrap_err();
+        assert_eq!(err.text, "2x");
+    }
+
+    #[test]
+    fn disassembler() {
+        let program = assemble("nop +0\njmp +2\njz y -5\nacc +1").unwrap();
+        assert_eq!(
+            disassemble(&program),
+            "0: nop +0
+1: jmp +2               ; -> 3
+2: jz y -5              ; -> out of bounds
+3: acc +1
+"
+        );
+        let program = assemble("jmp +1").unwrap();
+        assert_eq!(disassemble(&program), "0: jmp +1               ; -> end\n");
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mut program = input_generator(EXAMPLE).unwrap();
+        let bytes = encode(&program);
+        assert_eq!(&bytes[..5], b"HHC8\x01");
+        // One byte for each opcode and small value, `acc -99` needs a second byte for its value
+        assert_eq!(bytes.len(), 6 + 2 * program.len() + 1);
+        assert_eq!(decode(&bytes).unwrap(), program);
+
+        // Every opcode, register and extreme value survives the trip
+        let mut addr = program.len();
+        for opcode in Opcode::ALL.iter() {
+            for reg in Register::ALL.iter() {
+                for value in &[0, 1, -1, 63, -64, 64, isize::MAX, isize::MIN] {
+                    let instr = opcode.with_operands(*reg, *value);
+                    program.insert(addr, instr);
+                    addr += 1;
+                }
+            }
+        }
+        assert_eq!(decode(&encode(&program)).unwrap(), program);
+    }
+
+    #[test]
+    fn binary_errors() {
+        let bytes = encode(&input_generator(EXAMPLE).unwrap());
+        assert_eq!(decode(b"HH"), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(decode(b"nope!"), Err(DecodeError::BadMagic));
+        assert_eq!(
+            decode(b"HHC8\x02\x00"),
+            Err(DecodeError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert_eq!(
+            decode(&extra),
+            Err(DecodeError::TrailingBytes {
+                offset: bytes.len()
+            })
+        );
+
+        // `nop` can't name a register, and there is no opcode 15
+        let mut bad = bytes.clone();
+        bad[6] = 0x10;
+        assert_eq!(
+            decode(&bad),
+            Err(DecodeError::BadInstruction {
+                offset: 6,
+                byte: 0x10
+            })
+        );
+        bad[6] = 0x0f;
+        assert_eq!(
+            decode(&bad),
+            Err(DecodeError::BadInstruction {
+                offset: 6,
+                byte: 0x0f
+            })
+        );
+
+        let huge = b"HHC8\x01\x01\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\x7f";
+        assert_eq!(decode(huge), Err(DecodeError::Overflow { offset: 7 }));
+    }
+}
@@ -1,21 +1,67 @@
 use std::collections::VecDeque;
 
-use factorial::Factorial;
-use fnv::{FnvHashMap, FnvHashSet};
+use fnv::FnvHashSet;
 
-static WINDOW_SIZE: usize = 25;
-static mut PART_1: u64 = 0;
+use crate::solver::{Part, Solver, Variant};
+use crate::{ParseError, ParseErrorKind};
 
-pub fn calc_total_combinations(n: u128, r: u128) -> usize {
-    (n.factorial() / (r.factorial() * (n - r).factorial())) as usize
+pub mod stream;
+
+pub use stream::{
+    invalid_numbers, invalid_numbers_in, read_numbers, Invalid, StreamError, Validator,
+};
+
+/// Size of the preamble, and the window of previous numbers each number is checked against, used by the puzzle
+pub const WINDOW_SIZE: usize = 25;
+
+/// Number of distinct pairs that can be made from `n` values
+pub fn pair_count(n: usize) -> usize {
+    n * n.saturating_sub(1) / 2
+}
+
+/// A stream of numbers encrypted with the eXchange-Masking Addition System
+#[derive(Debug, Clone)]
+pub struct XmasCipher {
+    data: Vec<u64>,
+}
+
+impl XmasCipher {
+    pub fn new(data: Vec<u64>) -> Self {
+        XmasCipher { data }
+    }
+
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+
+    /// Find the first number after the preamble that isn't the sum of two of the `window` numbers before it. Returns
+    /// `None` if every number is valid.
+    pub fn first_invalid(&self, window: usize) -> Option<u64> {
+        first_invalid_streaming(&self.data, window)
+    }
+
+    /// Every number after the preamble that isn't the sum of two of the `window` numbers before it
+    pub fn invalid_numbers(&self, window: usize) -> Vec<Invalid> {
+        invalid_numbers(self.data.iter().copied(), window).collect()
+    }
+
+    /// Find a contiguous range of at least two numbers that adds up to `target` and return the sum of the smallest and
+    /// largest number in it. Returns `None` if there is no such range.
+    pub fn encryption_weakness(&self, target: u64) -> Option<u64> {
+        encryption_weakness_inchworm(&self.data, target)
+    }
 }
 
 #[aoc_generator(day9)]
-pub fn input_generator(input: &str) -> Vec<u64> {
+pub fn input_generator(input: &str) -> Result<XmasCipher, ParseError> {
     input
         .lines()
-        .map(|line| line.parse::<u64>().expect("Error while parsing input"))
-        .collect()
+        .map(|line| {
+            line.parse::<u64>()
+                .map_err(|_| ParseError::new(9, input, line, ParseErrorKind::InvalidNumber))
+        })
+        .collect::<Result<Vec<u64>, _>>()
+        .map(XmasCipher::new)
 }
 
 pub fn populate_vecdeque(v: &mut VecDeque<u64>, window: &[u64]) {
@@ -27,30 +73,22 @@ pub fn populate_vecdeque(v: &mut VecDeque<u64>, window: &[u64]) {
     }
 }
 
-#[aoc(day9, part1, vecdeque)]
-pub fn solve_part1_vecdeque(input: &[u64]) -> u64 {
-    // Pre-calculate the size of our VecDeque
-    let total_combinations = calc_total_combinations(WINDOW_SIZE as u128, 2);
-    // Initialize our VecDeque
-    let mut values: VecDeque<u64> = VecDeque::with_capacity(total_combinations);
+pub fn first_invalid_vecdeque(input: &[u64], window_size: usize) -> Option<u64> {
+    // Pre-calculate the size of our VecDeque and initialize it
+    let mut values: VecDeque<u64> = VecDeque::with_capacity(pair_count(window_size));
 
-    // Build our window into the input
-    let mut index = 0;
-
-    loop {
-        let window = &input[index..index + WINDOW_SIZE];
+    // Slide our window over the input
+    for index in 0..input.len().saturating_sub(window_size) {
+        let window = &input[index..index + window_size];
 
         populate_vecdeque(&mut values, window);
 
-        if !values.contains(&input[WINDOW_SIZE + index]) {
-            unsafe {
-                PART_1 = input[WINDOW_SIZE + index];
-            }
-            return input[WINDOW_SIZE + index];
+        if !values.contains(&input[window_size + index]) {
+            return Some(input[window_size + index]);
         }
-
-        index += 1;
     }
+
+    None
 }
 
 pub fn populate_vec(v: &mut Vec<u64>, window: &[u64]) {
@@ -62,139 +100,372 @@ pub fn populate_vec(v: &mut Vec<u64>, window: &[u64]) {
     }
 }
 
-#[aoc(day9, part1, Vec)]
-pub fn solve_part1_vec(input: &[u64]) -> u64 {
-    // Pre-calculate the size of our VecDeque
-    let total_combinations = calc_total_combinations(WINDOW_SIZE as u128, 2);
-    // Initialize our VecDeque
-    let mut values: Vec<u64> = Vec::with_capacity(total_combinations);
-
-    // Build our window into the input
-    let mut index = 0;
+pub fn first_invalid_vec(input: &[u64], window_size: usize) -> Option<u64> {
+    // Pre-calculate the size of our Vec and initialize it
+    let mut values: Vec<u64> = Vec::with_capacity(pair_count(window_size));
 
-    loop {
-        let window = &input[index..index + WINDOW_SIZE];
+    // Slide our window over the input
+    for index in 0..input.len().saturating_sub(window_size) {
+        let window = &input[index..index + window_size];
 
         populate_vec(&mut values, window);
 
-        if !values.contains(&input[WINDOW_SIZE + index]) {
-            return input[WINDOW_SIZE + index];
+        if !values.contains(&input[window_size + index]) {
+            return Some(input[window_size + index]);
         }
-
-        index += 1;
     }
+
+    None
 }
 
-#[aoc(day9, part1, fnvhashset)]
-pub fn solve_part1_fnvhashset(input: &[u64]) -> u64 {
-    let mut values: FnvHashSet<(u64, u64)> = FnvHashSet::default();
+pub fn first_invalid_fnvhashset(input: &[u64], window_size: usize) -> Option<u64> {
+    if input.len() <= window_size {
+        return None;
+    }
 
-    // Build our window into the input
-    let mut index = 0;
+    // Every pair sum in the window, alongside the index of the earlier number in the pair. Using the index rather than
+    // the value keeps repeated numbers in the window apart.
+    let mut values: FnvHashSet<(u64, usize)> = FnvHashSet::default();
 
-    // Initialize our Set  by inserting all the (Value, Parent) pairs
-    let window = &input[index..index + WINDOW_SIZE];
-    for x in 0..window.len() {
-        for y in x + 1..window.len() {
-            match values.insert((window[x] + window[y], window[x])) {
-                true => {}
-                false => panic!("Duplicate key"),
-            }
+    // Initialize our Set by inserting all the (Value, Parent) pairs
+    for x in 0..window_size {
+        for y in x + 1..window_size {
+            values.insert((input[x] + input[y], x));
         }
     }
 
-    let mut keys: Vec<(u64, u64)> = Vec::with_capacity(WINDOW_SIZE);
-    loop {
+    let mut keys: Vec<(u64, usize)> = Vec::with_capacity(window_size);
+    for index in 0..input.len() - window_size {
         // If we can't find a match in our values Set, then that is the value we need to return
         if values
             .iter()
-            .find(|(val, _)| *val == input[WINDOW_SIZE + index])
+            .find(|(val, _)| *val == input[window_size + index])
             .is_none()
         {
-            return input[WINDOW_SIZE + index];
+            return Some(input[window_size + index]);
         }
 
         // Purge the set of values related to the beginning of the window before we move it
-        &keys.clear();
-        for (key, val) in &values {
-            if *val == input[index] {
+        keys.clear();
+        for (key, parent) in &values {
+            if *parent == index {
                 // Record all the keys that are related to the window start value
-                &keys.push((*key, *val));
+                keys.push((*key, *parent));
             }
         }
         for key in &keys {
             // Remove all the keys that are related to the window start value
-            values.remove(&key);
+            values.remove(key);
         }
 
-        // Populate the set with new values related to the next value outside the window
-        for idx in 0..WINDOW_SIZE - 1 {
-            values.insert((
-                input[index + idx + 1] + input[index + WINDOW_SIZE],
-                input[index + WINDOW_SIZE],
-            ));
+        // Populate the set with new values related to the next value outside the window. The parent is the earlier
+        // number so the pair is purged as soon as it leaves the window.
+        for idx in index + 1..index + window_size {
+            values.insert((input[idx] + input[index + window_size], idx));
         }
-
-        index += 1;
     }
+
+    None
 }
 
-#[aoc(day9, part2, naive)]
-pub fn solve_part2_naive(input: &[u64]) -> u64 {
-    let target = unsafe { PART_1 };
-    // Start the loop from 0
-    let mut start_index = 0;
+pub fn first_invalid_streaming(input: &[u64], window_size: usize) -> Option<u64> {
+    invalid_numbers(input.iter().copied(), window_size)
+        .next()
+        .map(|invalid| invalid.value)
+}
+
+pub fn encryption_weakness_naive(input: &[u64], target: u64) -> Option<u64> {
     // This loop essentially will go through `input` and just sum from `start_index` until the end
     // if `sum_total` is ever > target, we know that can't be the right window and move on to the
     // next starting index
-    loop {
+    for start_index in 0..input.len() {
         // Start our sum from zero for each index
         let mut sum_total = 0;
         for x in start_index..input.len() {
             // Sum each input until we find the target, or overshoot it
             sum_total += input[x];
 
-            // If we find the target, use iterators to get our real return value
-            if sum_total == target {
-                return input[start_index..x].iter().min().unwrap()
-                    + input[start_index..x].iter().max().unwrap();
+            // If we find the target, use iterators to get our real return value. The range needs at least two numbers
+            // in it, otherwise the target itself would always match.
+            if sum_total == target && x > start_index {
+                return Some(
+                    input[start_index..=x].iter().min().unwrap()
+                        + input[start_index..=x].iter().max().unwrap(),
+                );
             }
             // If we overshoot our target, just move on to the next starting index
             else if sum_total > target {
                 break;
             }
         }
-        start_index += 1;
     }
+
+    None
 }
 
-#[aoc(day9, part2, inchworm)]
-pub fn solve_part2_inchworm(input: &[u64]) -> u64 {
-    let target = unsafe { PART_1 };
+pub fn encryption_weakness_inchworm(input: &[u64], target: u64) -> Option<u64> {
     let mut start_index = 0;
-    let mut end_index = 0;
     let mut sum_total = 0;
 
-    loop {
+    for end_index in 0..input.len() {
         // Sum contiguous values until we overshoot or find it
-        while sum_total < target {
-            sum_total += input[end_index];
-            end_index += 1;
-        }
+        sum_total += input[end_index];
 
-        // If we found it, just return our answer with some iterator magic
-        if sum_total == target {
-            return input[start_index..end_index].iter().min().unwrap()
-                + input[start_index..end_index].iter().max().unwrap();
-        }
         // If we overshot, we want to subtract the numbers from the start of the list until we are back under and
         // then we try again, this way we will inch our way towards whatever is the actual range. We can leverage
         // this because the numbers have to be contiguous
-        else if sum_total > target {
-            while sum_total > target {
-                sum_total -= input[start_index];
-                start_index += 1;
+        while sum_total > target {
+            sum_total -= input[start_index];
+            start_index += 1;
+        }
+
+        // If we found it, just return our answer with some iterator magic. The range needs at least two numbers in
+        // it, otherwise the target itself would always match.
+        if sum_total == target && end_index > start_index {
+            return Some(
+                input[start_index..=end_index].iter().min().unwrap()
+                    + input[start_index..=end_index].iter().max().unwrap(),
+            );
+        }
+    }
+
+    None
+}
+
+#[aoc(day9, part1, vecdeque)]
+pub fn solve_part1_vecdeque(input: &XmasCipher) -> u64 {
+    first_invalid_vecdeque(input.data(), WINDOW_SIZE).expect("Every number is valid")
+}
+
+#[aoc(day9, part1, Vec)]
+pub fn solve_part1_vec(input: &XmasCipher) -> u64 {
+    first_invalid_vec(input.data(), WINDOW_SIZE).expect("Every number is valid")
+}
+
+#[aoc(day9, part1, fnvhashset)]
+pub fn solve_part1_fnvhashset(input: &XmasCipher) -> u64 {
+    first_invalid_fnvhashset(input.data(), WINDOW_SIZE).expect("Every number is valid")
+}
+
+#[aoc(day9, part1, streaming)]
+pub fn solve_part1_streaming(input: &XmasCipher) -> u64 {
+    first_invalid_streaming(input.data(), WINDOW_SIZE).expect("Every number is valid")
+}
+
+#[aoc(day9, part2, naive)]
+pub fn solve_part2_naive(input: &XmasCipher) -> u64 {
+    let target = input
+        .first_invalid(WINDOW_SIZE)
+        .expect("Every number is valid");
+    encryption_weakness_naive(input.data(), target).expect("No range adds up to the invalid number")
+}
+
+#[aoc(day9, part2, inchworm)]
+pub fn solve_part2_inchworm(input: &XmasCipher) -> u64 {
+    let target = input
+        .first_invalid(WINDOW_SIZE)
+        .expect("Every number is valid");
+    encryption_weakness_inchworm(input.data(), target)
+        .expect("No range adds up to the invalid number")
+}
+
+/// Registers day 9 with the crate's own runner
+pub struct Solution;
+
+impl Solver for Solution {
+    type Input = XmasCipher;
+
+    const DAY: u8 = 9;
+
+    fn generate(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, Some("vecdeque"), |input| {
+                solve_part1_vecdeque(input).to_string()
+            }),
+            Variant::new(Part::One, Some("Vec"), |input| {
+                solve_part1_vec(input).to_string()
+            }),
+            Variant::new(Part::One, Some("fnvhashset"), |input| {
+                solve_part1_fnvhashset(input).to_string()
+            }),
+            Variant::new(Part::One, Some("streaming"), |input| {
+                solve_part1_streaming(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("naive"), |input| {
+                solve_part2_naive(input).to_string()
+            }),
+            Variant::new(Part::Two, Some("inchworm"), |input| {
+                solve_part2_inchworm(input).to_string()
+            }),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::assert_variants;
+
+    const EXAMPLE: [u64; 20] = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ];
+
+    #[test]
+    fn example_with_short_preamble() {
+        let cipher = XmasCipher::new(EXAMPLE.to_vec());
+
+        assert_eq!(cipher.first_invalid(5), Some(127));
+        assert_eq!(cipher.encryption_weakness(127), Some(62));
+    }
+
+    #[test]
+    fn variants_agree_on_example() {
+        for first_invalid in [
+            first_invalid_vecdeque,
+            first_invalid_vec,
+            first_invalid_fnvhashset,
+            first_invalid_streaming,
+        ]
+        .iter()
+        {
+            assert_eq!(first_invalid(&EXAMPLE, 5), Some(127));
+        }
+        for encryption_weakness in [encryption_weakness_naive, encryption_weakness_inchworm].iter()
+        {
+            assert_eq!(encryption_weakness(&EXAMPLE, 127), Some(62));
+        }
+    }
+
+    #[test]
+    fn all_valid() {
+        let cipher = XmasCipher::new(EXAMPLE[..14].to_vec());
+        assert_eq!(cipher.first_invalid(5), None);
+        assert_eq!(cipher.first_invalid(20), None);
+    }
+
+    #[test]
+    fn weakness_needs_two_numbers() {
+        // 127 is in the list on its own, but that doesn't count as a range
+        assert_eq!(encryption_weakness_inchworm(&[1, 127, 2], 127), None);
+        assert_eq!(encryption_weakness_naive(&[1, 127, 2], 127), None);
+        assert_eq!(encryption_weakness_inchworm(&[1, 127, 2], 129), Some(129));
+    }
+
+    #[test]
+    fn repeated_numbers_in_window() {
+        // 5 + 5 makes 10 while both are in the window, but not once the first one has left it
+        let data = [5, 1, 5, 6, 10];
+        assert_eq!(first_invalid_fnvhashset(&data, 3), Some(10));
+        assert_eq!(first_invalid_vec(&data, 3), Some(10));
+        assert_eq!(first_invalid_streaming(&data, 3), Some(10));
+
+        // Two pairs make 6 here, so it stays valid after the first pair leaves the window
+        let data = [1, 5, 2, 4, 6, 6];
+        assert_eq!(first_invalid_streaming(&data, 4), None);
+        let data = [3, 3, 3, 6, 6, 7];
+        assert_eq!(
+            invalid_numbers(data.iter().copied(), 3).collect::<Vec<_>>(),
+            vec![Invalid {
+                position: 5,
+                value: 7
+            }]
+        );
+    }
+
+    #[test]
+    fn every_invalid_number() {
+        let cipher = XmasCipher::new(EXAMPLE.to_vec());
+        let invalid = cipher.invalid_numbers(5);
+        assert_eq!(
+            invalid,
+            vec![Invalid {
+                position: 14,
+                value: 127
+            }]
+        );
+
+        // Numbers after an invalid one are still checked, with the invalid one in their window
+        let data = [1, 2, 3, 100, 5, 105, 1];
+        let invalid: Vec<usize> = invalid_numbers(data.iter().copied(), 2)
+            .map(|invalid| invalid.position)
+            .collect();
+        assert_eq!(invalid, vec![3, 4, 6]);
+
+        // Sums that overflow can't match anything, and an empty preamble leaves nothing to add up
+        let data = [u64::MAX, u64::MAX, 1, u64::MAX];
+        assert_eq!(invalid_numbers(data.iter().copied(), 2).count(), 2);
+        assert_eq!(invalid_numbers(data.iter().copied(), 0).count(), 4);
+    }
+
+    #[test]
+    fn validator() {
+        let mut validator = Validator::new(2);
+        assert_eq!(validator.push(1), None);
+        assert_eq!(validator.push(2), None);
+        assert!(validator.is_valid(3));
+        assert!(!validator.is_valid(2));
+        assert_eq!(
+            validator.push(4),
+            Some(Invalid {
+                position: 2,
+                value: 4
+            })
+        );
+        assert_eq!(validator.position(), 3);
+        assert!(validator.is_valid(6));
+        assert!(!validator.is_valid(3));
+    }
+
+    #[test]
+    fn read_stream() {
+        let text = EXAMPLE
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        let invalid = invalid_numbers_in(format!("{}\n\n", text).as_bytes(), 5)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            invalid,
+            vec![Invalid {
+                position: 14,
+                value: 127
+            }]
+        );
+
+        let mut numbers = read_numbers("1\n\n  2  \nthree\n4".as_bytes());
+        assert_eq!(numbers.next().unwrap().unwrap(), 1);
+        assert_eq!(numbers.next().unwrap().unwrap(), 2);
+        match numbers.next() {
+            Some(Err(StreamError::InvalidNumber { line, text })) => {
+                assert_eq!((line, text.as_str()), (4, "three"))
             }
+            other => panic!("Expected an invalid number, got {:?}", other),
         }
+
+        // Validation stops at the first unreadable number
+        let mut invalid = invalid_numbers_in("1\n2\n9\nx\n9".as_bytes(), 2);
+        assert_eq!(invalid.next().unwrap().unwrap().position, 2);
+        assert!(invalid.next().unwrap().is_err());
+        assert!(invalid.next().is_none());
+    }
+
+    #[test]
+    fn full_window() {
+        // The solvers use the puzzle's window of 25 numbers, so build an input around it. 100 can't be made from any
+        // pair in the window before it, and 9 through 16 add up to 100.
+        let input = (1..=26)
+            .chain(vec![49, 100])
+            .map(|n| n.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        assert_variants::<Solution>(&input, Part::One, "100");
+        assert_variants::<Solution>(&input, Part::Two, "25");
     }
 }
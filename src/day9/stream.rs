@@ -0,0 +1,244 @@
+use fnv::FnvHashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// A number that isn't the sum of two of the numbers in the window before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Invalid {
+    /// Where the number is in the stream, counting from 0
+    pub position: usize,
+    pub value: u64,
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.value)
+    }
+}
+
+/// Checks numbers one at a time as they arrive. Only the last `preamble` numbers are kept, along with how many pairs of
+/// them make each sum, so memory stays the same however long the stream is and each number costs `preamble` updates
+/// rather than rebuilding every pair sum.
+///
+/// A pair is two different positions in the window, so repeated numbers can be added to each other, and a sum made by
+/// several pairs stays valid until the last of them leaves the window.
+#[derive(Debug, Clone)]
+pub struct Validator {
+    preamble: usize,
+    window: VecDeque<u64>,
+    /// How many pairs in the window add up to each sum. Sums that overflow a `u64` can't match a number, so they are
+    /// never counted.
+    sums: FnvHashMap<u64, usize>,
+    position: usize,
+}
+
+impl Validator {
+    pub fn new(preamble: usize) -> Self {
+        Validator {
+            preamble,
+            window: VecDeque::with_capacity(preamble + 1),
+            sums: FnvHashMap::default(),
+            position: 0,
+        }
+    }
+
+    pub fn preamble(&self) -> usize {
+        self.preamble
+    }
+
+    /// How many numbers have been pushed so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns `true` if two of the numbers in the current window add up to `value`
+    pub fn is_valid(&self, value: u64) -> bool {
+        self.sums.contains_key(&value)
+    }
+
+    /// Check the next number in the stream and slide the window over it. Numbers in the preamble are always valid.
+    pub fn push(&mut self, value: u64) -> Option<Invalid> {
+        let invalid = if self.position >= self.preamble && !self.is_valid(value) {
+            Some(Invalid {
+                position: self.position,
+                value,
+            })
+        } else {
+            None
+        };
+        self.position += 1;
+
+        if self.preamble == 0 {
+            return invalid;
+        }
+
+        // Invalid numbers still count as one of the previous numbers for the ones that follow
+        if self.window.len() == self.preamble {
+            let oldest = self.window.pop_front().unwrap();
+            for other in &self.window {
+                if let Some(sum) = oldest.checked_add(*other) {
+                    let count = self.sums.get_mut(&sum).expect("Every pair sum is counted");
+                    *count -= 1;
+                    if *count == 0 {
+                        self.sums.remove(&sum);
+                    }
+                }
+            }
+        }
+        for other in &self.window {
+            if let Some(sum) = value.checked_add(*other) {
+                *self.sums.entry(sum).or_insert(0) += 1;
+            }
+        }
+        self.window.push_back(value);
+
+        invalid
+    }
+}
+
+/// Every invalid number in `values`, checked lazily as the iterator is consumed
+pub fn invalid_numbers<I>(values: I, preamble: usize) -> InvalidNumbers<I::IntoIter>
+where
+    I: IntoIterator<Item = u64>,
+{
+    InvalidNumbers {
+        values: values.into_iter(),
+        validator: Validator::new(preamble),
+    }
+}
+
+/// Iterator returned by `invalid_numbers`
+#[derive(Debug, Clone)]
+pub struct InvalidNumbers<I> {
+    values: I,
+    validator: Validator,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for InvalidNumbers<I> {
+    type Item = Invalid;
+
+    fn next(&mut self) -> Option<Invalid> {
+        let validator = &mut self.validator;
+        self.values.find_map(|value| validator.push(value))
+    }
+}
+
+/// Why a number couldn't be read from a stream
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    /// The line at `line`, counting from 1, isn't a number
+    InvalidNumber {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "unable to read numbers: {}", err),
+            Self::InvalidNumber { line, text } => {
+                write!(f, "line {}: `{}` is not a valid number", line, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::InvalidNumber { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        StreamError::Io(err)
+    }
+}
+
+/// Numbers from `reader`, one per line. Lines are read into the same buffer one at a time, and blank lines are skipped.
+pub fn read_numbers<R: Read>(reader: R) -> Numbers<R> {
+    Numbers {
+        reader: BufReader::new(reader),
+        line: String::new(),
+        line_number: 0,
+    }
+}
+
+/// Iterator returned by `read_numbers`
+#[derive(Debug)]
+pub struct Numbers<R> {
+    reader: BufReader<R>,
+    line: String,
+    line_number: usize,
+}
+
+impl<R: Read> Iterator for Numbers<R> {
+    type Item = Result<u64, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(err) => return Some(Err(err.into())),
+            }
+
+            let text = self.line.trim();
+            if !text.is_empty() {
+                return Some(text.parse().map_err(|_| StreamError::InvalidNumber {
+                    line: self.line_number,
+                    text: text.to_string(),
+                }));
+            }
+        }
+    }
+}
+
+/// Every invalid number in the stream read from `reader`. The stream stops at the first number that can't be read.
+pub fn invalid_numbers_in<R: Read>(reader: R, preamble: usize) -> InvalidNumbersIn<R> {
+    InvalidNumbersIn {
+        numbers: read_numbers(reader),
+        validator: Validator::new(preamble),
+        failed: false,
+    }
+}
+
+/// Iterator returned by `invalid_numbers_in`
+#[derive(Debug)]
+pub struct InvalidNumbersIn<R> {
+    numbers: Numbers<R>,
+    validator: Validator,
+    failed: bool,
+}
+
+impl<R: Read> Iterator for InvalidNumbersIn<R> {
+    type Item = Result<Invalid, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        for value in &mut self.numbers {
+            match value {
+                Ok(value) => {
+                    if let Some(invalid) = self.validator.push(value) {
+                        return Some(Ok(invalid));
+                    }
+                }
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+/// The different ways a puzzle input can fail to parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The offending text was expected to be a number but isn't one (or doesn't fit the target type)
+    InvalidNumber,
+    /// A character that has no meaning in this day's input
+    UnexpectedChar(char),
+    /// An opcode, field name or other keyword that this day doesn't know about
+    UnknownKeyword,
+    /// The text doesn't follow the expected layout, which is described by the contained `&str`
+    Malformed(&'static str),
+    /// A required piece of the input, described by the contained `&str`, could not be found
+    Missing(&'static str),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidNumber => write!(f, "invalid number"),
+            Self::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            Self::UnknownKeyword => write!(f, "unknown keyword"),
+            Self::Malformed(expected) => write!(f, "malformed input, expected {}", expected),
+            Self::Missing(what) => write!(f, "missing {}", what),
+        }
+    }
+}
+
+/// Error returned by every day's `input_generator` when the provided puzzle input can't be understood. It carries
+/// enough location information to point the user straight at the problem in their input file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Day whose input was being parsed
+    pub day: u8,
+    /// Line of the offending text, starting at 1
+    pub line: usize,
+    /// Column (in characters) of the offending text, starting at 1
+    pub column: usize,
+    /// The offending text itself
+    pub text: String,
+    /// What went wrong
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// Build a new `ParseError` for `day`. `fragment` is the offending text and should be a slice of `context`, the
+    /// string that was being parsed, so the line and column can be worked out from where it sits within `context`.
+    /// If `fragment` doesn't come from `context` the error points at the start of `context`.
+    pub fn new(day: u8, context: &str, fragment: &str, kind: ParseErrorKind) -> Self {
+        let (line, column) = locate(context, fragment).unwrap_or((1, 1));
+
+        ParseError {
+            day,
+            line,
+            column,
+            text: fragment.to_string(),
+            kind,
+        }
+    }
+
+    /// Re-anchor an error that was produced while parsing `inner` so that its position is relative to `outer`. This
+    /// lets per-line or per-group parsers report positions without knowing about the rest of the input, as long as
+    /// `inner` is a slice of `outer`.
+    pub fn within(mut self, outer: &str, inner: &str) -> Self {
+        if let Some((line, column)) = locate(outer, inner) {
+            if self.line == 1 {
+                self.column += column - 1;
+            }
+            self.line += line - 1;
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} input, line {}, column {}: {} at {:?}",
+            self.day, self.line, self.column, self.kind, self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Find the 1-based line and column of `fragment` within `context`, returns `None` if `fragment` is not a slice of
+/// `context`
+fn locate(context: &str, fragment: &str) -> Option<(usize, usize)> {
+    let start = context.as_ptr() as usize;
+    let offset = (fragment.as_ptr() as usize).checked_sub(start)?;
+
+    if offset + fragment.len() > context.len() || !context.is_char_boundary(offset) {
+        return None;
+    }
+
+    let before = &context[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+
+    Some((line, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_fragment() {
+        let input = "abc\ndéf ghi\njkl";
+        let err = ParseError::new(1, input, &input[9..12], ParseErrorKind::UnknownKeyword);
+
+        assert_eq!((err.line, err.column), (2, 5));
+        assert_eq!(err.text, "ghi");
+    }
+
+    #[test]
+    fn unrelated_fragment_points_at_start() {
+        let err = ParseError::new(1, "abc", "xyz", ParseErrorKind::InvalidNumber);
+
+        assert_eq!((err.line, err.column), (1, 1));
+    }
+
+    #[test]
+    fn within_rebases_position() {
+        let input = "first\nsecond line\nthird";
+        let line = input.lines().nth(1).unwrap();
+        let err = ParseError::new(1, line, &line[7..], ParseErrorKind::InvalidNumber);
+        assert_eq!((err.line, err.column), (1, 8));
+
+        let err = err.within(input, line);
+        assert_eq!((err.line, err.column), (2, 8));
+    }
+
+    #[test]
+    fn display_includes_location() {
+        let err = ParseError::new(8, "nop +0\nxyz +3", "xyz", ParseErrorKind::UnknownKeyword);
+
+        assert_eq!(
+            err.to_string(),
+            "day 8 input, line 1, column 1: unknown keyword at \"xyz\""
+        );
+    }
+}
@@ -7,6 +7,8 @@ extern crate crypto;
 #[macro_use]
 extern crate lazy_static;
 
+pub mod automaton;
+pub mod cursor;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -17,7 +19,14 @@ pub mod day15;
 pub mod day16;
 pub mod day17;
 pub mod day18;
+pub mod day19;
 pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
 pub mod day3;
 pub mod day4;
 pub mod day5;
@@ -25,5 +34,10 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod error;
+pub mod render;
+pub mod solver;
+
+pub use error::{ParseError, ParseErrorKind};
 
 aoc_lib! { year = 2020 }
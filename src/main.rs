@@ -1,7 +1,477 @@
 extern crate advent_of_code_2020;
-extern crate aoc_runner;
-extern crate aoc_runner_derive;
+extern crate clap;
 
-use aoc_runner_derive::aoc_main;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
 
-aoc_main! { lib = advent_of_code_2020 }
+use advent_of_code_2020::render::{self, Animation};
+use advent_of_code_2020::solver::{self, Day, Part};
+use advent_of_code_2020::{automaton, day11, day12, day17, day8, day9};
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+/// Name used on the command line for variants that don't have one
+const DEFAULT_VARIANT: &str = "default";
+
+fn main() {
+    let matches = App::new("Advent of Code 2020")
+        .about("Runs the Advent of Code 2020 solutions and times them")
+        .arg(
+            Arg::with_name("day")
+                .short("d")
+                .long("day")
+                .takes_value(true)
+                .help("Day to run, every day is run if this is omitted"),
+        )
+        .arg(
+            Arg::with_name("part")
+                .short("p")
+                .long("part")
+                .takes_value(true)
+                .possible_values(&["1", "2"])
+                .help("Only run variants for this part"),
+        )
+        .arg(
+            Arg::with_name("variant")
+                .short("v")
+                .long("variant")
+                .takes_value(true)
+                .help("Only run the named variant, unnamed variants are called `default`"),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .requires("day")
+                .help("Puzzle input to use instead of input/2020/day<N>.txt"),
+        )
+        .arg(
+            Arg::with_name("check")
+                .short("c")
+                .long("check")
+                .conflicts_with("variant")
+                .help("Run every variant and report any that disagree with the first variant of their part"),
+        )
+        .subcommand(
+            SubCommand::with_name("debug")
+                .about("Step through a day 8 boot program interactively, type `help` for commands")
+                .arg(
+                    Arg::with_name("program")
+                        .takes_value(true)
+                        .help("Boot program to debug instead of input/2020/day8.txt"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Report every invalid number in a day 9 XMAS stream, one number per line")
+                .arg(
+                    Arg::with_name("preamble")
+                        .short("p")
+                        .long("preamble")
+                        .takes_value(true)
+                        .help("How many previous numbers each number is checked against, 25 by default"),
+                )
+                .arg(
+                    Arg::with_name("stream")
+                        .takes_value(true)
+                        .help("File to read the numbers from instead of stdin"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("render")
+                .about("Show every generation of the day 11 seating or day 17 cube simulation, as text or images")
+                .arg(
+                    Arg::with_name("day")
+                        .required(true)
+                        .possible_values(&["11", "17"])
+                        .help("Simulation to render"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .takes_value(true)
+                        .help("Puzzle input to use instead of input/2020/day<N>.txt"),
+                )
+                .arg(
+                    Arg::with_name("part")
+                        .short("p")
+                        .long("part")
+                        .takes_value(true)
+                        .possible_values(&["1", "2"])
+                        .help("Day 11 seating rules to use, part 1 by default"),
+                )
+                .arg(
+                    Arg::with_name("z")
+                        .short("z")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Day 17 layer to slice, 0 by default"),
+                )
+                .arg(
+                    Arg::with_name("w")
+                        .short("w")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Day 17 hyperplane to slice, 0 by default"),
+                )
+                .arg(
+                    Arg::with_name("gif")
+                        .long("gif")
+                        .takes_value(true)
+                        .help("Write an animated GIF here instead of printing the frames"),
+                )
+                .arg(
+                    Arg::with_name("png")
+                        .long("png")
+                        .takes_value(true)
+                        .help("Write one PNG per frame into this directory instead of printing the frames"),
+                )
+                .arg(
+                    Arg::with_name("scale")
+                        .long("scale")
+                        .takes_value(true)
+                        .help("Pixels per cell in images, 8 by default"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("navigate")
+                .about("Log the day 12 ship's course as CSV, or draw it as an SVG")
+                .arg(
+                    Arg::with_name("instructions")
+                        .takes_value(true)
+                        .help("Navigation instructions to follow instead of input/2020/day12.txt"),
+                )
+                .arg(
+                    Arg::with_name("waypoint")
+                        .long("waypoint")
+                        .help("Steer with the waypoint, as in part 2"),
+                )
+                .arg(
+                    Arg::with_name("float")
+                        .long("float")
+                        .help("Navigate with floats so turns don't have to be multiples of 90 degrees"),
+                )
+                .arg(
+                    Arg::with_name("svg")
+                        .long("svg")
+                        .help("Print an SVG of the course instead of CSV"),
+                ),
+        )
+        .get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    if let Some(matches) = matches.subcommand_matches("debug") {
+        return debug_program(matches.value_of("program").unwrap_or("input/2020/day8.txt"));
+    }
+    if let Some(matches) = matches.subcommand_matches("validate") {
+        let preamble = match matches.value_of("preamble") {
+            Some(preamble) => preamble
+                .parse()
+                .map_err(|_| format!("`{}` is not a valid preamble", preamble))?,
+            None => day9::WINDOW_SIZE,
+        };
+        return match matches.value_of("stream") {
+            Some(path) => validate_stream(
+                fs::File::open(path).map_err(|err| format!("unable to read {} : {}", path, err))?,
+                preamble,
+            ),
+            None => validate_stream(io::stdin(), preamble),
+        };
+    }
+    if let Some(matches) = matches.subcommand_matches("render") {
+        return render_simulation(matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("navigate") {
+        let path = matches
+            .value_of("instructions")
+            .unwrap_or("input/2020/day12.txt");
+        return if matches.is_present("float") {
+            log_course::<f64>(path, matches)
+        } else {
+            log_course::<i64>(path, matches)
+        };
+    }
+
+    let part = matches
+        .value_of("part")
+        .and_then(|part| part.parse().ok())
+        .and_then(Part::from_number);
+    let variant = matches.value_of("variant");
+    let check = matches.is_present("check");
+
+    let days = match matches.value_of("day") {
+        Some(day) => {
+            let day = day
+                .parse::<u8>()
+                .map_err(|_| format!("`{}` is not a valid day", day))?;
+            vec![solver::find_day(day).ok_or_else(|| format!("day {} has not been solved", day))?]
+        }
+        None => solver::registry(),
+    };
+
+    let mut failed = false;
+    for day in days {
+        let path = matches
+            .value_of("input")
+            .map(String::from)
+            .unwrap_or_else(|| format!("input/2020/day{}.txt", day.day()));
+
+        let result = if check {
+            check_day(&*day, &path, part)
+        } else {
+            run_day(&*day, &path, part, variant)
+        };
+        if let Err(err) = result {
+            eprintln!("Day {}: {}", day.day(), err);
+            failed = true;
+        }
+    }
+
+    if failed {
+        Err(String::from("not every day ran successfully"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Run every variant of `day` that matches the `part` and `variant` filters against the input at `path`
+fn run_day(
+    day: &dyn Day,
+    path: &str,
+    part: Option<Part>,
+    variant: Option<&str>,
+) -> Result<(), String> {
+    let filter = |variant_part: Part, variant_name: Option<&str>| {
+        part.map_or(true, |part| part == variant_part)
+            && variant.map_or(true, |variant| {
+                variant == variant_name.unwrap_or(DEFAULT_VARIANT)
+            })
+    };
+
+    if !day
+        .variants()
+        .iter()
+        .any(|(variant_part, variant_name)| filter(*variant_part, *variant_name))
+    {
+        let available: Vec<String> = day
+            .variants()
+            .iter()
+            .map(|(part, name)| format!("{} {}", part, name.unwrap_or(DEFAULT_VARIANT)))
+            .collect();
+        return Err(format!(
+            "no variant matches, available variants are: {}",
+            available.join(", ")
+        ));
+    }
+
+    let input = read_input(path)?;
+    let run = day.run(&input, &filter).map_err(|err| err.to_string())?;
+
+    println!("Day {} (generator: {:?})", run.day, run.generator);
+    for answer in run.answers {
+        match answer.variant {
+            Some(name) => println!(
+                "\t{} - {}: {} ({:?})",
+                answer.part, name, answer.value, answer.elapsed
+            ),
+            None => println!("\t{}: {} ({:?})", answer.part, answer.value, answer.elapsed),
+        }
+    }
+
+    Ok(())
+}
+
+/// Run every variant of `day` for `part` against the input at `path`, and fail if the variants of a part don't all give
+/// the same answer
+fn check_day(day: &dyn Day, path: &str, part: Option<Part>) -> Result<(), String> {
+    let input = read_input(path)?;
+    let checks = solver::cross_check(day, &input, part).map_err(|err| err.to_string())?;
+    if checks.is_empty() {
+        return Err(String::from("no variant matches"));
+    }
+
+    println!("Day {}", day.day());
+    let mut disagreements = 0;
+    for check in &checks {
+        if check.answers.len() == 1 {
+            println!("\t{}: only one variant", check.part);
+        } else if check.agrees() {
+            println!(
+                "\t{}: {} variants agree on {}",
+                check.part,
+                check.answers.len(),
+                check.reference().value
+            );
+        } else {
+            println!("\t{}: variants DISAGREE", check.part);
+            disagreements += check.disagreements().count();
+        }
+
+        let fastest = check.fastest().variant;
+        for answer in &check.answers {
+            let mut notes = Vec::new();
+            if answer.value != check.reference().value {
+                notes.push("mismatch");
+            }
+            if check.answers.len() > 1 && answer.variant == fastest {
+                notes.push("fastest");
+            }
+
+            println!(
+                "\t\t{}: {} ({:?}){}",
+                answer.variant.unwrap_or(DEFAULT_VARIANT),
+                answer.value,
+                answer.elapsed,
+                if notes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", notes.join(", "))
+                }
+            );
+        }
+    }
+
+    if disagreements > 0 {
+        Err(format!(
+            "{} variant(s) disagree with the reference variant",
+            disagreements
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Run the day 8 debugger on the boot program at `path`, reading commands from stdin
+fn debug_program(path: &str) -> Result<(), String> {
+    let input = read_input(path)?;
+    let program = day8::input_generator(&input).map_err(|err| err.to_string())?;
+    let mut debugger = day8::Debugger::new(&program);
+
+    let stdin = io::stdin();
+    day8::debugger::repl(&mut debugger, stdin.lock(), io::stdout()).map_err(|err| err.to_string())
+}
+
+/// Print every invalid number in the XMAS stream read from `reader` as it is found
+fn validate_stream<R: Read>(reader: R, preamble: usize) -> Result<(), String> {
+    let mut count = 0;
+    for invalid in day9::invalid_numbers_in(reader, preamble) {
+        println!("{}", invalid.map_err(|err| err.to_string())?);
+        count += 1;
+    }
+    eprintln!("{} invalid number(s)", count);
+
+    Ok(())
+}
+
+/// Build the animation asked for by the `render` subcommand, then print it or write it out as images
+fn render_simulation(matches: &ArgMatches) -> Result<(), String> {
+    fn number<T: std::str::FromStr>(
+        matches: &ArgMatches,
+        name: &str,
+        default: T,
+    ) -> Result<T, String> {
+        match matches.value_of(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("`{}` is not a valid {}", value, name)),
+            None => Ok(default),
+        }
+    }
+
+    let day = matches.value_of("day").unwrap();
+    let path = matches
+        .value_of("input")
+        .map(String::from)
+        .unwrap_or_else(|| format!("input/2020/day{}.txt", day));
+    let input = read_input(&path)?;
+
+    let animation: Animation = if day == "11" {
+        let boat = day11::input_generator(&input).map_err(|err| err.to_string())?;
+        if matches.value_of("part") == Some("2") {
+            render::seating(
+                &boat,
+                &day11::line_of_sight(),
+                day11::VISIBLE_RULE,
+                day11::MAX_ROUNDS,
+            )
+        } else {
+            render::seating(
+                &boat,
+                &automaton::Adjacent,
+                day11::ADJACENT_RULE,
+                day11::MAX_ROUNDS,
+            )
+        }
+    } else {
+        let pocket = day17::input_generator_p2(&input).map_err(|err| err.to_string())?;
+        render::pocket_slices(
+            &pocket,
+            6,
+            number(matches, "z", 0)?,
+            number(matches, "w", 0)?,
+        )
+    };
+
+    let scale = number(matches, "scale", 8)?;
+    if scale == 0 {
+        return Err(String::from("`0` is not a valid scale"));
+    }
+    if let Some(path) = matches.value_of("gif") {
+        animation
+            .write_gif(path, scale, 50)
+            .map_err(|err| format!("unable to write {} : {}", path, err))?;
+        eprintln!("{} frame(s) written to {}", animation.frames.len(), path);
+    }
+    if let Some(dir) = matches.value_of("png") {
+        let paths = animation
+            .write_png_frames(dir, scale)
+            .map_err(|err| format!("unable to write to {} : {}", dir, err))?;
+        eprintln!("{} frame(s) written to {}", paths.len(), dir);
+    }
+    if !matches.is_present("gif") && !matches.is_present("png") {
+        println!("{}", animation.to_ascii());
+    }
+
+    Ok(())
+}
+
+/// Follow the day 12 instructions at `path` and print the ship's track
+fn log_course<T: day12::Scalar>(path: &str, matches: &ArgMatches) -> Result<(), String> {
+    let input = read_input(path)?;
+    let instructions = day12::input_generator(&input).map_err(|err| err.to_string())?;
+    let steering = if matches.is_present("waypoint") {
+        day12::Steering::Waypoint
+    } else {
+        day12::Steering::Ship
+    };
+
+    let mut navigator = day12::Navigator::<T>::new(steering);
+    navigator
+        .run(&instructions)
+        .map_err(|err| err.to_string())?;
+    if matches.is_present("svg") {
+        print!("{}", navigator.track().to_svg());
+    } else {
+        print!("{}", navigator.track().to_csv());
+    }
+    eprintln!("{} from the start", navigator.distance());
+
+    Ok(())
+}
+
+/// Read the puzzle input at `path`
+fn read_input(path: &str) -> Result<String, String> {
+    let input =
+        fs::read_to_string(path).map_err(|err| format!("unable to read {} : {}", path, err))?;
+
+    // cargo-aoc strips the trailing newline before handing input to generators, and they all rely on that
+    Ok(input.trim_end_matches('\n').to_string())
+}
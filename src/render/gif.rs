@@ -0,0 +1,169 @@
+use fnv::FnvHashMap;
+use std::io;
+
+use super::{Frame, Swatch};
+
+/// Codes in GIF's LZW never get wider than this
+const MAX_CODE_WIDTH: u32 = 12;
+
+/// Encode `frames` as a looping animated GIF, showing each frame for `delay` hundredths of a second. The screen is as
+/// large as the largest frame, and every frame is drawn from its top left corner. GIF sizes are 16 bit, so a frame
+/// wider or taller than 65535 is an error.
+pub fn encode(frames: &[Frame], palette: &[Swatch], delay: u16) -> io::Result<Vec<u8>> {
+    assert!(
+        !palette.is_empty() && palette.len() <= 256,
+        "A GIF palette holds 1 to 256 colors"
+    );
+
+    let width = dimension(frames.iter().map(Frame::width).max().unwrap_or(0))?;
+    let height = dimension(frames.iter().map(Frame::height).max().unwrap_or(0))?;
+    // The color table holds a power of two colors, at least 2
+    let depth = (1u8..=8)
+        .find(|bits| 1usize << bits >= palette.len())
+        .unwrap();
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    // A global color table of 2^depth colors, sorted no, background color 0, square pixels
+    gif.extend_from_slice(&[0x80 | (depth - 1) << 4 | (depth - 1), 0, 0]);
+    for idx in 0..1usize << depth {
+        gif.extend_from_slice(&palette.get(idx).map_or([0; 3], |swatch| swatch.color));
+    }
+
+    // Loop forever
+    gif.extend_from_slice(&[0x21, 0xff, 11]);
+    gif.extend_from_slice(b"NETSCAPE2.0");
+    gif.extend_from_slice(&[3, 1, 0, 0, 0]);
+
+    for frame in frames {
+        // Graphic control: leave the frame in place, then wait `delay`
+        gif.extend_from_slice(&[0x21, 0xf9, 4, 0x04]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[0, 0]);
+
+        // Image descriptor at (0, 0), using the global color table, no frame is larger than the screen so it fits
+        gif.push(0x2c);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&(frame.width() as u16).to_le_bytes());
+        gif.extend_from_slice(&(frame.height() as u16).to_le_bytes());
+        gif.push(0);
+
+        // Code widths start at one more than the minimum code size, which GIF wants to be at least 2
+        let min_code_size = depth.max(2);
+        gif.push(min_code_size);
+        for block in lzw(frame.pixels(), min_code_size as u32).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3b);
+    Ok(gif)
+}
+
+/// A width or height as the 16 bits GIF stores it in
+fn dimension(size: usize) -> io::Result<u16> {
+    if size > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("a GIF is at most 65535 pixels across, not {}", size),
+        ));
+    }
+    Ok(size as u16)
+}
+
+/// Writes codes of varying width, least significant bit first
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u32, width: u32) {
+        self.buffer |= code << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF flavored LZW. The decoder adds each dictionary entry one code later than the encoder, so the width only grows
+/// once the code after the newest entry no longer fits, and a full dictionary is cleared rather than frozen.
+pub(crate) fn lzw(pixels: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1u32 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+
+    let mut table: FnvHashMap<(u32, u8), u32> = FnvHashMap::default();
+    let mut width = min_code_size + 1;
+    // The newest code handed out, and the first code that doesn't fit in `width` bits
+    let mut newest = end;
+    let mut overflow = clear << 1;
+
+    writer.write(clear, width);
+    let mut pixels = pixels.iter();
+    let mut code = match pixels.next() {
+        Some(pixel) => *pixel as u32,
+        None => {
+            writer.write(end, width);
+            return writer.finish();
+        }
+    };
+
+    // Count the new code and grow the width to fit it, returns `false` if the dictionary had to be cleared
+    let mut next_code = |writer: &mut BitWriter, width: &mut u32, newest: &mut u32| {
+        *newest += 1;
+        if *newest == overflow {
+            *width += 1;
+            overflow <<= 1;
+        }
+        if *newest == (1 << MAX_CODE_WIDTH) - 1 {
+            writer.write(clear, *width);
+            *width = min_code_size + 1;
+            *newest = end;
+            overflow = clear << 1;
+            false
+        } else {
+            true
+        }
+    };
+
+    for pixel in pixels {
+        if let Some(known) = table.get(&(code, *pixel)) {
+            code = *known;
+            continue;
+        }
+
+        writer.write(code, width);
+        if next_code(&mut writer, &mut width, &mut newest) {
+            table.insert((code, *pixel), newest);
+        } else {
+            table.clear();
+        }
+        code = *pixel as u32;
+    }
+
+    writer.write(code, width);
+    if !next_code(&mut writer, &mut width, &mut newest) {
+        table.clear();
+    }
+    writer.write(end, width);
+    writer.finish()
+}
@@ -0,0 +1,514 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::automaton::{Grid, Neighborhood};
+use crate::day11::{Boat, Seat, SeatingRule};
+use crate::day17::PocketDimension;
+
+pub mod gif;
+pub mod png;
+
+/// How one value in a frame is drawn, as a character in the terminal and a color in images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swatch {
+    pub glyph: char,
+    pub color: [u8; 3],
+}
+
+/// Day 11, indexed by `seat_index`: floor, empty seats, occupied seats and the gaps past short rows
+pub const SEATING_PALETTE: [Swatch; 4] = [
+    Swatch {
+        glyph: '.',
+        color: [0x3a, 0x32, 0x2c],
+    },
+    Swatch {
+        glyph: 'L',
+        color: [0x4f, 0x9d, 0x69],
+    },
+    Swatch {
+        glyph: '#',
+        color: [0xd6, 0x4f, 0x3c],
+    },
+    Swatch {
+        glyph: ' ',
+        color: [0x00, 0x00, 0x00],
+    },
+];
+
+/// Day 17: inactive then active cubes
+pub const CUBE_PALETTE: [Swatch; 2] = [
+    Swatch {
+        glyph: '.',
+        color: [0x10, 0x14, 0x24],
+    },
+    Swatch {
+        glyph: '#',
+        color: [0x7f, 0xdb, 0xff],
+    },
+];
+
+/// One picture, each pixel an index into a palette of `Swatch`es
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Shown above the frame in the terminal, like the puzzle's own `z=0, w=0`
+    pub label: String,
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// A `width` by `height` frame filled with palette index 0
+    pub fn new(label: String, width: usize, height: usize) -> Self {
+        Frame {
+            label,
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Every pixel, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: u8) {
+        self.pixels[y * self.width + x] = value;
+    }
+
+    /// The frame blown up so each pixel becomes a `scale` by `scale` square, small grids are hard to see otherwise
+    pub fn scaled(&self, scale: usize) -> Frame {
+        let mut scaled = Frame::new(self.label.clone(), self.width * scale, self.height * scale);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.set(x, y, self.get(x / scale, y / scale));
+            }
+        }
+        scaled
+    }
+
+    /// The label, then one line of glyphs per row
+    pub fn to_ascii(&self, palette: &[Swatch]) -> String {
+        let mut ascii = self.label.clone();
+        ascii.push('\n');
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            ascii.extend(row.iter().map(|pixel| palette[*pixel as usize].glyph));
+            ascii.push('\n');
+        }
+        ascii
+    }
+}
+
+/// Frames that are drawn with the same palette, one after the other
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub palette: Vec<Swatch>,
+    pub frames: Vec<Frame>,
+}
+
+impl Animation {
+    /// Every frame as text, separated by blank lines
+    pub fn to_ascii(&self) -> String {
+        self.frames
+            .iter()
+            .map(|frame| frame.to_ascii(&self.palette))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// A looping GIF of every frame scaled up by `scale`, each shown for `delay` hundredths of a second. Fails if the
+    /// scaled frames are too large for a GIF.
+    pub fn to_gif(&self, scale: usize, delay: u16) -> io::Result<Vec<u8>> {
+        let frames: Vec<Frame> = self
+            .frames
+            .iter()
+            .map(|frame| frame.scaled(scale))
+            .collect();
+        gif::encode(&frames, &self.palette, delay)
+    }
+
+    pub fn write_gif<P: AsRef<Path>>(&self, path: P, scale: usize, delay: u16) -> io::Result<()> {
+        fs::write(path, self.to_gif(scale, delay)?)
+    }
+
+    /// Write every frame scaled up by `scale` to its own PNG in `dir`, named `frame-000.png` onwards, and return the
+    /// paths written
+    pub fn write_png_frames<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        scale: usize,
+    ) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(&dir)?;
+        let mut paths = Vec::with_capacity(self.frames.len());
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let path = dir.as_ref().join(format!("frame-{:03}.png", idx));
+            fs::write(&path, png::encode(&frame.scaled(scale), &self.palette))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Index of `seat` in `SEATING_PALETTE`
+pub fn seat_index(seat: Seat) -> u8 {
+    match seat {
+        Seat::Floor => 0,
+        Seat::Empty => 1,
+        Seat::Occupied => 2,
+        Seat::Outside => 3,
+    }
+}
+
+/// Frame showing every seat in `boat`
+pub fn boat_frame(boat: &Boat, label: String) -> Frame {
+    seats_frame(boat.seats(), label)
+}
+
+fn seats_frame(seats: &Grid<Seat>, label: String) -> Frame {
+    Frame {
+        label,
+        width: seats.width(),
+        height: seats.height(),
+        pixels: seats.cells().iter().map(|seat| seat_index(*seat)).collect(),
+    }
+}
+
+/// Every round of the seating simulation, from the starting layout to the first round that changes nothing, stopping
+/// early after `max_rounds` rounds
+pub fn seating<N: Neighborhood<Seat>>(
+    boat: &Boat,
+    neighborhood: &N,
+    rule: SeatingRule,
+    max_rounds: usize,
+) -> Animation {
+    let mut automaton = boat.automaton(neighborhood, rule);
+    let label = |round: usize, occupied: usize| format!("round {}: {} occupied", round, occupied);
+    let mut frames = vec![boat_frame(boat, label(0, boat.count_occupied_seats()))];
+
+    for _ in 0..max_rounds {
+        let changes = automaton.step();
+        let seats = automaton.grid();
+        let occupied = seats.count(Seat::is_occupied);
+        frames.push(seats_frame(seats, label(automaton.generation(), occupied)));
+        if changes == 0 {
+            break;
+        }
+    }
+
+    Animation {
+        palette: SEATING_PALETTE.to_vec(),
+        frames,
+    }
+}
+
+/// Smallest and largest `(x, y)` of the active cubes, or `None` if there are none
+fn xy_bounds(cubes: &[(i32, i32, i32, i32)]) -> Option<((i32, i32), (i32, i32))> {
+    let first = cubes.first()?;
+    Some(cubes.iter().fold(
+        ((first.0, first.1), (first.0, first.1)),
+        |((min_x, min_y), (max_x, max_y)), (x, y, _, _)| {
+            (
+                (min_x.min(*x), min_y.min(*y)),
+                (max_x.max(*x), max_y.max(*y)),
+            )
+        },
+    ))
+}
+
+/// The `z`/`w` slice of `cubes` covering `bounds`
+fn slice(
+    cubes: &[(i32, i32, i32, i32)],
+    bounds: ((i32, i32), (i32, i32)),
+    z: i32,
+    w: i32,
+    label: String,
+) -> Frame {
+    let ((min_x, min_y), (max_x, max_y)) = bounds;
+    let mut frame = Frame::new(
+        label,
+        (max_x - min_x + 1) as usize,
+        (max_y - min_y + 1) as usize,
+    );
+    for (x, y, _, _) in cubes.iter().filter(|cube| cube.2 == z && cube.3 == w) {
+        frame.set((x - min_x) as usize, (y - min_y) as usize, 1);
+    }
+    frame
+}
+
+/// Every z/w layer of the current generation that has an active cube in it, like the puzzle's own walkthrough. All
+/// the layers cover the same area, just large enough for every active cube.
+pub fn pocket_layers(pocket: &PocketDimension) -> Animation {
+    let cubes = pocket.active_cubes();
+    let mut layers: Vec<(i32, i32)> = cubes.iter().map(|(_, _, z, w)| (*w, *z)).collect();
+    layers.sort_unstable();
+    layers.dedup();
+
+    let frames = match xy_bounds(&cubes) {
+        Some(bounds) => layers
+            .into_iter()
+            .map(|(w, z)| slice(&cubes, bounds, z, w, format!("z={}, w={}", z, w)))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Animation {
+        palette: CUBE_PALETTE.to_vec(),
+        frames,
+    }
+}
+
+/// The `z`/`w` slice through `generations` cycles of the simulation, starting with the current one. Every frame covers
+/// the same area, large enough for every active cube in any of the cycles.
+pub fn pocket_slices(pocket: &PocketDimension, generations: usize, z: i32, w: i32) -> Animation {
+    let mut pocket = pocket.clone();
+    let mut snapshots = vec![pocket.active_cubes()];
+    for _ in 0..generations {
+        pocket.step();
+        snapshots.push(pocket.active_cubes());
+    }
+
+    let all: Vec<(i32, i32, i32, i32)> = snapshots.iter().flatten().copied().collect();
+    let frames = match xy_bounds(&all) {
+        Some(bounds) => snapshots
+            .iter()
+            .enumerate()
+            .map(|(cycle, cubes)| {
+                let label = format!("cycle {}, z={}, w={}", cycle, z, w);
+                slice(cubes, bounds, z, w, label)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Animation {
+        palette: CUBE_PALETTE.to_vec(),
+        frames,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Adjacent;
+    use crate::day11::{self, ADJACENT_RULE};
+    use crate::day17;
+
+    const SEATS: &str = "L.LL.LL.LL
+LLLLLLL.LL
+L.L.L..L..
+LLLL.LL.LL
+L.LL.LL.LL
+L.LLLLL.LL
+..L.L.....
+LLLLLLLLLL
+L.LLLLLL.L
+L.LLLLL.LL";
+
+    /// Reverse of `gif::lzw`, written the way a decoder would be so the two can't share a mistake
+    fn unlzw(bytes: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = min_code_size + 1;
+        let mut previous: Option<usize> = None;
+        let mut pixels = Vec::new();
+
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u32, bytes.iter());
+        loop {
+            while bits < width {
+                buffer |= (*bytes.next().expect("Missing end code") as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as usize;
+            buffer >>= width;
+            bits -= width;
+
+            if code == clear {
+                table = (0..clear).map(|value| vec![value as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                width = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return pixels;
+            }
+
+            let entry = match (previous, table.get(code)) {
+                (_, Some(entry)) => entry.clone(),
+                (Some(previous), None) => {
+                    let mut entry = table[previous].clone();
+                    entry.push(entry[0]);
+                    entry
+                }
+                (None, None) => panic!("Code {} before any dictionary entry", code),
+            };
+            if let Some(previous) = previous {
+                let mut added = table[previous].clone();
+                added.push(entry[0]);
+                table.push(added);
+                if table.len() == 1 << width && width < 12 {
+                    width += 1;
+                }
+            }
+            pixels.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn seating_frames() {
+        let boat = day11::input_generator(SEATS).unwrap();
+        let animation = seating(&boat, &Adjacent, ADJACENT_RULE, 100);
+        // The starting layout, 5 rounds of changes and the round that changes nothing
+        assert_eq!(animation.frames.len(), 7);
+        assert_eq!(
+            animation.frames[1].to_ascii(&animation.palette),
+            "round 1: 71 occupied
+#.##.##.##
+#######.##
+#.#.#..#..
+####.##.##
+#.##.##.##
+#.#####.##
+..#.#.....
+##########
+#.######.#
+#.#####.##
+"
+        );
+        assert_eq!(animation.frames[6].label, "round 6: 37 occupied");
+        assert!(animation
+            .to_ascii()
+            .starts_with("round 0: 0 occupied\nL.LL.LL.LL\n"));
+        assert!(animation.to_ascii().contains("L.LL\n\nround 1"));
+
+        // Gaps past short rows are blank
+        let ragged = day11::input_generator("L.L\nL").unwrap();
+        assert_eq!(
+            boat_frame(&ragged, String::from("ragged")).to_ascii(&SEATING_PALETTE),
+            "ragged\nL.L\nL  \n"
+        );
+    }
+
+    #[test]
+    fn pocket_frames() {
+        let pocket = day17::input_generator_p2(".#.\n..#\n###").unwrap();
+        assert_eq!(
+            pocket_layers(&pocket).to_ascii(),
+            "z=0, w=0\n.#.\n..#\n###\n"
+        );
+
+        let mut stepped = pocket.clone();
+        stepped.step();
+        let layers = pocket_layers(&stepped);
+        assert_eq!(layers.frames.len(), 9);
+        assert_eq!(
+            layers.frames[0].to_ascii(&CUBE_PALETTE),
+            "z=-1, w=-1\n#..\n..#\n.#.\n"
+        );
+        assert_eq!(layers.frames[4].label, "z=0, w=0");
+        assert_eq!(
+            layers.frames[4].to_ascii(&CUBE_PALETTE),
+            "z=0, w=0\n#.#\n.##\n.#.\n"
+        );
+
+        // Every cycle is drawn over the same area
+        let slices = pocket_slices(&pocket, 2, 0, 0);
+        assert_eq!(slices.frames.len(), 3);
+        assert!(slices
+            .frames
+            .iter()
+            .all(|frame| (frame.width(), frame.height())
+                == (slices.frames[2].width(), slices.frames[2].height())));
+        assert_eq!(slices.frames[0].label, "cycle 0, z=0, w=0");
+    }
+
+    #[test]
+    fn png_encoding() {
+        let mut frame = Frame::new(String::new(), 3, 2);
+        frame.set(1, 0, 1);
+        frame.set(2, 1, 1);
+        let png = png::encode(&frame.scaled(2), &CUBE_PALETTE);
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 6, 0, 0, 0, 4]);
+        // Every chunk checks out, the image ends with the standard IEND
+        let mut pos = 8;
+        let mut data = Vec::new();
+        while pos < png.len() {
+            let len =
+                u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]) as usize;
+            let chunk = &png[pos + 4..pos + 8 + len];
+            let crc = &png[pos + 8 + len..pos + 12 + len];
+            assert_eq!(crc, &png::crc32(chunk).to_be_bytes());
+            if &chunk[..4] == b"IDAT" {
+                data.extend_from_slice(&chunk[4..]);
+            }
+            pos += 12 + len;
+        }
+        assert_eq!(
+            &png[png.len() - 8..],
+            &[b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+
+        // One stored block holding a filter byte and 6 pixels per row
+        assert_eq!(&data[..3], &[0x78, 0x01, 1]);
+        let raw = &data[7..data.len() - 4];
+        assert_eq!(raw.len(), 4 * 7);
+        assert_eq!(&raw[..7], &[0, 0, 0, 1, 1, 0, 0]);
+        assert_eq!(&raw[21..], &[0, 0, 0, 0, 0, 1, 1]);
+        assert_eq!(&data[data.len() - 4..], &png::adler32(raw).to_be_bytes());
+        assert_eq!(png::adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn gif_encoding() {
+        let pocket = day17::input_generator_p2(".#.\n..#\n###").unwrap();
+        let animation = pocket_slices(&pocket, 3, 0, 0);
+        let gif = animation.to_gif(1, 50).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif[gif.len() - 1], 0x3b);
+        assert_eq!(
+            gif.windows(11)
+                .filter(|bytes| *bytes == b"NETSCAPE2.0")
+                .count(),
+            1
+        );
+
+        // LZW round trips through the dictionary growing, and being cleared when it fills up
+        let pixels: Vec<u8> = (0..20_000u32)
+            .map(|n| ((n * n / 7 + n / 13) % 4) as u8)
+            .collect();
+        assert_eq!(unlzw(&gif::lzw(&pixels, 2), 2), pixels);
+        let noise: Vec<u8> = (0..50_000u32)
+            .map(|n| (n.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+        assert_eq!(unlzw(&gif::lzw(&noise, 8), 8), noise);
+        assert_eq!(unlzw(&gif::lzw(&[], 2), 2), Vec::<u8>::new());
+        assert_eq!(unlzw(&gif::lzw(&[3], 2), 2), vec![3]);
+
+        // GIF can't describe frames past 65535 pixels across
+        let wide = Frame::new(String::new(), 65_536, 1);
+        let err = gif::encode(&[wide], &CUBE_PALETTE, 50).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let widest = Frame::new(String::new(), 65_535, 1);
+        assert!(gif::encode(&[widest], &CUBE_PALETTE, 50).is_ok());
+    }
+}
@@ -0,0 +1,90 @@
+use super::{Frame, Swatch};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Largest block of data a stored deflate block can hold
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+/// Encode `frame` as an indexed-color PNG. The image data is wrapped in stored (uncompressed) deflate blocks, which
+/// every PNG reader understands and which keeps this encoder to a page. Frames are small enough that the lost
+/// compression doesn't matter.
+pub fn encode(frame: &Frame, palette: &[Swatch]) -> Vec<u8> {
+    assert!(
+        !palette.is_empty() && palette.len() <= 256,
+        "A PNG palette holds 1 to 256 colors"
+    );
+
+    let mut png = SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(frame.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(frame.height() as u32).to_be_bytes());
+    // 8 bits per pixel, indexed color, deflate, adaptive filtering, no interlacing
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    let colors: Vec<u8> = palette.iter().flat_map(|swatch| swatch.color).collect();
+    write_chunk(&mut png, b"PLTE", &colors);
+
+    // Each row starts with its filter type, 0 for none
+    let mut raw = Vec::with_capacity((frame.width() + 1) * frame.height());
+    for row in frame.pixels().chunks(frame.width().max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// `data` as a zlib stream of stored deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary, the check bits make the header a multiple of 31
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
@@ -0,0 +1,385 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use crate::ParseError;
+
+/// Which half of a day's puzzle a solution answers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    /// Convert the puzzle's part number (1 or 2) into a `Part`
+    pub fn from_number(part: u8) -> Option<Self> {
+        match part {
+            1 => Some(Part::One),
+            2 => Some(Part::Two),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::One => write!(f, "Part 1"),
+            Part::Two => write!(f, "Part 2"),
+        }
+    }
+}
+
+/// A single solution function for one part of a day. Most days have one per part, but some have several competing
+/// implementations of the same part that we want to keep around for comparison.
+pub struct Variant<I> {
+    /// Part of the puzzle this variant answers
+    pub part: Part,
+    /// Name of the variant, `None` is the default solution, the same as an unnamed `#[aoc(dayN, partN)]`
+    pub name: Option<&'static str>,
+    /// Function that solves the puzzle from the parsed input
+    pub solve: fn(&I) -> String,
+}
+
+impl<I> Variant<I> {
+    pub fn new(part: Part, name: Option<&'static str>, solve: fn(&I) -> String) -> Self {
+        Variant { part, name, solve }
+    }
+}
+
+/// Implemented once per day, ties the day's `input_generator` to all of its solution variants. Unlike cargo-aoc this
+/// doesn't restrict what the parsed input looks like, so generators are free to hand back any type they like.
+pub trait Solver {
+    /// Parsed form of the puzzle input that every variant works from
+    type Input;
+
+    /// Day of the advent calendar this solves
+    const DAY: u8;
+
+    /// Parse the raw puzzle input
+    fn generate(input: &str) -> Result<Self::Input, ParseError>;
+
+    /// Every solution variant for this day, in the order they should be run
+    fn variants() -> Vec<Variant<Self::Input>>;
+}
+
+/// The answer from running a single variant
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub part: Part,
+    pub variant: Option<&'static str>,
+    pub value: String,
+    /// Time spent in the variant, excluding input parsing
+    pub elapsed: Duration,
+}
+
+/// Everything produced by running some or all of a day's variants against one input
+#[derive(Debug, Clone)]
+pub struct DayRun {
+    pub day: u8,
+    /// Time spent parsing the input, the input is only parsed once per run
+    pub generator: Duration,
+    pub answers: Vec<Answer>,
+}
+
+impl DayRun {
+    /// Group the answers by part so the variants of each part can be compared against each other
+    pub fn cross_check(&self) -> Vec<CrossCheck> {
+        let mut checks: Vec<CrossCheck> = Vec::new();
+        for answer in &self.answers {
+            match checks.iter_mut().find(|check| check.part == answer.part) {
+                Some(check) => check.answers.push(answer.clone()),
+                None => checks.push(CrossCheck {
+                    day: self.day,
+                    part: answer.part,
+                    answers: vec![answer.clone()],
+                }),
+            }
+        }
+
+        checks.sort_by_key(|check| check.part);
+        checks
+    }
+}
+
+/// Every variant's answer for one part of a day, all produced from the same input. The first registered variant is
+/// the reference that the others are compared against, so a new variant can be checked against an existing one just
+/// by registering it after it.
+#[derive(Debug, Clone)]
+pub struct CrossCheck {
+    pub day: u8,
+    pub part: Part,
+    /// Answers in registration order, never empty
+    pub answers: Vec<Answer>,
+}
+
+impl CrossCheck {
+    /// The answer every other variant is expected to match
+    pub fn reference(&self) -> &Answer {
+        &self.answers[0]
+    }
+
+    /// Every answer that doesn't match the reference
+    pub fn disagreements(&self) -> impl Iterator<Item = &Answer> {
+        let reference = &self.reference().value;
+        self.answers
+            .iter()
+            .filter(move |answer| answer.value != *reference)
+    }
+
+    /// True if every variant gave the same answer
+    pub fn agrees(&self) -> bool {
+        self.disagreements().next().is_none()
+    }
+
+    /// The variant that took the least time
+    pub fn fastest(&self) -> &Answer {
+        self.answers
+            .iter()
+            .min_by_key(|answer| answer.elapsed)
+            .unwrap()
+    }
+}
+
+/// Run every variant of `day`, optionally only those for `part`, against `input` and compare the answers of each part
+pub fn cross_check(
+    day: &dyn Day,
+    input: &str,
+    part: Option<Part>,
+) -> Result<Vec<CrossCheck>, ParseError> {
+    let run = day.run(input, &|variant_part, _| {
+        part.map_or(true, |part| part == variant_part)
+    })?;
+
+    Ok(run.cross_check())
+}
+
+/// Object safe view of a `Solver`, this is what the registry hands out so days with different input types can live
+/// side by side.
+pub trait Day: Send + Sync {
+    /// Day of the advent calendar this solves
+    fn day(&self) -> u8;
+
+    /// The part and name of every registered variant
+    fn variants(&self) -> Vec<(Part, Option<&'static str>)>;
+
+    /// Parse `input` once and run every variant that `filter` accepts against it
+    fn run(
+        &self,
+        input: &str,
+        filter: &dyn Fn(Part, Option<&str>) -> bool,
+    ) -> Result<DayRun, ParseError>;
+}
+
+/// Adapter that turns any `Solver` into a `Day`
+pub struct Registered<S>(PhantomData<fn() -> S>);
+
+impl<S> Registered<S> {
+    pub fn new() -> Self {
+        Registered(PhantomData)
+    }
+}
+
+impl<S> Default for Registered<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Solver> Day for Registered<S> {
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn variants(&self) -> Vec<(Part, Option<&'static str>)> {
+        S::variants()
+            .iter()
+            .map(|variant| (variant.part, variant.name))
+            .collect()
+    }
+
+    fn run(
+        &self,
+        input: &str,
+        filter: &dyn Fn(Part, Option<&str>) -> bool,
+    ) -> Result<DayRun, ParseError> {
+        let start = Instant::now();
+        let parsed = S::generate(input)?;
+        let generator = start.elapsed();
+
+        let answers = S::variants()
+            .into_iter()
+            .filter(|variant| filter(variant.part, variant.name))
+            .map(|variant| {
+                let start = Instant::now();
+                let value = (variant.solve)(&parsed);
+                Answer {
+                    part: variant.part,
+                    variant: variant.name,
+                    value,
+                    elapsed: start.elapsed(),
+                }
+            })
+            .collect();
+
+        Ok(DayRun {
+            day: S::DAY,
+            generator,
+            answers,
+        })
+    }
+}
+
+/// Every day that has been solved, ordered by day
+pub fn registry() -> Vec<Box<dyn Day>> {
+    use crate::*;
+
+    vec![
+        Box::new(Registered::<day1::Solution>::new()),
+        Box::new(Registered::<day2::Solution>::new()),
+        Box::new(Registered::<day3::Solution>::new()),
+        Box::new(Registered::<day4::Solution>::new()),
+        Box::new(Registered::<day5::Solution>::new()),
+        Box::new(Registered::<day6::Solution>::new()),
+        Box::new(Registered::<day7::Solution>::new()),
+        Box::new(Registered::<day8::Solution>::new()),
+        Box::new(Registered::<day9::Solution>::new()),
+        Box::new(Registered::<day10::Solution>::new()),
+        Box::new(Registered::<day11::Solution>::new()),
+        Box::new(Registered::<day12::Solution>::new()),
+        Box::new(Registered::<day13::Solution>::new()),
+        Box::new(Registered::<day14::Solution>::new()),
+        Box::new(Registered::<day15::Solution>::new()),
+        Box::new(Registered::<day16::Solution>::new()),
+        Box::new(Registered::<day17::Solution>::new()),
+        Box::new(Registered::<day18::Solution>::new()),
+        Box::new(Registered::<day19::Solution>::new()),
+        Box::new(Registered::<day20::Solution>::new()),
+        Box::new(Registered::<day21::Solution>::new()),
+        Box::new(Registered::<day22::Solution>::new()),
+        Box::new(Registered::<day23::Solution>::new()),
+        Box::new(Registered::<day24::Solution>::new()),
+        Box::new(Registered::<day25::Solution>::new()),
+    ]
+}
+
+/// Look up a single day in the registry
+pub fn find_day(day: u8) -> Option<Box<dyn Day>> {
+    registry().into_iter().find(|solver| solver.day() == day)
+}
+
+/// Run every `part` variant of `S` against `input` and check that they all produce `expected`
+#[cfg(test)]
+pub(crate) fn assert_variants<S: Solver>(input: &str, part: Part, expected: &str) {
+    let parsed = S::generate(input).expect("Example input should always parse");
+    let variants: Vec<Variant<S::Input>> = S::variants()
+        .into_iter()
+        .filter(|variant| variant.part == part)
+        .collect();
+    assert!(
+        !variants.is_empty(),
+        "Day {} has no {} variants",
+        S::DAY,
+        part
+    );
+
+    for variant in variants {
+        assert_eq!(
+            (variant.solve)(&parsed),
+            expected,
+            "Day {} - {} - {} gave the wrong answer",
+            S::DAY,
+            part,
+            variant.name.unwrap_or("(default)")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Day with one part whose variants agree and one part whose variants don't
+    struct Disagreeable;
+
+    impl Solver for Disagreeable {
+        type Input = u32;
+
+        const DAY: u8 = 0;
+
+        fn generate(input: &str) -> Result<Self::Input, ParseError> {
+            Ok(input.len() as u32)
+        }
+
+        fn variants() -> Vec<Variant<Self::Input>> {
+            vec![
+                Variant::new(Part::Two, None, |input| (input * 2).to_string()),
+                Variant::new(Part::One, None, |input| input.to_string()),
+                Variant::new(Part::One, Some("same"), |input| input.to_string()),
+                Variant::new(Part::Two, Some("off_by_one"), |input| {
+                    (input * 2 + 1).to_string()
+                }),
+                Variant::new(Part::Two, Some("doubled"), |input| {
+                    (input + input).to_string()
+                }),
+            ]
+        }
+    }
+
+    #[test]
+    fn cross_check_finds_disagreements() {
+        let day = Registered::<Disagreeable>::new();
+        let checks = cross_check(&day, "abc", None).unwrap();
+
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].part, Part::One);
+        assert_eq!(checks[0].answers.len(), 2);
+        assert!(checks[0].agrees());
+
+        assert_eq!(checks[1].part, Part::Two);
+        assert_eq!(checks[1].reference().value, "6");
+        let disagreements: Vec<Option<&str>> = checks[1]
+            .disagreements()
+            .map(|answer| answer.variant)
+            .collect();
+        assert_eq!(disagreements, vec![Some("off_by_one")]);
+    }
+
+    #[test]
+    fn cross_check_filters_by_part() {
+        let day = Registered::<Disagreeable>::new();
+        let checks = cross_check(&day, "abc", Some(Part::One)).unwrap();
+
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].part, Part::One);
+    }
+
+    #[test]
+    fn registry_is_ordered_and_unique() {
+        let days: Vec<u8> = registry().iter().map(|day| day.day()).collect();
+        let expected: Vec<u8> = (1..=25).collect();
+        assert_eq!(days, expected);
+    }
+
+    #[test]
+    fn run_only_runs_matching_variants() {
+        let day = find_day(1).unwrap();
+        let run = day
+            .run("1721\n979\n366\n299\n675\n1456", &|part, name| {
+                part == Part::One && name == Some("find")
+            })
+            .unwrap();
+
+        assert_eq!(run.answers.len(), 1);
+        assert_eq!(run.answers[0].value, "514579");
+    }
+
+    #[test]
+    fn run_reports_parse_errors() {
+        let day = find_day(1).unwrap();
+        let err = day.run("1721\nabc", &|_, _| true).unwrap_err();
+
+        assert_eq!((err.day, err.line, err.column), (1, 2, 1));
+    }
+}
@@ -0,0 +1,126 @@
+//! Runs every registered variant against the checked in puzzle inputs and checks the answers haven't changed
+
+use std::fs;
+
+use advent_of_code_2020::solver::{self, Part};
+
+/// Run every variant of `day` against its input file and check each one against the expected answer for its part
+fn check_day(day: u8, part1: Option<&str>, part2: Option<&str>) {
+    let path = format!("input/2020/day{}.txt", day);
+    let input =
+        fs::read_to_string(&path).unwrap_or_else(|err| panic!("Unable to read {}: {}", path, err));
+    let input = input.trim_end_matches('\n');
+
+    let run = solver::find_day(day)
+        .unwrap_or_else(|| panic!("Day {} is not registered", day))
+        .run(input, &|_, _| true)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    assert!(!run.answers.is_empty(), "Day {} has no variants", day);
+    for answer in run.answers {
+        let expected = match answer.part {
+            Part::One => part1,
+            Part::Two => part2,
+        };
+        if let Some(expected) = expected {
+            assert_eq!(
+                answer.value,
+                expected,
+                "Day {} - {} - {} gave the wrong answer",
+                day,
+                answer.part,
+                answer.variant.unwrap_or("(default)")
+            );
+        }
+    }
+}
+
+#[test]
+fn day1() {
+    check_day(1, Some("538464"), Some("278783190"));
+}
+
+#[test]
+fn day2() {
+    check_day(2, Some("620"), Some("727"));
+}
+
+#[test]
+fn day3() {
+    check_day(3, Some("276"), Some("7812180000"));
+}
+
+#[test]
+fn day4() {
+    check_day(4, Some("170"), Some("103"));
+}
+
+#[test]
+fn day5() {
+    check_day(5, Some("922"), Some("747"));
+}
+
+#[test]
+fn day6() {
+    check_day(6, Some("6596"), Some("3219"));
+}
+
+#[test]
+fn day7() {
+    check_day(7, Some("192"), Some("12128"));
+}
+
+#[test]
+fn day8() {
+    check_day(8, Some("1420"), Some("1245"));
+}
+
+#[test]
+fn day9() {
+    check_day(9, Some("1492208709"), Some("238243506"));
+}
+
+#[test]
+fn day10() {
+    check_day(10, Some("2030"), Some("42313823813632"));
+}
+
+#[test]
+fn day11() {
+    check_day(11, Some("2316"), Some("2128"));
+}
+
+#[test]
+fn day12() {
+    check_day(12, Some("1603"), Some("52866"));
+}
+
+#[test]
+fn day13() {
+    check_day(13, Some("3269"), Some("672754131923874"));
+}
+
+#[test]
+fn day14() {
+    check_day(14, Some("8332632930672"), Some("4753238784664"));
+}
+
+#[test]
+fn day15() {
+    check_day(15, Some("1294"), Some("573522"));
+}
+
+#[test]
+fn day16() {
+    check_day(16, Some("23954"), Some("453459307723"));
+}
+
+#[test]
+fn day17() {
+    check_day(17, None, Some("1492"));
+}
+
+#[test]
+fn day18() {
+    check_day(18, Some("12956356593940"), Some("94240043727614"));
+}